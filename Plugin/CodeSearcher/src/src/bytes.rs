@@ -0,0 +1,148 @@
+// mode=bytes：按十六进制给出的字节序列搜索任意文件 (包括二进制文件)，用于查找嵌入了某段魔数或特定字节的资源文件。
+// 结果报告字节偏移和匹配前后各 CONTEXT_BYTES 字节的十六进制转储，没有行号、列号和上下文行
+
+use serde::Serialize;
+
+use crate::SearchResult;
+
+/// context_hex 在匹配前后各包含的字节数
+pub const CONTEXT_BYTES: usize = 16;
+
+/// 解析十六进制查询，忽略空白 (允许 "89 50 4E 47" 这样分组书写)，大小写不限
+pub fn parse_hex(query: &str) -> Result<Vec<u8>, String> {
+    let digits: Vec<u8> = query.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+    if digits.is_empty() {
+        return Err("Invalid hex query: empty".to_string());
+    }
+    if !digits.len().is_multiple_of(2) {
+        return Err(format!("Invalid hex query: {:?} has an odd number of digits", query));
+    }
+    // from_str_radix 接受前导 '+'，需要先逐个检查
+    if !digits.iter().all(u8::is_ascii_hexdigit) {
+        return Err(format!("Invalid hex query: {:?} contains a non-hex digit", query));
+    }
+    Ok(digits
+        .chunks(2)
+        .map(|pair| (hex_value(pair[0]) << 4) | hex_value(pair[1]))
+        .collect())
+}
+
+fn hex_value(digit: u8) -> u8 {
+    match digit {
+        b'0'..=b'9' => digit - b'0',
+        b'a'..=b'f' => digit - b'a' + 10,
+        _ => digit - b'A' + 10,
+    }
+}
+
+/// 以空格分隔的小写十六进制，如 "89 50 4e 47"
+pub fn hex_dump(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join(" ")
+}
+
+/// 为 offset 处长度为 len 的匹配构造结果。line_number 借用为字节偏移，排序和去重按它进行，
+/// 输出前由 ByteMatch 换成字节偏移字段
+pub fn byte_result(content: &[u8], offset: usize, len: usize, shown_path: &str) -> SearchResult {
+    let context_start = offset.saturating_sub(CONTEXT_BYTES);
+    let context_end = (offset + len + CONTEXT_BYTES).min(content.len());
+    SearchResult {
+        file_path: shown_path.to_string(),
+        line_number: offset,
+        line_content: hex_dump(&content[offset..offset + len]),
+        context_before: Vec::new(),
+        context_after: Vec::new(),
+        match_column: None,
+        match_column_utf16: None,
+        leading_whitespace_removed: None,
+        line_truncated: None,
+        original_match_column: None,
+        original_match_column_utf16: None,
+        highlight_spans: None,
+        matched_term: None,
+        matched_text: None,
+        match_start: None,
+        match_end: None,
+        near_lines: None,
+        line_ending: None,
+        file_size: None,
+        modified_at: None,
+        language: None,
+        enclosing_line: None,
+        enclosing_text: None,
+        context_hex: Some(hex_dump(&content[context_start..context_end])),
+        file_match_count: 0,
+        file_mtime: None,
+        captures: None,
+        named_captures: None,
+        captures_truncated: None,
+    }
+}
+
+/// mode=bytes 时输出的单个匹配，代替 SearchResult
+#[derive(Serialize, Debug)]
+pub struct ByteMatch {
+    file_path: String,
+    byte_offset: usize,
+    matched_hex: String,
+    context_start: usize,  // context_hex 第一个字节的偏移
+    context_hex: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file_size: Option<u64>,  // 以下两项仅 file_metadata 时存在
+    #[serde(skip_serializing_if = "Option::is_none")]
+    modified_at: Option<String>,
+}
+
+impl From<SearchResult> for ByteMatch {
+    fn from(result: SearchResult) -> Self {
+        ByteMatch {
+            byte_offset: result.line_number,
+            context_start: result.line_number.saturating_sub(CONTEXT_BYTES),
+            file_path: result.file_path,
+            matched_hex: result.line_content,
+            context_hex: result.context_hex.unwrap_or_default(),
+            file_size: result.file_size,
+            modified_at: result.modified_at,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_hex_accepts_case_and_whitespace() {
+        assert_eq!(parse_hex("89504E47"), Ok(vec![0x89, 0x50, 0x4e, 0x47]));
+        assert_eq!(parse_hex("89 50 4e 47"), Ok(vec![0x89, 0x50, 0x4e, 0x47]));
+        assert_eq!(parse_hex(" 0a\t0D\n"), Ok(vec![0x0a, 0x0d]));
+        assert_eq!(parse_hex("00ff"), Ok(vec![0x00, 0xff]));
+    }
+
+    #[test]
+    fn parse_hex_rejects_invalid_input() {
+        assert_eq!(parse_hex(""), Err("Invalid hex query: empty".to_string()));
+        assert_eq!(parse_hex("  "), Err("Invalid hex query: empty".to_string()));
+        assert_eq!(parse_hex("895"), Err("Invalid hex query: \"895\" has an odd number of digits".to_string()));
+        assert_eq!(parse_hex("8 9 5"), Err("Invalid hex query: \"8 9 5\" has an odd number of digits".to_string()));
+        assert_eq!(parse_hex("zz"), Err("Invalid hex query: \"zz\" contains a non-hex digit".to_string()));
+        // 0x 前缀、符号和多字节字符都不是十六进制数字
+        assert!(parse_hex("0x89").is_err());
+        assert!(parse_hex("+1").is_err());
+        assert!(parse_hex("é").is_err());
+    }
+
+    #[test]
+    fn byte_result_clamps_context_to_file() {
+        let content: Vec<u8> = (0u8..40).collect();
+        let result = byte_result(&content, 2, 2, "a.bin");
+        assert_eq!(result.line_number, 2);
+        assert_eq!(result.line_content, "02 03");
+        assert_eq!(result.context_hex.as_deref(), Some(hex_dump(&content[0..20]).as_str()));
+
+        let result = byte_result(&content, 36, 4, "a.bin");
+        assert_eq!(result.context_hex.as_deref(), Some(hex_dump(&content[20..40]).as_str()));
+
+        let matched = ByteMatch::from(byte_result(&content, 30, 1, "a.bin"));
+        assert_eq!((matched.byte_offset, matched.context_start), (30, 14));
+    }
+}
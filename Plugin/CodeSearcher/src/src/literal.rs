@@ -0,0 +1,89 @@
+// 非正则、非整词查询的字面量快速路径：单个区分大小写的搜索词用 memmem，其余用 Aho-Corasick。
+// 只在能保证与组合正则的匹配结果 (位置、多个匹配的切分) 完全一致时启用，否则调用方继续用正则
+
+use aho_corasick::{AhoCorasick, MatchKind};
+use memchr::memmem;
+
+/// 匹配在行内的字节区间 [start, end)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl From<regex::Match<'_>> for Span {
+    fn from(m: regex::Match<'_>) -> Self {
+        Span { start: m.start(), end: m.end() }
+    }
+}
+
+pub enum LiteralMatcher {
+    Single(Box<memmem::Finder<'static>>),
+    Multi(AhoCorasick),
+}
+
+impl LiteralMatcher {
+    /// terms 的顺序与组合正则中的分支顺序相同。无法保证结果一致时返回 None：
+    /// 有空搜索词；或不区分大小写时搜索词含非 ASCII 字符、k 或 s
+    /// (正则按 Unicode 大小写折叠，k 还会匹配开尔文符号 K，s 还会匹配长 s ſ，Aho-Corasick 只折叠 ASCII)
+    pub fn new(terms: &[&str], case_sensitive: bool) -> Option<Self> {
+        if terms.is_empty() || terms.iter().any(|term| term.is_empty()) {
+            return None;
+        }
+        if !case_sensitive
+            && terms
+                .iter()
+                .flat_map(|term| term.chars())
+                .any(|c| !c.is_ascii() || matches!(c.to_ascii_lowercase(), 'k' | 's'))
+        {
+            return None;
+        }
+        match terms {
+            [term] if case_sensitive => {
+                Some(LiteralMatcher::Single(Box::new(memmem::Finder::new(term.as_bytes()).into_owned())))
+            }
+            // LeftmostFirst 与正则分支的语义相同：取最左的匹配，同一位置按搜索词的先后顺序
+            _ => AhoCorasick::builder()
+                .match_kind(MatchKind::LeftmostFirst)
+                .ascii_case_insensitive(!case_sensitive)
+                .build(terms)
+                .ok()
+                .map(LiteralMatcher::Multi),
+        }
+    }
+
+    pub fn is_match(&self, haystack: &str) -> bool {
+        match self {
+            LiteralMatcher::Single(finder) => finder.find(haystack.as_bytes()).is_some(),
+            LiteralMatcher::Multi(automaton) => automaton.is_match(haystack),
+        }
+    }
+
+    pub fn find(&self, haystack: &str) -> Option<Span> {
+        match self {
+            LiteralMatcher::Single(finder) => finder
+                .find(haystack.as_bytes())
+                .map(|start| Span { start, end: start + finder.needle().len() }),
+            LiteralMatcher::Multi(automaton) => automaton
+                .find(haystack)
+                .map(|m| Span { start: m.start(), end: m.end() }),
+        }
+    }
+
+    /// 从左到右互不重叠的全部匹配
+    pub fn find_iter<'a>(&'a self, haystack: &'a str) -> Box<dyn Iterator<Item = Span> + 'a> {
+        match self {
+            LiteralMatcher::Single(finder) => {
+                let len = finder.needle().len();
+                Box::new(
+                    finder
+                        .find_iter(haystack.as_bytes())
+                        .map(move |start| Span { start, end: start + len }),
+                )
+            }
+            LiteralMatcher::Multi(automaton) => {
+                Box::new(automaton.find_iter(haystack).map(|m| Span { start: m.start(), end: m.end() }))
+            }
+        }
+    }
+}
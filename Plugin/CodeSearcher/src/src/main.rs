@@ -1,112 +1,1166 @@
+mod bytes;
+mod literal;
+mod sarif;
+mod symbols;
+mod walk_cache;
+
+use bytes::ByteMatch;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, SecondsFormat, Utc};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
 use ignore::{WalkBuilder, WalkState};
-use regex::Regex;
+use literal::{LiteralMatcher, Span};
+use memchr::memmem;
+use sarif::SarifLog;
+use walk_cache::{CachedFile, WalkRecorder};
+use regex::{Regex, RegexBuilder};
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{InstanceType, NumberValidation, Schema, SchemaObject, StringValidation, SubschemaValidation};
+use schemars::{schema_for, JsonSchema};
 use serde::{de::{self, Deserializer, Unexpected}, Deserialize, Serialize};
-use std::collections::HashSet;
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::cell::OnceCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::env;
 use std::fs;
-use std::io::{self, Read};
+use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
-use std::sync::mpsc;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicIsize, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use walkdir::WalkDir;
 
-const MAX_FILE_SIZE: u64 = 1024 * 1024; // 1MB
+const DEFAULT_MAX_FILE_SIZE: usize = 1024 * 1024; // 1MB
 const DEFAULT_MAX_RESULTS: usize = 100;
+const DEFAULT_CONTEXT_LINES: usize = 2;
+const CONFIG_FILE_NAME: &str = "codesearcher.config.json";
+const DEFAULT_TIMEOUT_MS: usize = 30_000;
+const MAX_DEFAULT_THREADS: usize = 8;  // 自动选择线程数时的上限，避免在多核共享机器上占满所有核
+const DEFAULT_MAX_REPLACEMENTS: usize = 1000;
+const DEFAULT_MAX_OUTPUT_BYTES: usize = 64 * 1024;
+const MAX_TOTAL_COUNT: usize = 100_000;  // total_count 的上限
+const RESULT_CHANNEL_CAPACITY: usize = 1024;  // 工作线程到汇总线程的结果通道容量，满时工作线程阻塞等待
+const DEFAULT_CACHE_MAX_SIZE_MB: u64 = 100;
+const DEFAULT_SLOW_FILE_THRESHOLD_MS: u64 = 100;
+const DEFAULT_PLUGIN_TIMEOUT_MS: u64 = 10_000;
+const DEFAULT_MAX_SCAN_LINE_LENGTH: usize = 20_000;
+const MAX_PATTERN_LENGTH: usize = 2000;  // 单个搜索词的最大字符数
+const MIN_QUERY_LENGTH: usize = 2;  // 短于此长度的搜索词在 query_warnings 中提示过于宽泛
+const REGEX_SIZE_LIMIT: usize = 10 * 1024 * 1024;  // 编译后正则程序的大小上限，防止巨大的模式耗尽内存
+const REGEX_DFA_SIZE_LIMIT: usize = 10 * 1024 * 1024;
+const MAX_WARNINGS: usize = 100;  // 超出部分只计数 (warnings_dropped)
+const MAX_EXCLUSION_EXAMPLES: usize = 20;  // explain 模式下每类排除原因列出的示例路径数
+const DEFAULT_DIR_DEPTH: usize = 2;
+const MAX_DIR_EXAMPLE_FILES: usize = 5;  // output_mode=dirs 时每个目录列出的示例文件数
+const TRUNCATION_MARKER: &str = "…";
+const UTF8_BOM: &str = "\u{feff}";
+const COMMENT_STYLES: [&str; 4] = ["//", "#", "--", "/*"];
+const FILE_TYPES: [(&str, &[&str]); 9] = [
+    ("rust", &["rs"]),
+    ("web", &["js", "ts", "jsx", "tsx", "html", "css", "vue"]),
+    ("config", &["toml", "yaml", "yml", "json", "ini"]),
+    ("python", &["py", "pyi"]),
+    ("go", &["go"]),
+    ("java", &["java"]),
+    ("c", &["c", "h", "cpp", "hpp", "cc"]),
+    ("markdown", &["md", "markdown"]),
+    ("text", &["txt"]),
+];
+const DEFAULT_ALWAYS_INCLUDE_FILENAMES: [&str; 4] = ["Makefile", "Dockerfile", "Justfile", "Procfile"];
+const TEST_FILE_PATTERNS: [&str; 6] = ["*_test.rs", "*.test.ts", "*.spec.ts", "*.test.js", "*.spec.js", "*_test.go"];
+const GROUP_BY_MODES: [&str; 2] = ["file", "directory"];
+const OUTPUT_MODES: [&str; 2] = ["results", "dirs"];
+const OUTPUT_FORMATS: [&str; 3] = ["json", "text", "sarif"];
+const SORT_MODES: [&str; 4] = ["path", "matches", "mtime", "none"];
+const WORD_MODES: [&str; 3] = ["identifier", "unicode", "none"];
+const LINE_FORMATS: [&str; 4] = ["trim", "trim_start", "trim_end", "raw"];
+const SEARCH_MODES: [&str; 2] = ["text", "bytes"];
+const MAX_CAPTURE_BYTES: usize = 1024;  // 单条结果中 captures 的文本总字节数上限
+const CACHE_FORMAT_VERSION: &str = "8";  // SearchResult 的字段或列含义变化时递增，使旧缓存失效
+const CACHE_SUBDIR: &str = "codesearcher-cache";  // cache_dir 下实际存放缓存文件的子目录，淘汰只在其中进行
 
 // --- Serde Deserialization Helpers ---
 
+/// 布尔参数：接受 JSON 布尔值、"true"/"false"/"1"/"0" 字符串 (不区分大小写) 以及数字 1/0
+struct BoolVisitor;
+
+impl<'de> de::Visitor<'de> for BoolVisitor {
+    type Value = bool;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a boolean (true, false, 1, 0) or a boolean string")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<bool, E> {
+        Ok(v)
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<bool, E> {
+        match v {
+            1 => Ok(true),
+            0 => Ok(false),
+            _ => Err(E::invalid_value(Unexpected::Unsigned(v), &self)),
+        }
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<bool, E> {
+        match u64::try_from(v) {
+            Ok(v) => self.visit_u64(v),
+            Err(_) => Err(E::invalid_value(Unexpected::Signed(v), &self)),
+        }
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<bool, E> {
+        match v.to_lowercase().as_str() {
+            "true" | "1" => Ok(true),
+            "false" | "0" => Ok(false),
+            _ => Err(E::invalid_value(Unexpected::Str(v), &self)),
+        }
+    }
+}
+
+/// 非负整数参数：接受 JSON 数字和 "20" 这样的数字字符串
+struct UsizeVisitor;
+
+impl<'de> de::Visitor<'de> for UsizeVisitor {
+    type Value = usize;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("an unsigned integer or an unsigned integer string")
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<usize, E> {
+        usize::try_from(v).map_err(|_| E::invalid_value(Unexpected::Unsigned(v), &self))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<usize, E> {
+        usize::try_from(v).map_err(|_| E::invalid_value(Unexpected::Signed(v), &self))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<usize, E> {
+        v.trim().parse().map_err(|_| E::invalid_value(Unexpected::Str(v), &self))
+    }
+}
+
+/// BoolVisitor 接受的写法：布尔值、"true"/"false"/"1"/"0" 字符串 (不区分大小写) 或整数 0/1
+fn lenient_bool_schema(_: &mut SchemaGenerator) -> Schema {
+    one_of(vec![
+        instance(InstanceType::Boolean),
+        SchemaObject {
+            string: Some(Box::new(StringValidation {
+                pattern: Some("^(?:[Tt][Rr][Uu][Ee]|[Ff][Aa][Ll][Ss][Ee]|[01])$".to_string()),
+                ..Default::default()
+            })),
+            ..instance(InstanceType::String)
+        },
+        SchemaObject {
+            number: Some(Box::new(NumberValidation {
+                minimum: Some(0.0),
+                maximum: Some(1.0),
+                ..Default::default()
+            })),
+            ..instance(InstanceType::Integer)
+        },
+    ])
+}
+
+/// UsizeVisitor 接受的写法：非负整数或数字字符串 (允许首尾空白)
+fn lenient_usize_schema(_: &mut SchemaGenerator) -> Schema {
+    one_of(vec![
+        SchemaObject {
+            number: Some(Box::new(NumberValidation {
+                minimum: Some(0.0),
+                ..Default::default()
+            })),
+            ..instance(InstanceType::Integer)
+        },
+        SchemaObject {
+            string: Some(Box::new(StringValidation {
+                pattern: Some(r"^\s*[0-9]+\s*$".to_string()),
+                ..Default::default()
+            })),
+            ..instance(InstanceType::String)
+        },
+    ])
+}
+
+fn instance(instance_type: InstanceType) -> SchemaObject {
+    SchemaObject {
+        instance_type: Some(instance_type.into()),
+        ..Default::default()
+    }
+}
+
+fn one_of(variants: Vec<SchemaObject>) -> Schema {
+    SchemaObject {
+        subschemas: Some(Box::new(SubschemaValidation {
+            one_of: Some(variants.into_iter().map(Schema::Object).collect()),
+            ..Default::default()
+        })),
+        ..Default::default()
+    }
+    .into()
+}
+
 fn deserialize_bool_from_string<'de, D>(deserializer: D) -> Result<bool, D::Error>
 where
     D: Deserializer<'de>,
 {
-    match String::deserialize(deserializer)?.to_lowercase().as_str() {
-        "true" | "1" => Ok(true),
-        "false" | "0" => Ok(false),
-        other => Err(de::Error::invalid_value(
-            Unexpected::Str(other),
-            &"a boolean string (true, false, 1, 0)",
-        )),
-    }
+    deserializer.deserialize_any(BoolVisitor)
 }
 
 fn deserialize_usize_from_string<'de, D>(deserializer: D) -> Result<usize, D::Error>
 where
     D: Deserializer<'de>,
 {
-    let s = String::deserialize(deserializer)?;
-    s.parse::<usize>().map_err(|_| {
-        de::Error::invalid_value(Unexpected::Str(&s), &"an unsigned integer string")
-    })
+    deserializer.deserialize_any(UsizeVisitor)
+}
+
+fn deserialize_optional_bool_from_string<'de, D>(deserializer: D) -> Result<Option<bool>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize_bool_from_string(deserializer).map(Some)
+}
+
+fn deserialize_optional_usize_from_string<'de, D>(deserializer: D) -> Result<Option<usize>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize_usize_from_string(deserializer).map(Some)
 }
 
+#[derive(Deserialize, Debug, JsonSchema)]
+struct NearArgs {
+    a: String,
+    b: String,
+    /// 最大行距，0 表示必须在同一行
+    #[serde(deserialize_with = "deserialize_usize_from_string")]
+    #[schemars(schema_with = "lenient_usize_schema")]
+    distance: usize,
+}
 
-#[derive(Deserialize, Debug)]
+/// CodeSearcher 的输入参数。布尔值和数字既可以是 JSON 原生值，也可以是字符串 (如 "true"、"20")
+#[derive(Deserialize, Debug, JsonSchema)]
 struct InputArgs {
-    query: String,
+    /// 搜索内容，默认按字面量匹配。只有一个搜索词时 queries 的简写
+    query: Option<String>,
+    /// 多个搜索词，与 query 一起按 operator 组合
+    queries: Option<Vec<String>>,
+    /// "text" (默认) 或 "bytes"。bytes 时 query 是十六进制字节序列 (如 "89504E47"，可用空白分组)，
+    /// 文件按字节读取 (包括二进制文件，仍受 max_file_size 限制)，结果以 byte_matches 代替 result，
+    /// 每项给出 byte_offset 和匹配前后各 16 字节的 context_hex。此时不按 ALLOWED_EXTENSIONS 过滤，
+    /// 需要限定文件类型时使用 file_types (可配合 type_definitions，不与 ALLOWED_EXTENSIONS 取交集)。
+    /// 不能与 queries、near、query_expansion、use_regex、whole_word、invert_match、ignore_comments、stream、
+    /// merge_context、group_by、line_start/line_end、replace、enclosing_symbol 以及 json 以外的 format 同时使用
+    mode: Option<String>,
+    /// 邻近搜索：报告 a 和 b 相距不超过 distance 行的位置，不能与 query/queries 同时使用。
+    /// 大小写、正则、整词选项同时作用于 a 和 b
+    near: Option<NearArgs>,
+    /// "or" (默认)：匹配任意一个搜索词；"and"：只报告包含全部搜索词的文件中的匹配
+    operator: Option<String>,
+    /// query 的同义词，与 query 组成 (?:query|term1|term2…) 的分支。
+    /// 每一项和 query 一样处理 (默认转义为字面量，use_regex 时按正则)，等价于手写分支正则，
+    /// 只是便于从结构化数据构造；whole_word 和大小写选项作用于整个组合
+    query_expansion: Option<Vec<String>>,
+    /// 反向匹配 (grep -v)：报告不匹配的行，此时结果不含 match_column。必须同时指定 search_path 或 files
+    #[serde(default, deserialize_with = "deserialize_bool_from_string")]
+    #[schemars(schema_with = "lenient_bool_schema")]
+    invert_match: bool,
+    /// 每个文件最多报告的结果数，0 或缺省表示不限制
+    #[serde(default, deserialize_with = "deserialize_optional_usize_from_string")]
+    #[schemars(schema_with = "lenient_usize_schema")]
+    max_matches_per_file: Option<usize>,
+    /// 排序后每种扩展名 (无扩展名的文件算作一种) 最多保留的结果数，超出的结果被丢弃，
+    /// 把名额留给其他扩展名；因此少了结果时 truncated_reason 为 "max_per_extension"。
+    /// 0 或缺省表示不限制，不能与 stream 同时使用。指定后 sort=none 也要搜完全部文件才能确定名额，
+    /// 不会在收满一页后提前停止
+    #[serde(default, deserialize_with = "deserialize_optional_usize_from_string")]
+    #[schemars(schema_with = "lenient_usize_schema")]
+    max_per_extension: Option<usize>,
+    /// 项目根目录，优先于 PROJECT_BASE_PATH 环境变量和自动探测；必须是已存在的目录
+    project_base: Option<String>,
+    /// 搜索子目录 (相对项目根目录)，不能与 files 同时使用
     search_path: Option<String>,
+    /// 结果中的 file_path 使用绝对路径，默认 false (相对项目根目录)。
+    /// 与相对路径一样先规范化：项目内的符号链接和项目根目录本身的符号链接都解析为实际路径
     #[serde(default, deserialize_with = "deserialize_bool_from_string")]
+    #[schemars(schema_with = "lenient_bool_schema")]
+    absolute_paths: bool,
+    /// 区分大小写
+    #[serde(default, deserialize_with = "deserialize_bool_from_string")]
+    #[schemars(schema_with = "lenient_bool_schema")]
     case_sensitive: bool,
+    /// 全词匹配
     #[serde(default, deserialize_with = "deserialize_bool_from_string")]
+    #[schemars(schema_with = "lenient_bool_schema")]
     whole_word: bool,
-    #[serde(default = "default_context", deserialize_with = "deserialize_usize_from_string")]
-    context_lines: usize,
+    /// whole_word 的词边界规则："unicode" (默认，Unicode 词边界，搜索词以中日文等不分词的字符开头或结尾时该侧不要求边界)、
+    /// "identifier" (只把 ASCII 字母、数字和 _ 视为词的一部分，适合代码标识符；搜索词首尾不是这些字符时该侧不要求边界)、"none" (不加边界)
+    word_mode: Option<String>,
+    /// query 按正则表达式解析，默认按字面量转义。模式含捕获组时结果附带本行第一个匹配的 captures
+    /// (第 1 组起，未参与匹配的组为 null)，含 (?P<name>...) 时另附 named_captures；
+    /// 每条结果的捕获文本合计最多 1024 字节，超出时截断并标记 captures_truncated
+    #[serde(default, deserialize_with = "deserialize_bool_from_string")]
+    #[schemars(schema_with = "lenient_bool_schema")]
+    use_regex: bool,
+    /// query 含大写字母时区分大小写，否则不区分
+    #[serde(default, deserialize_with = "deserialize_bool_from_string")]
+    #[schemars(schema_with = "lenient_bool_schema")]
+    smart_case: bool,
+    /// 匹配行前后附带的上下文行数，缺省时使用配置文件中的 context_lines，默认 2
+    #[serde(default, deserialize_with = "deserialize_optional_usize_from_string")]
+    #[schemars(schema_with = "lenient_usize_schema")]
+    context_lines: Option<usize>,
+    /// 匹配行之前的上下文行数，指定时覆盖 context_lines
+    #[serde(default, deserialize_with = "deserialize_optional_usize_from_string")]
+    #[schemars(schema_with = "lenient_usize_schema")]
+    context_before: Option<usize>,
+    /// 匹配行之后的上下文行数，指定时覆盖 context_lines
+    #[serde(default, deserialize_with = "deserialize_optional_usize_from_string")]
+    #[schemars(schema_with = "lenient_usize_schema")]
+    context_after: Option<usize>,
+    /// 以 NDJSON 逐条输出结果
+    #[serde(default, deserialize_with = "deserialize_bool_from_string")]
+    #[schemars(schema_with = "lenient_bool_schema")]
+    stream: bool,
+    /// 分页：跳过排序后的前 offset 条结果，默认 0。每次请求都重新搜索，除 sort="none" 外排序在多次运行之间稳定
+    /// (同分时按路径、行号、列号排列)，文件没有变化时各页不会重复或遗漏
+    #[serde(default, deserialize_with = "deserialize_optional_usize_from_string")]
+    #[schemars(schema_with = "lenient_usize_schema")]
+    offset: Option<usize>,
+    /// 分页：本页最多返回的结果数，代替 MAX_RESULTS；0 或缺省时使用 MAX_RESULTS。
+    /// 指定 offset 或 limit 时输出 total_count，调用方据此决定是否继续请求下一页
+    #[serde(default, deserialize_with = "deserialize_optional_usize_from_string")]
+    #[schemars(schema_with = "lenient_usize_schema")]
+    limit: Option<usize>,
+    /// 结果部分序列化后的字节数上限，超出时整条丢弃后面的结果并标记 truncated_reason="max_output_bytes"。
+    /// 0 表示不限制，默认 65536
+    #[serde(default = "default_max_output_bytes", deserialize_with = "deserialize_usize_from_string")]
+    #[schemars(schema_with = "lenient_usize_schema")]
+    max_output_bytes: usize,
+    /// 超过该字节数的行不参与匹配 (计入 long_lines_skipped)，避免压缩过的超长行拖慢整个搜索。0 表示不限制，默认 20000
+    #[serde(default = "default_max_scan_line_length", deserialize_with = "deserialize_usize_from_string")]
+    #[schemars(schema_with = "lenient_usize_schema")]
+    max_scan_line_length: usize,
+    /// 单行最大字符数，0 表示不限制，默认 500
+    #[serde(default = "default_max_line_length", deserialize_with = "deserialize_usize_from_string")]
+    #[schemars(schema_with = "lenient_usize_schema")]
+    max_line_length: usize,
+    /// 跳过以注释符号开头的行
+    #[serde(default, deserialize_with = "deserialize_bool_from_string")]
+    #[schemars(schema_with = "lenient_bool_schema")]
+    ignore_comments: bool,
+    /// "//"、"#"、"--"、"/*"，缺省时按扩展名推断
+    comment_style: Option<String>,
+    /// 只搜索这些文件 (相对项目根目录)，不遍历目录，也不按扩展名白名单和 ignore_test_files 过滤
+    files: Option<Vec<String>>,
+    /// 只报告从这一行 (1 起，含) 开始的匹配，上下文行不受限制。要求 files 恰好只有一个文件，超出文件范围时钳制到首行/末行
+    #[serde(default, deserialize_with = "deserialize_optional_usize_from_string")]
+    #[schemars(schema_with = "lenient_usize_schema")]
+    line_start: Option<usize>,
+    /// 只报告到这一行 (含) 为止的匹配，其余同 line_start
+    #[serde(default, deserialize_with = "deserialize_optional_usize_from_string")]
+    #[schemars(schema_with = "lenient_usize_schema")]
+    line_end: Option<usize>,
+    /// 原样返回匹配行和上下文行，保留缩进；为 false 时去掉首尾空白，默认 true
+    #[serde(default = "default_true", deserialize_with = "deserialize_bool_from_string")]
+    #[schemars(schema_with = "lenient_bool_schema")]
+    preserve_whitespace: bool,
+    /// 匹配行和上下文行的空白处理："trim" (去掉首尾空白)、"trim_start" (只去掉行首缩进)、
+    /// "trim_end" (只去掉行尾空白，保留缩进)、"raw" (原样)。指定时优先于 preserve_whitespace，
+    /// 缺省时按 preserve_whitespace 取 "raw" 或 "trim"。match_column 始终相对原始行
+    line_format: Option<String>,
+    /// 把同一文件中上下文窗口重叠或相邻的匹配合并为代码块，以 blocks 代替 result 输出
+    #[serde(default, deserialize_with = "deserialize_bool_from_string")]
+    #[schemars(schema_with = "lenient_bool_schema")]
+    merge_context: bool,
+    /// 同一文件中相邻两个结果的上下文不相连时，在前一个结果的 context_after 末尾追加的分隔行 (类似 grep 的 --)，
+    /// 默认 "--"，空字符串表示不追加。上下文行数为 0 或 merge_context 时不生效
+    #[serde(default = "default_context_separator")]
+    context_separator: String,
+    /// 结果排序："path" (默认，按路径和行号)、"matches" (匹配多的文件在前)、"mtime" (最近修改的文件在前)、"none"。
+    /// 无论线程数多少，都是先按文件收集、排序，再截断到 MAX_RESULTS 并报告 truncated 和 total_matches；
+    /// "none" 时文件顺序取决于线程调度，但同一文件的结果总是连续的。stream 模式按到达顺序输出，忽略此项
+    #[serde(default = "default_sort")]
+    sort: String,
+    /// "file" 或 "directory"：在 result 之外再按文件 (file_groups) 或目录 (grouped_result) 分组输出
+    group_by: Option<String>,
+    /// "results" (默认)：逐条返回匹配行；"dirs"：只按目录汇总匹配数和文件数 (directories)，不返回匹配行，
+    /// 适合先了解某个功能分布在哪些目录。不受 MAX_RESULTS 限制，不能与 stream、group_by、merge_context、replace 同时使用
+    output_mode: Option<String>,
+    /// "json" (默认)、"text" 或 "sarif"。text 时按 grep 风格逐行输出 path:line:column: content，上下文行为 path-line-content，
+    /// 不相邻的块之间用 context_separator 分隔，最后一行是 status=success 开头的汇总；出错时 stdout 只有 status=error，
+    /// 错误信息写到 stderr。sarif 时输出 SARIF 2.1.0 文档，截断和警告在 toolExecutionNotifications 中，
+    /// 出错时 executionSuccessful 为 false。两者都不能与 stream、group_by、merge_context、output_mode=dirs、summary_only、replace 同时使用
+    format: Option<String>,
+    /// output_mode=dirs 时按相对项目根目录的前几级目录汇总，默认 2；0 表示全部汇总到 "."
+    #[serde(default, deserialize_with = "deserialize_optional_usize_from_string")]
+    #[schemars(schema_with = "lenient_usize_schema")]
+    dir_depth: Option<usize>,
+    /// 只返回 summary (总匹配数和匹配文件列表)，不返回匹配行，也不输出每个文件的匹配数，比 output_mode=dirs 更精简。
+    /// 不受 MAX_RESULTS 限制，不能与 output_mode=dirs、stream、group_by、merge_context、replace 同时使用
+    #[serde(default, deserialize_with = "deserialize_optional_bool_from_string")]
+    #[schemars(schema_with = "lenient_bool_schema")]
+    summary_only: Option<bool>,
+    /// 把匹配替换为该字符串；use_regex 时支持 $1、${name} 捕获组引用。不能与 invert_match、max_matches_per_file 同时使用
+    replace: Option<String>,
+    /// 只返回每个文件的 unified diff 预览，不修改文件，默认 true
+    #[serde(default = "default_dry_run", deserialize_with = "deserialize_bool_from_string")]
+    #[schemars(schema_with = "lenient_bool_schema")]
+    dry_run: bool,
+    /// 全部文件合计最多替换的次数，超出时剩余文件不再修改，默认 1000
+    #[serde(default = "default_max_replacements", deserialize_with = "deserialize_usize_from_string")]
+    #[schemars(schema_with = "lenient_usize_schema")]
+    max_replacements: usize,
+    /// 只搜索这些文件类型 (如 "rust"、"web"、"config")，展开为扩展名后再与 ALLOWED_EXTENSIONS 取交集
+    file_types: Option<Vec<String>>,
+    /// 本次请求的自定义文件类型：类型名 → 扩展名列表，同名时覆盖内置类型
+    type_definitions: Option<HashMap<String, Vec<String>>>,
+    /// 遵循 .gitignore、全局 gitignore 和 .git/info/exclude，默认 true
+    #[serde(default = "default_true", deserialize_with = "deserialize_bool_from_string")]
+    #[schemars(schema_with = "lenient_bool_schema")]
+    respect_gitignore: bool,
+    /// 搜索隐藏文件和目录 (以 . 开头)，默认 true
+    #[serde(default = "default_true", deserialize_with = "deserialize_bool_from_string")]
+    #[schemars(schema_with = "lenient_bool_schema")]
+    include_hidden: bool,
+    /// 只搜索 git 跟踪的文件 (git ls-files)，仍然应用扩展名、测试文件、exclude_file_patterns、IGNORED_FOLDERS 和隐藏文件过滤。
+    /// git 不可用或搜索目录不在仓库中时记一条 warning 并退回普通遍历。不能与 files 同时使用
+    #[serde(default, deserialize_with = "deserialize_bool_from_string")]
+    #[schemars(schema_with = "lenient_bool_schema")]
+    tracked_only: bool,
+    /// 遍历时跟随符号链接，默认 false。链接到项目根目录之外的文件不搜索，记为 outside_project 警告
+    #[serde(default, deserialize_with = "deserialize_bool_from_string")]
+    #[schemars(schema_with = "lenient_bool_schema")]
+    follow_symlinks: bool,
+    /// 额外的忽略文件 (相对项目根目录)，语法与 .gitignore 相同
+    ignore_file: Option<String>,
+    /// 排除匹配这些 glob 的文件 (如 "**/*.generated.ts"、"**/migrations/**")，按相对项目根目录的路径匹配，
+    /// 可带前缀 "!"。与 IGNORED_FOLDERS 叠加生效，只作用于目录遍历，不影响 files
+    exclude_file_patterns: Option<Vec<String>>,
+    /// 跳过测试文件 (*_test.rs、*.spec.ts 等)，缺省时使用 IGNORE_TEST_FILES 环境变量。
+    /// 与扩展名白名单一样只作用于目录遍历，files 中显式列出的测试文件照常搜索
+    #[serde(default, deserialize_with = "deserialize_optional_bool_from_string")]
+    #[schemars(schema_with = "lenient_bool_schema")]
+    ignore_test_files: Option<bool>,
+    /// 文件过滤 (扩展名白名单、ALWAYS_INCLUDE_FILENAMES、测试文件、exclude_file_patterns) 是否区分大小写。
+    /// 缺省时跟随系统惯例：macOS 和 Windows 的文件系统不区分大小写，此时 .TS 与 ts 视为同一扩展名；其它系统区分。
+    /// IGNORED_FOLDERS 始终按目录名精确匹配
+    #[serde(default, deserialize_with = "deserialize_optional_bool_from_string")]
+    #[schemars(schema_with = "lenient_bool_schema")]
+    case_sensitive_paths: Option<bool>,
+    /// 代码文件 (rs、js、ts、py、go、java) 中向上查找匹配所在的函数、类等定义行，附带 enclosing_line/enclosing_text；
+    /// 向上 200 行内没有找到时不附带
+    #[serde(default, deserialize_with = "deserialize_bool_from_string")]
+    #[schemars(schema_with = "lenient_bool_schema")]
+    enclosing_symbol: bool,
+    /// 在每条结果中附带所在文件的 file_size、modified_at (UTC ISO-8601) 和 language (file_types 中的类型名，如 "rust"、"web")，
+    /// 便于按修改时间或大小取舍结果而不必再逐个 stat
+    #[serde(default, deserialize_with = "deserialize_bool_from_string")]
+    #[schemars(schema_with = "lenient_bool_schema")]
+    file_metadata: bool,
+    /// 在结果中附带 highlight_spans
+    #[serde(default, deserialize_with = "deserialize_bool_from_string")]
+    #[schemars(schema_with = "lenient_bool_schema")]
+    include_spans: bool,
+    /// 按 (文件, mtime, 查询) 缓存单文件结果的目录。同时缓存目录遍历得到的文件列表，
+    /// 之后的请求在遍历到的目录和忽略文件都没有变化时跳过遍历 (见输出中的 walk_cache)。
+    /// 必须位于项目根目录内，缓存文件写在其中的 codesearcher-cache 子目录
+    cache_dir: Option<String>,
+    /// 单文件大小上限 (字节)，0 表示不限制，缺省时使用配置文件中的 max_file_size，默认 1MB
+    #[serde(default, deserialize_with = "deserialize_optional_usize_from_string")]
+    #[schemars(schema_with = "lenient_usize_schema")]
+    max_file_size: Option<usize>,
+    /// 本次搜索最多读取的文件字节数，达到后停止遍历并标记 truncated_reason="max_bytes"。
+    /// 0 表示不限制，缺省时使用环境变量 MAX_TOTAL_BYTES_SEARCHED，默认不限制
+    #[serde(default, deserialize_with = "deserialize_optional_usize_from_string")]
+    #[schemars(schema_with = "lenient_usize_schema")]
+    max_total_bytes_searched: Option<usize>,
+    /// 子目录深度，0 表示只搜索根目录下直接包含的文件，缺省不限制
+    #[serde(default, deserialize_with = "deserialize_optional_usize_from_string")]
+    #[schemars(schema_with = "lenient_usize_schema")]
+    max_depth: Option<usize>,
+    /// 遍历目录使用的线程数，1 表示在当前线程中顺序遍历 (结果顺序确定，便于排查)。
+    /// 缺省或 0 时使用环境变量 CODESEARCHER_THREADS，仍未设置时取 CPU 核数，最多 8
+    #[serde(default, deserialize_with = "deserialize_optional_usize_from_string")]
+    #[schemars(schema_with = "lenient_usize_schema")]
+    threads: Option<usize>,
+    /// 搜索时间预算 (毫秒)，超时后返回已收集的结果，0 表示不限制，默认 30000
+    #[serde(default = "default_timeout_ms", deserialize_with = "deserialize_usize_from_string")]
+    #[schemars(schema_with = "lenient_usize_schema")]
+    timeout_ms: usize,
+    /// 只搜索在此之后修改过的文件：ISO-8601 时间 (如 2024-05-01T08:00:00Z、2024-05-01) 或相对时长 (如 48h、30m、7d)
+    modified_after: Option<String>,
+    /// 只搜索在此之前修改过的文件，格式同 modified_after
+    modified_before: Option<String>,
+    /// 在输出中附带 exclusions：按原因统计未被搜索的文件 (扩展名、大小、gitignore、IGNORED_FOLDERS、二进制、隐藏文件等)，
+    /// 每类最多列出 20 个示例路径。需要额外遍历一次目录，默认 false
+    #[serde(default, deserialize_with = "deserialize_bool_from_string")]
+    #[schemars(schema_with = "lenient_bool_schema")]
+    explain: bool,
+    /// 输出 JSON 之后向 stderr 写一行统计 (遍历到的文件数、实际搜索的文件数、匹配数、结果通道中积压结果数的峰值、耗时)，
+    /// 供只采集 stderr 的监控使用，不影响 stdout 上的 JSON
+    #[serde(default, deserialize_with = "deserialize_optional_bool_from_string")]
+    #[schemars(schema_with = "lenient_bool_schema")]
+    print_stats: Option<bool>,
 }
 
-fn default_context() -> usize { 2 }
+fn default_max_line_length() -> usize { 500 }
+fn default_true() -> bool { true }
+fn default_sort() -> String { "path".to_string() }
+fn default_timeout_ms() -> usize { DEFAULT_TIMEOUT_MS }
+fn default_dry_run() -> bool { true }
+fn default_max_replacements() -> usize { DEFAULT_MAX_REPLACEMENTS }
+fn default_max_output_bytes() -> usize { DEFAULT_MAX_OUTPUT_BYTES }
+fn default_context_separator() -> String { "--".to_string() }
+fn default_max_scan_line_length() -> usize { DEFAULT_MAX_SCAN_LINE_LENGTH }
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 struct SearchResult {
     file_path: String,
     line_number: usize,
     line_content: String,
     context_before: Vec<String>,
     context_after: Vec<String>,
-    match_column: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    match_column: Option<usize>,  // 匹配起点在原始行 (未 trim) 中的字符偏移，invert_match 时为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    match_column_utf16: Option<usize>,  // 同一位置的 UTF-16 码元偏移 (VS Code 等编辑器使用)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    leading_whitespace_removed: Option<usize>,  // line_format 为 trim 或 trim_start 时 line_content 去掉的行首空白字符数，未截断时 match_column 减去它即为在 line_content 中的列
+    #[serde(skip_serializing_if = "Option::is_none")]
+    line_truncated: Option<bool>,  // 匹配行或上下文行被截断
+    #[serde(skip_serializing_if = "Option::is_none")]
+    original_match_column: Option<usize>,  // 截断前的匹配列，match_column 此时相对截断后的内容
+    #[serde(skip_serializing_if = "Option::is_none")]
+    original_match_column_utf16: Option<usize>,  // 截断前匹配列的 UTF-16 码元偏移
+    #[serde(skip_serializing_if = "Option::is_none")]
+    highlight_spans: Option<Vec<[usize; 2]>>,  // 行内所有不重叠匹配的 [start, end) 字符区间，与 match_column 同一坐标
+    #[serde(skip_serializing_if = "Option::is_none")]
+    matched_term: Option<String>,  // 有多个搜索词时，本行匹配到的那个
+    // 本行第一个匹配。行内全部匹配见 highlight_spans，两者坐标不同：
+    // match_start/match_end 相对 line_content，highlight_spans 与 match_column 同一坐标
+    #[serde(skip_serializing_if = "Option::is_none")]
+    matched_text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    match_start: Option<usize>,  // 在 line_content 中的字符偏移
+    #[serde(skip_serializing_if = "Option::is_none")]
+    match_end: Option<usize>,
+    // 以下两项在 deliver 中设置，供 sort=matches/mtime 比较，不输出也不写入缓存。
+    // 汇总线程中途裁剪时只能看到部分结果，排序依据必须随结果一起带着
+    #[serde(skip)]
+    file_match_count: usize,  // 所在文件的结果数
+    #[serde(skip)]
+    file_mtime: Option<SystemTime>,  // 所在文件的修改时间，取不到时为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    near_lines: Option<[usize; 2]>,  // near 搜索时 a、b 各自所在的行号；结果行是两者中靠前的一行，上下文覆盖到靠后的一行
+    #[serde(skip_serializing_if = "Option::is_none")]
+    line_ending: Option<String>,  // 所在文件的换行符："crlf"、"lf"，两种都有时为 "mixed"；行内容本身不含 \r
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file_size: Option<u64>,  // 以下三项仅 file_metadata 时存在
+    #[serde(skip_serializing_if = "Option::is_none")]
+    modified_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    language: Option<String>,  // 扩展名不在内置 file_types 中时为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    enclosing_line: Option<usize>,  // enclosing_symbol 时匹配行之上最近的定义行的行号
+    #[serde(skip_serializing_if = "Option::is_none")]
+    enclosing_text: Option<String>,  // 该定义行的内容 (去掉首尾空白，按 max_line_length 截断)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    context_hex: Option<String>,  // mode=bytes 时匹配前后的十六进制转储，输出前转成 ByteMatch
+    #[serde(skip_serializing_if = "Option::is_none")]
+    captures: Option<Vec<Option<String>>>,  // use_regex 且有捕获组时，本行第一个匹配中第 1 组起各组的文本，未参与匹配的组为 null
+    #[serde(skip_serializing_if = "Option::is_none")]
+    named_captures: Option<NamedCaptures>,  // 其中 (?P<name>...) 命名组的文本
+    #[serde(skip_serializing_if = "Option::is_none")]
+    captures_truncated: Option<bool>,  // 捕获文本合计超过 MAX_CAPTURE_BYTES，超出部分被截掉
 }
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Debug, Default)]
 struct Output {
     status: String,
     result: Option<Vec<SearchResult>>,
     error: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    blocks: Option<Vec<ResultBlock>>,  // merge_context 模式下代替 result
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file_groups: Option<Vec<FileGroup>>,  // group_by=file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    grouped_result: Option<Vec<DirectoryGroup>>,  // group_by=directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    directories: Option<Vec<DirectorySummary>>,  // output_mode=dirs 时代替 result，按匹配数降序
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<SearchSummary>,  // summary_only 时代替 result
+    #[serde(skip_serializing_if = "Option::is_none")]
+    line_window: Option<LineWindow>,  // 指定 line_start/line_end 时实际生效的行范围
+    #[serde(skip_serializing_if = "Option::is_none")]
+    byte_matches: Option<Vec<ByteMatch>>,  // mode=bytes 时代替 result
+    #[serde(skip_serializing_if = "Option::is_none")]
+    replacements: Option<Vec<FileReplacement>>,  // 指定 replace 时每个文件的替换情况
+    #[serde(skip_serializing_if = "Option::is_none")]
+    files_modified: Option<usize>,  // dry_run=false 时实际改写的文件数
+    #[serde(skip_serializing_if = "Option::is_none")]
     truncated: Option<bool>,  // 是否被截断
+    #[serde(skip_serializing_if = "Option::is_none")]
+    truncated_reason: Option<&'static str>,  // "max_results"、"max_per_extension"、"max_output_bytes"、"max_bytes" 或 "timeout"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total_count: Option<usize>,  // 指定 offset/limit 时分页前的结果总数，最多 MAX_TOTAL_COUNT
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total_matches: Option<usize>,  // 截断前的总匹配数
+    #[serde(skip_serializing_if = "Option::is_none")]
+    files_scanned: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    files_matched: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    files_skipped: Option<usize>,  // 因大小/扩展名/二进制被跳过的文件
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duplicates_skipped: Option<usize>,  // 经符号链接等途径重复到达、已跳过的同一物理文件
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duplicate_results: Option<usize>,  // 同一文件以同一显示路径再次到达时丢弃的匹配，不计入 total_matches
+    #[serde(skip_serializing_if = "Option::is_none")]
+    elapsed_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    search_root: Option<String>,  // 实际搜索的绝对路径
+    #[serde(skip_serializing_if = "Option::is_none")]
+    project_base: Option<String>,  // 解析出的项目根目录 (绝对路径)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timed_out: Option<bool>,  // 超出 timeout_ms，结果不完整
+    #[serde(skip_serializing_if = "Option::is_none")]
+    warnings: Option<Vec<SearchWarning>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    query_warnings: Option<Vec<String>>,  // 查询过短、通配一切或命中过多文件时给出的改进建议
+    #[serde(skip_serializing_if = "Option::is_none")]
+    warnings_dropped: Option<usize>,  // 超出 MAX_WARNINGS 未列出的警告数
+    #[serde(skip_serializing_if = "Option::is_none")]
+    long_lines_skipped: Option<usize>,  // 超过 max_scan_line_length 未参与匹配的行数 (不含命中缓存的文件)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    slow_files: Option<Vec<SlowFileReport>>,  // 搜索耗时超过 CODESEARCHER_SLOW_FILE_THRESHOLD_MS 的文件，最慢的在前
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cache_stats: Option<CacheStats>,  // 指定 cache_dir 时的缓存目录占用与本次淘汰情况
+    #[serde(skip_serializing_if = "Option::is_none")]
+    walk_cache: Option<&'static str>,  // 指定 cache_dir 时目录遍历缓存的使用情况："hit"、"miss" 或 "disabled" (files、tracked_only、explain 时)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exclusions: Option<BTreeMap<&'static str, ExclusionSummary>>,  // explain=true 时按原因统计的未搜索文件
+    #[serde(skip_serializing_if = "Option::is_none")]
+    threads: Option<usize>,  // explain=true 时实际使用的搜索线程数
+}
+
+/// explain 模式下某一类排除原因的统计。
+/// ignored_folder、hidden、gitignore 三类在目录被整体跳过时按目录计数，不再展开其中的文件
+#[derive(Serialize, Debug, Default)]
+struct ExclusionSummary {
+    count: usize,
+    examples: Vec<String>,
+}
+
+/// 单个文件的搜索耗时 (读取 + 匹配)，用于找出拖慢整体搜索的文件
+#[derive(Serialize, Debug)]
+struct SlowFileReport {
+    path: String,
+    duration_ms: u64,
+    match_count: usize,
+}
+
+/// 缓存目录统计：entries/size_bytes 是淘汰之后的状态
+#[derive(Serialize, Debug, Default)]
+struct CacheStats {
+    entries: usize,
+    size_bytes: u64,
+    max_size_bytes: u64,
+    evicted_entries: usize,
+    evicted_bytes: u64,
+}
+
+/// merge_context 模式下合并后的代码块：start_line..=end_line 的连续行及其中的全部匹配
+#[derive(Serialize, Debug)]
+struct ResultBlock {
+    file_path: String,
+    start_line: usize,
+    end_line: usize,
+    lines: Vec<String>,
+    matches: Vec<BlockMatch>,
+}
+
+#[derive(Serialize, Debug)]
+struct BlockMatch {
+    line_number: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    column: Option<usize>,  // 与 SearchResult.match_column 含义相同
+}
+
+/// group_by 分组：同一文件的全部结果
+#[derive(Serialize, Debug)]
+struct FileGroup {
+    file_path: String,
+    matches: Vec<SearchResult>,
+}
+
+/// group_by=directory 分组：同一目录下 (不含子目录) 的文件
+#[derive(Serialize, Debug)]
+struct DirectoryGroup {
+    directory: String,
+    files: Vec<FileGroup>,
+    total_matches: usize,
+}
+
+/// output_mode=dirs 时收集的 (文件, 匹配数)，只包含有匹配的文件
+type FileMatchCounts = Vec<(PathBuf, usize)>;
+
+/// output_mode=dirs 的单个目录汇总，包含其下所有子目录中的匹配
+#[derive(Serialize, Debug)]
+struct DirectorySummary {
+    directory: String,
+    match_count: usize,
+    file_count: usize,
+    example_files: Vec<String>,  // 匹配最多的前 MAX_DIR_EXAMPLE_FILES 个文件
+}
+
+/// summary_only 的输出：只有总数和匹配文件 (按路径排序)，不含匹配行
+#[derive(Serialize, Debug)]
+struct SearchSummary {
+    total_matches: usize,
+    matching_files: Vec<String>,
+    truncated: bool,  // 超时或达到 max_total_bytes_searched，总数和文件列表不完整
+}
+
+/// line_start/line_end 钳制到文件行数后实际生效的行范围 (1 起，含两端)
+#[derive(Serialize, Debug, Clone, Copy)]
+struct LineWindow {
+    line_start: usize,
+    line_end: usize,
+}
+
+impl LineWindow {
+    /// 把请求的行范围钳制到 [1, line_count]；空文件按 1 行计
+    fn clamp(requested: (usize, usize), line_count: usize) -> Self {
+        let last = line_count.max(1);
+        let line_start = requested.0.clamp(1, last);
+        LineWindow {
+            line_start,
+            line_end: requested.1.clamp(line_start, last),
+        }
+    }
+
+    /// 第 i 行 (0 起) 是否在范围内
+    fn contains(&self, i: usize) -> bool {
+        (self.line_start - 1..self.line_end).contains(&i)
+    }
+}
+
+/// 单个文件的替换情况
+#[derive(Serialize, Debug)]
+struct FileReplacement {
+    file_path: String,
+    replacements: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    diff: Option<String>,  // dry_run 时的 unified diff
+    modified: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    skipped: Option<String>,  // 未修改的原因 (超出 max_replacements、写入失败等)
+}
+
+/// replace 相关参数
+struct ReplaceOptions {
+    replacement: String,
+    expand_captures: bool,  // use_regex 时展开 $1 等捕获组引用，否则按字面量替换
+    dry_run: bool,
+    max_replacements: usize,
+}
+
+/// 流式模式下每行输出的记录
+#[derive(Serialize, Debug)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum StreamRecord<'a> {
+    Match(&'a SearchResult),
+    Summary {
+        truncated: bool,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        truncated_reason: Option<&'static str>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        total_count: Option<usize>,
+        total_matches: usize,
+        files_scanned: usize,
+        files_matched: usize,
+        files_skipped: usize,
+        duplicates_skipped: usize,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        duplicate_results: Option<usize>,
+        elapsed_ms: u64,
+        #[serde(skip_serializing_if = "std::ops::Not::not")]
+        timed_out: bool,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        warnings: Vec<SearchWarning>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        warnings_dropped: Option<usize>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        long_lines_skipped: Option<usize>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        exclusions: Option<BTreeMap<&'static str, ExclusionSummary>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        threads: Option<usize>,
+    },
+}
+
+/// 遍历过程中各工作线程共享的计数器
+#[derive(Default)]
+struct ScanCounters {
+    total_matches: AtomicUsize,
+    collected: AtomicUsize,
+    files_walked: AtomicUsize,  // 遍历器、tracked_only 或 files 交出的文件，包括随后被过滤掉的
+    files_scanned: AtomicUsize,
+    files_matched: AtomicUsize,
+    files_skipped: AtomicUsize,
+    duplicates_skipped: AtomicUsize,
+    duplicate_results: AtomicUsize,
+    warnings_dropped: AtomicUsize,
+    long_lines_skipped: AtomicUsize,
+    replacements: AtomicUsize,  // 已占用的 max_replacements 额度
+    bytes_searched: AtomicU64,
+    // 已送入结果通道、汇总线程尚未取走的结果数。发送后加、取出后减，两者之间可能短暂为负
+    pending_results: AtomicIsize,
+    peak_pending_results: AtomicUsize,  // pending_results 的峰值，有界通道下不超过 RESULT_CHANNEL_CAPACITY + 1
+    timed_out: AtomicBool,  // 超时后置位，通知所有工作线程尽快退出
+    byte_limit_reached: AtomicBool,  // 达到 max_total_bytes_searched 后置位，遍历不再开始新文件
+}
+
+struct SearchStats {
+    total_matches: usize,
+    files_walked: usize,
+    files_scanned: usize,
+    files_matched: usize,
+    files_skipped: usize,
+    duplicates_skipped: usize,
+    duplicate_results: usize,
+    warnings_dropped: usize,
+    long_lines_skipped: usize,
+    peak_pending_results: usize,
+}
+
+/// 汇总线程交回的结果
+#[derive(Default)]
+struct Collected {
+    results: Vec<SearchResult>,
+    emitted: usize,  // 流式模式下已输出的条数
+    over_budget: bool,  // 流式输出超出 max_output_bytes
+    received: usize,  // 收到的结果数 (按扩展名限额后)，中途裁剪不影响
+    pruned: bool,  // 收集过程中按 page_end 裁掉过结果
+    extension_capped: bool,  // 收集过程中按扩展名限额丢弃过结果
+}
+
+struct SearchOutcome {
+    results: Vec<SearchResult>,
+    truncated: bool,
+    truncated_reason: Option<&'static str>,
+    total_count: Option<usize>,
+    timed_out: bool,
+    stats: SearchStats,
+    warnings: Vec<SearchWarning>,
+    replacements: Vec<FileReplacement>,
+    slow_files: Vec<SlowFileReport>,
+    exclusions: Option<BTreeMap<&'static str, ExclusionSummary>>,
+    directories: Option<Vec<DirectorySummary>>,
+    summary: Option<SearchSummary>,
+    threads: usize,  // 实际使用的搜索线程数
+    line_window: Option<LineWindow>,  // 文件被跳过 (不存在、二进制等) 时为 None
+    walk_cache: Option<&'static str>,
+}
+
+/// 不影响整体结果的单个文件问题 (文件不存在、无权限、无法解码、遍历出错等)
+#[derive(Serialize, Debug)]
+struct SearchWarning {
+    path: String,
+    kind: String,
+    message: String,
 }
 
 struct AppConfig {
     max_results: usize,
+    context_lines: usize,  // 请求参数缺省时的默认值，main 中会被请求参数覆盖
+    max_file_size: usize,
+    max_total_bytes_searched: usize,  // 0 表示不限制
+    threads: usize,  // 请求参数 threads 缺省时的线程数
     ignored_folders: HashSet<String>,
     allowed_extensions: HashSet<String>,
+    always_include_filenames: HashSet<String>,  // 不受扩展名白名单限制的文件名 (ALWAYS_INCLUDE_FILENAMES)，file_types 时清空
+    ignore_test_files: bool,
+    case_sensitive_paths: bool,  // 请求参数 case_sensitive_paths 缺省时跟随系统惯例
+    cache_max_size: u64,
+    slow_file_threshold: Duration,
+    modified_after: Option<SystemTime>,  // 由请求参数 modified_after/modified_before 解析得到
+    modified_before: Option<SystemTime>,
+    file_reader: Option<Arc<FileReaderPlugin>>,
+    near: Option<Arc<NearSearch>>,  // 由请求参数 near 编译得到
+    exclude_globs: Option<GlobSet>,  // 由请求参数 exclude_file_patterns 编译得到
+    byte_pattern: Option<Arc<memmem::Finder<'static>>>,  // mode=bytes 时由 query 解析得到
 }
 
-impl AppConfig {
-    fn from_env() -> Self {
-        let max_results = env::var("MAX_RESULTS")
-            .ok()
-            .and_then(|v| v.parse().ok())
-            .unwrap_or(DEFAULT_MAX_RESULTS);
-
-        let ignored_folders = env::var("IGNORED_FOLDERS")
-            .unwrap_or_else(|_| "target,.git,node_modules,dist,build".to_string())
-            .split(',')
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
-            .collect();
+/// 外部文件读取插件 (FILE_READER_PLUGIN)，用于搜索 .docx、.pdf 这类非纯文本文件。
+/// 约定：以文件路径作为唯一参数调用插件，插件把 UTF-8 文本写到 stdout 并以 0 退出；
+/// 非 0 退出、输出不是 UTF-8 或超过 PLUGIN_TIMEOUT_MS 的文件都会被跳过并记入 warnings
+struct FileReaderPlugin {
+    command: PathBuf,
+    extensions: HashSet<String>,  // PLUGIN_EXTENSIONS，不带点
+    timeout: Duration,
+}
 
-        let allowed_extensions = env::var("ALLOWED_EXTENSIONS")
-            .unwrap_or_else(|_| "rs,toml,md,txt,js,ts,py,java,go,yml,yaml,json".to_string())
+impl FileReaderPlugin {
+    fn from_vars(var: &impl Fn(&str) -> Option<String>) -> Option<Self> {
+        let command = var("FILE_READER_PLUGIN").filter(|v| !v.is_empty())?;
+        let extensions: HashSet<String> = var("PLUGIN_EXTENSIONS")
+            .unwrap_or_else(|| "docx,pdf".to_string())
             .split(',')
             .map(|s| s.trim().replace(".", ""))
             .filter(|s| !s.is_empty())
             .collect();
+        let timeout = Duration::from_millis(
+            var("PLUGIN_TIMEOUT_MS")
+                .and_then(|v| v.trim().parse().ok())
+                .unwrap_or(DEFAULT_PLUGIN_TIMEOUT_MS),
+        );
+        Some(FileReaderPlugin { command: PathBuf::from(command), extensions, timeout })
+    }
+
+    fn handles(&self, file_path: &Path, case_sensitive: bool) -> bool {
+        file_path.extension().and_then(|ext| ext.to_str()).is_some_and(|ext| {
+            self.extensions.iter().any(|handled| {
+                if case_sensitive {
+                    handled == ext
+                } else {
+                    handled.eq_ignore_ascii_case(ext)
+                }
+            })
+        })
+    }
+
+    /// 运行插件读取文件内容；超时会杀掉插件进程
+    fn read(&self, file_path: &Path) -> io::Result<String> {
+        let mut child = Command::new(&self.command)
+            .arg(file_path)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        // 必须边运行边读 stdout，否则输出超过管道缓冲区时插件会阻塞到超时
+        let mut stdout = child.stdout.take().ok_or_else(|| io::Error::other("plugin stdout unavailable"))?;
+        let reader = thread::spawn(move || {
+            let mut output = Vec::new();
+            stdout.read_to_end(&mut output).map(|_| output)
+        });
+
+        let deadline = Instant::now() + self.timeout;
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if Instant::now() >= deadline {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!("file reader plugin timed out after {}ms", self.timeout.as_millis()),
+                ));
+            }
+            thread::sleep(Duration::from_millis(10));
+        };
+
+        let output = reader
+            .join()
+            .map_err(|_| io::Error::other("plugin output reader panicked"))??;
+        if !status.success() {
+            return Err(io::Error::other(format!("file reader plugin exited with {}", status)));
+        }
+        String::from_utf8(output).map_err(|_| {
+            io::Error::new(io::ErrorKind::InvalidData, "file reader plugin output is not valid UTF-8")
+        })
+    }
+}
+
+/// codesearcher.config.json：各项都可省略。
+/// 优先级：内置默认值 < 配置文件 < 环境变量 (MAX_RESULTS、IGNORED_FOLDERS、ALLOWED_EXTENSIONS) < 请求参数
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    max_results: Option<usize>,
+    ignored_folders: Option<Vec<String>>,
+    allowed_extensions: Option<Vec<String>>,
+    context_lines: Option<usize>,
+    max_file_size: Option<usize>,
+}
+
+impl ConfigFile {
+    /// 先找项目根目录，再找可执行文件所在目录，只读取找到的第一个；都没有时返回空配置。
+    /// 文件存在但无法解析 (包括拼错的字段名) 时报错，而不是静默使用默认值
+    fn load(project_base: &Path) -> Result<Self, String> {
+        let exe_dir = env::current_exe().ok().and_then(|exe| exe.parent().map(Path::to_path_buf));
+        let Some(path) = std::iter::once(project_base.to_path_buf())
+            .chain(exe_dir)
+            .map(|dir| dir.join(CONFIG_FILE_NAME))
+            .find(|path| path.is_file())
+        else {
+            return Ok(ConfigFile::default());
+        };
+        let json = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read config file {}: {}", path.display(), e))?;
+        serde_json::from_str(&json).map_err(|e| format!("Invalid config file {}: {}", path.display(), e))
+    }
+}
+
+impl AppConfig {
+    fn from_env(project_base: &Path) -> Result<Self, String> {
+        Self::from_vars(project_base, |name| env::var(name).ok())
+    }
+
+    /// 与 from_env 相同，但配置变量通过 var 读取，单元测试借此不受运行环境中的变量影响
+    fn from_vars(project_base: &Path, var: impl Fn(&str) -> Option<String>) -> Result<Self, String> {
+        let file = ConfigFile::load(project_base)?;
+
+        let max_results = var("MAX_RESULTS")
+            .and_then(|v| v.parse().ok())
+            .or(file.max_results)
+            .unwrap_or(DEFAULT_MAX_RESULTS);
+
+        let ignored_folders = match var("IGNORED_FOLDERS") {
+            Some(v) => v.split(',').map(str::to_string).collect(),
+            None => file.ignored_folders.unwrap_or_else(|| {
+                ["target", ".git", "node_modules", "dist", "build"].map(str::to_string).to_vec()
+            }),
+        }
+        .into_iter()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect();
+
+        let mut allowed_extensions: HashSet<String> = match var("ALLOWED_EXTENSIONS") {
+            Some(v) => v.split(',').map(str::to_string).collect(),
+            None => file.allowed_extensions.unwrap_or_else(|| {
+                ["rs", "toml", "md", "txt", "js", "ts", "py", "java", "go", "yml", "yaml", "json"]
+                    .map(str::to_string)
+                    .to_vec()
+            }),
+        }
+        .into_iter()
+        .map(|s| s.trim().replace(".", ""))
+        .filter(|s| !s.is_empty())
+        .collect();
+
+        // 插件能读取的扩展名同样参与扩展名过滤 (白名单为空表示不限制，不需要再加)
+        let file_reader = FileReaderPlugin::from_vars(&var);
+        if let Some(plugin) = &file_reader {
+            if !allowed_extensions.is_empty() {
+                allowed_extensions.extend(plugin.extensions.iter().cloned());
+            }
+        }
+
+        // 没有扩展名的构建和部署文件按完整文件名 (区分大小写) 放行
+        let always_include_filenames = match var("ALWAYS_INCLUDE_FILENAMES") {
+            Some(v) => v.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect(),
+            None => DEFAULT_ALWAYS_INCLUDE_FILENAMES.map(str::to_string).into_iter().collect(),
+        };
 
-        AppConfig {
+        let ignore_test_files = var("IGNORE_TEST_FILES")
+            .map(|v| matches!(v.trim().to_lowercase().as_str(), "true" | "1"))
+            .unwrap_or(false);
+
+        let cache_max_size = var("CODESEARCHER_CACHE_MAX_SIZE_MB")
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .unwrap_or(DEFAULT_CACHE_MAX_SIZE_MB)
+            * 1024
+            * 1024;
+
+        let max_total_bytes_searched = var("MAX_TOTAL_BYTES_SEARCHED")
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(0);
+
+        let threads = var("CODESEARCHER_THREADS")
+            .and_then(|v| v.trim().parse().ok())
+            .filter(|&n: &usize| n > 0)
+            .unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get()).min(MAX_DEFAULT_THREADS));
+
+        let slow_file_threshold = Duration::from_millis(
+            var("CODESEARCHER_SLOW_FILE_THRESHOLD_MS")
+                .and_then(|v| v.trim().parse().ok())
+                .unwrap_or(DEFAULT_SLOW_FILE_THRESHOLD_MS),
+        );
+
+        Ok(AppConfig {
             max_results,
+            context_lines: file.context_lines.unwrap_or(DEFAULT_CONTEXT_LINES),
+            max_file_size: file.max_file_size.unwrap_or(DEFAULT_MAX_FILE_SIZE),
+            max_total_bytes_searched,
+            threads,
             ignored_folders,
             allowed_extensions,
+            always_include_filenames,
+            ignore_test_files,
+            case_sensitive_paths: !cfg!(any(target_os = "macos", target_os = "windows")),
+            cache_max_size,
+            slow_file_threshold,
+            modified_after: None,
+            modified_before: None,
+            file_reader: file_reader.map(Arc::new),
+            near: None,
+            exclude_globs: None,
+            byte_pattern: None,
+        })
+    }
+}
+
+/// 把 file_types 展开为扩展名集合，再与 ALLOWED_EXTENSIONS 白名单 (非空时) 取交集，
+/// 结果直接替换 allowed_extensions，沿用原有的扩展名过滤逻辑
+fn resolve_file_types(
+    file_types: &[String],
+    type_definitions: Option<&HashMap<String, Vec<String>>>,
+    allowed_extensions: &HashSet<String>,
+) -> Result<HashSet<String>, String> {
+    let mut types: BTreeMap<String, Vec<String>> = FILE_TYPES
+        .iter()
+        .map(|(name, exts)| (name.to_string(), exts.iter().map(|e| e.to_string()).collect()))
+        .collect();
+    if let Some(custom) = type_definitions {
+        for (name, exts) in custom {
+            types.insert(name.clone(), exts.iter().map(|e| e.trim().replace(".", "")).collect());
+        }
+    }
+
+    let mut extensions = HashSet::new();
+    for name in file_types {
+        match types.get(name) {
+            Some(exts) => extensions.extend(exts.iter().cloned()),
+            None => {
+                let available: Vec<&str> = types.keys().map(String::as_str).collect();
+                return Err(format!(
+                    "Unknown file type: {} (available: {})",
+                    name,
+                    available.join(", ")
+                ));
+            }
         }
     }
+    if !allowed_extensions.is_empty() {
+        extensions.retain(|ext| allowed_extensions.contains(ext));
+    }
+    // 空集合在过滤逻辑里表示不限制，这里必须报错而不是静默搜索全部文件
+    if extensions.is_empty() {
+        return Err(format!(
+            "file_types {} match no extension allowed by ALLOWED_EXTENSIONS",
+            file_types.join(", ")
+        ));
+    }
+    Ok(extensions)
 }
 
 fn find_project_root() -> PathBuf {
+    // The host passes the project root explicitly; it takes precedence over marker discovery
+    if let Some(base) = env::var_os("PROJECT_BASE_PATH").map(PathBuf::from) {
+        if base.is_dir() {
+            return base;
+        }
+    }
+
     // Start from the current working directory
     if let Ok(mut path) = env::current_dir() {
         // Search up to 5 levels for common project markers
@@ -128,6 +1182,15 @@ fn find_project_root() -> PathBuf {
 }
 
 fn main() {
+    // --schema：输出 InputArgs 的 JSON Schema 供编辑器插件做补全和校验
+    if env::args().skip(1).any(|arg| arg == "--schema") {
+        if let Ok(json) = serde_json::to_string_pretty(&schema_for!(InputArgs)) {
+            println!("{}", json);
+        }
+        return;
+    }
+
+    let started = Instant::now();
     let mut buffer = String::new();
     if let Err(e) = io::stdin().read_to_string(&mut buffer) {
         print_error(format!("Failed to read stdin: {}", e));
@@ -141,183 +1204,3144 @@ fn main() {
             return;
         }
     };
+    if let Some(format) = OUTPUT_FORMATS.iter().find(|&&format| args.format.as_deref() == Some(format)) {
+        let _ = OUTPUT_FORMAT.set(format);
+    }
 
-    let config = AppConfig::from_env();
-    
-    let regex = match build_regex(&args) {
-        Ok(re) => re,
-        Err(e) => {
-            print_error(format!("Invalid regex: {}", e));
+    // 优先级：请求参数 project_base > PROJECT_BASE_PATH > 按标记文件自动探测
+    let base_path = match args.project_base.as_deref() {
+        Some(base) if !Path::new(base).is_dir() => {
+            print_error(format!("Invalid project_base: {} is not an existing directory", base));
             return;
         }
+        Some(base) => PathBuf::from(base),
+        None => find_project_root(),
     };
 
-    let base_path = find_project_root();
-    
-    let search_root = match args.search_path.as_ref() {
-        Some(p) => base_path.join(p),
-        None => base_path.clone(),
+    let mut config = match AppConfig::from_env(&base_path) {
+        Ok(config) => config,
+        Err(e) => {
+            print_error(e);
+            return;
+        }
     };
+    if let Some(context_lines) = args.context_lines {
+        config.context_lines = context_lines;
+    }
+    if let Some(max_file_size) = args.max_file_size {
+        config.max_file_size = max_file_size;
+    }
+    if let Some(max_total_bytes_searched) = args.max_total_bytes_searched {
+        config.max_total_bytes_searched = max_total_bytes_searched;
+    }
+    if let Some(threads) = args.threads.filter(|&n| n > 0) {
+        config.threads = threads;
+    }
+    if let Some(limit) = args.limit.filter(|&limit| limit > 0) {
+        config.max_results = limit;
+    }
+    if let Some(case_sensitive_paths) = args.case_sensitive_paths {
+        config.case_sensitive_paths = case_sensitive_paths;
+    }
+
+    if let Some(mode) = args.mode.as_deref() {
+        if !SEARCH_MODES.contains(&mode) {
+            print_error(format!(
+                "Invalid mode: {} (expected one of {})",
+                mode,
+                SEARCH_MODES.join(", ")
+            ));
+            return;
+        }
+    }
+    if args.mode.as_deref() == Some("bytes") {
+        let conflict = [
+            ("queries", args.queries.is_some()),
+            ("near", args.near.is_some()),
+            ("query_expansion", args.query_expansion.is_some()),
+            ("use_regex", args.use_regex),
+            ("whole_word", args.whole_word),
+            ("invert_match", args.invert_match),
+            ("ignore_comments", args.ignore_comments),
+            ("stream", args.stream),
+            ("merge_context", args.merge_context),
+            ("group_by", args.group_by.is_some()),
+            ("line_start/line_end", args.line_start.is_some() || args.line_end.is_some()),
+            ("replace", args.replace.is_some()),
+            ("enclosing_symbol", args.enclosing_symbol),
+            ("format other than \"json\"", args.format.as_deref().is_some_and(|format| format != "json")),
+        ]
+        .into_iter()
+        .find(|&(_, set)| set);
+        if let Some((name, _)) = conflict {
+            print_error(format!("mode \"bytes\" cannot be used together with {}", name));
+            return;
+        }
+        let Some(query) = args.query.as_deref() else {
+            print_error("mode \"bytes\" requires query".to_string());
+            return;
+        };
+        match bytes::parse_hex(query) {
+            Ok(pattern) => config.byte_pattern = Some(Arc::new(memmem::Finder::new(&pattern).into_owned())),
+            Err(e) => {
+                print_error(e);
+                return;
+            }
+        }
+        // 要找的通常是图片、压缩包这类不在文本扩展名白名单中的文件；file_types 仍可限定范围
+        config.allowed_extensions.clear();
+    }
+
+    if let Some(file_types) = &args.file_types {
+        match resolve_file_types(file_types, args.type_definitions.as_ref(), &config.allowed_extensions) {
+            Ok(extensions) => {
+                // file_types 明确指定了要搜索的语言，不再额外放行 Makefile 等文件
+                config.allowed_extensions = extensions;
+                config.always_include_filenames.clear();
+            }
+            Err(e) => {
+                print_error(e);
+                return;
+            }
+        }
+    }
+
+    if let Some(style) = args.comment_style.as_deref() {
+        if !COMMENT_STYLES.contains(&style) {
+            print_error(format!(
+                "Invalid comment_style: {} (expected one of {})",
+                style,
+                COMMENT_STYLES.join(", ")
+            ));
+            return;
+        }
+    }
+    
+    if let Some(mode) = args.word_mode.as_deref() {
+        if !WORD_MODES.contains(&mode) {
+            print_error(format!(
+                "Invalid word_mode: {} (expected one of {})",
+                mode,
+                WORD_MODES.join(", ")
+            ));
+            return;
+        }
+    }
+
+    if let Some(format) = args.line_format.as_deref() {
+        if !LINE_FORMATS.contains(&format) {
+            print_error(format!(
+                "Invalid line_format: {} (expected one of {})",
+                format,
+                LINE_FORMATS.join(", ")
+            ));
+            return;
+        }
+    }
+
+    if let Some(near) = &args.near {
+        let conflict = if !search_terms(&args).is_empty() {
+            Some("near cannot be used together with query or queries")
+        } else if args.invert_match {
+            Some("near cannot be used together with invert_match")
+        } else if args.replace.is_some() {
+            Some("near cannot be used together with replace")
+        } else if near.a.is_empty() || near.b.is_empty() {
+            Some("near.a and near.b must not be empty")
+        } else {
+            None
+        };
+        if let Some(message) = conflict {
+            print_error(message.to_string());
+            return;
+        }
+    } else if search_terms(&args).is_empty() {
+        print_error("query, queries or near is required".to_string());
+        return;
+    }
+
+    let require_all_terms = match args.operator.as_deref() {
+        None | Some("or") => false,
+        Some("and") => true,
+        Some(other) => {
+            print_error(format!("Invalid operator: {} (expected and, or)", other));
+            return;
+        }
+    };
+    if require_all_terms && args.query_expansion.is_some() {
+        print_error("query_expansion cannot be used with operator \"and\"".to_string());
+        return;
+    }
+    if require_all_terms && args.invert_match {
+        print_error("invert_match cannot be used with operator \"and\"".to_string());
+        return;
+    }
+
+    let patterns: Vec<&str> = search_terms(&args)
+        .into_iter()
+        .chain(args.query_expansion.iter().flatten().map(String::as_str))
+        .chain(args.near.iter().flat_map(|near| [near.a.as_str(), near.b.as_str()]))
+        .collect();
+    if let Err(e) = validate_patterns(&patterns, args.use_regex) {
+        print_error(e);
+        return;
+    }
+
+    let near = match args.near.as_ref().map(|near| build_near(near, &args)).transpose() {
+        Ok(near) => near,
+        Err(e) => {
+            print_error(regex_error_message(&e));
+            return;
+        }
+    };
+
+    let built = match &near {
+        Some(near) => Ok((near.combined.clone(), Vec::new())),
+        None => build_regex(&args).and_then(|re| Ok((re, build_term_regexes(&args)?))),
+    };
+    config.near = near.map(Arc::new);
+    let (regex, terms) = match built {
+        Ok(built) => built,
+        Err(e) => {
+            print_error(regex_error_message(&e));
+            return;
+        }
+    };
+
+    if args.files.is_some() && args.search_path.is_some() {
+        print_error("files and search_path cannot be used together".to_string());
+        return;
+    }
+
+    if args.line_start.is_some() || args.line_end.is_some() {
+        let conflict = if args.files.as_ref().is_none_or(|files| files.len() != 1) {
+            Some("line_start and line_end require files with exactly one entry")
+        } else if args.line_start.zip(args.line_end).is_some_and(|(start, end)| start > end) {
+            Some("line_start must not be greater than line_end")
+        } else if args.replace.is_some() {
+            Some("line_start and line_end cannot be used together with replace")
+        } else {
+            None
+        };
+        if let Some(message) = conflict {
+            print_error(message.to_string());
+            return;
+        }
+    }
+
+    if args.files.is_some() && args.tracked_only {
+        print_error("files and tracked_only cannot be used together".to_string());
+        return;
+    }
+
+    if args.invert_match && args.search_path.is_none() && args.files.is_none() {
+        print_error("invert_match requires search_path or files".to_string());
+        return;
+    }
+
+    if args.merge_context && args.stream {
+        print_error("merge_context cannot be used together with stream".to_string());
+        return;
+    }
+
+    if args.stream && args.max_per_extension.is_some_and(|cap| cap > 0) {
+        print_error("max_per_extension cannot be used together with stream".to_string());
+        return;
+    }
+
+    if let Some(mode) = args.output_mode.as_deref() {
+        if !OUTPUT_MODES.contains(&mode) {
+            print_error(format!(
+                "Invalid output_mode: {} (expected one of {})",
+                mode,
+                OUTPUT_MODES.join(", ")
+            ));
+            return;
+        }
+    }
+
+    if let Some(format) = args.format.as_deref() {
+        if !OUTPUT_FORMATS.contains(&format) {
+            print_error(format!(
+                "Invalid format: {} (expected one of {})",
+                format,
+                OUTPUT_FORMATS.join(", ")
+            ));
+            return;
+        }
+        if format != "json" {
+            let conflict = [
+                ("stream", args.stream),
+                ("group_by", args.group_by.is_some()),
+                ("merge_context", args.merge_context),
+                ("output_mode \"dirs\"", args.output_mode.as_deref() == Some("dirs")),
+                ("summary_only", args.summary_only == Some(true)),
+                ("replace", args.replace.is_some()),
+            ]
+            .into_iter()
+            .find(|&(_, set)| set);
+            if let Some((name, _)) = conflict {
+                print_error(format!("format {:?} cannot be used together with {}", format, name));
+                return;
+            }
+        }
+    }
+
+    // 只计数、不返回匹配行的两种模式
+    let counting_mode = if args.summary_only == Some(true) {
+        Some("summary_only")
+    } else if args.output_mode.as_deref() == Some("dirs") {
+        Some("output_mode \"dirs\"")
+    } else {
+        None
+    };
+    if let Some(mode) = counting_mode {
+        let conflict = [
+            ("output_mode \"dirs\"", args.summary_only == Some(true) && args.output_mode.as_deref() == Some("dirs")),
+            ("stream", args.stream),
+            ("group_by", args.group_by.is_some()),
+            ("merge_context", args.merge_context),
+            ("replace", args.replace.is_some()),
+        ]
+        .into_iter()
+        .find(|&(_, set)| set);
+        if let Some((name, _)) = conflict {
+            print_error(format!("{} cannot be used together with {}", mode, name));
+            return;
+        }
+    }
+
+    if args.replace.is_some() {
+        let conflict = if args.invert_match {
+            Some("replace cannot be used together with invert_match")
+        } else if args.stream {
+            Some("replace cannot be used together with stream")
+        } else if args.max_matches_per_file.is_some_and(|n| n > 0) {
+            // 替换作用于文件中的全部匹配，每个文件的结果上限只会让报告与实际改动不一致
+            Some("replace cannot be used together with max_matches_per_file")
+        } else if config.max_file_size == 0 {
+            Some("replace requires a max_file_size limit")
+        } else {
+            None
+        };
+        if let Some(message) = conflict {
+            print_error(message.to_string());
+            return;
+        }
+    }
+
+    if !SORT_MODES.contains(&args.sort.as_str()) {
+        print_error(format!(
+            "Invalid sort: {} (expected one of {})",
+            args.sort,
+            SORT_MODES.join(", ")
+        ));
+        return;
+    }
+
+    if let Some(mode) = args.group_by.as_deref() {
+        if !GROUP_BY_MODES.contains(&mode) {
+            print_error(format!(
+                "Invalid group_by: {} (expected one of {})",
+                mode,
+                GROUP_BY_MODES.join(", ")
+            ));
+            return;
+        }
+        if args.stream {
+            print_error("group_by cannot be used together with stream".to_string());
+            return;
+        }
+    }
+
+    for (name, value, slot) in [
+        ("modified_after", &args.modified_after, &mut config.modified_after),
+        ("modified_before", &args.modified_before, &mut config.modified_before),
+    ] {
+        if let Some(value) = value {
+            match parse_time_filter(value, SystemTime::now()) {
+                Some(time) => *slot = Some(time),
+                None => {
+                    print_error(format!(
+                        "Invalid {}: {} (expected ISO-8601 timestamp or relative duration like 48h)",
+                        name, value
+                    ));
+                    return;
+                }
+            }
+        }
+    }
+
+    if let Some(patterns) = &args.exclude_file_patterns {
+        match build_exclude_globs(patterns, config.case_sensitive_paths) {
+            Ok(globs) => config.exclude_globs = Some(globs),
+            Err(e) => {
+                print_error(e);
+                return;
+            }
+        }
+    }
+
+    let search_root = match args.search_path.as_ref() {
+        Some(p) => base_path.join(p),
+        None => base_path.clone(),
+    };
+
+    // 防止通过 ..、绝对路径或指向外部的符号链接读取项目根目录之外的文件
+    if args.search_path.is_some() {
+        match is_within_base(&base_path, &search_root) {
+            Ok(true) => {}
+            Ok(false) => {
+                print_error("search_path escapes project root".to_string());
+                return;
+            }
+            Err(e) => {
+                print_error(format!("Invalid search_path: {}", e));
+                return;
+            }
+        }
+    }
+
+    // ignore_file 的内容和 mtime 都会被读取 (后者记入遍历缓存)，同样不能指向项目根目录之外
+    if let Some(ignore_file) = &args.ignore_file {
+        match is_within_base(&base_path, &base_path.join(ignore_file)) {
+            Ok(true) => {}
+            Ok(false) => {
+                print_error("ignore_file escapes project root".to_string());
+                return;
+            }
+            Err(e) => {
+                print_error(format!("Invalid ignore_file {}: {}", ignore_file, e));
+                return;
+            }
+        }
+    }
+
+    // 缓存目录首次使用时可能还不存在，按最近的已存在上级目录检查；之后的淘汰、缓存读写和遍历缓存都在其中进行
+    if let Some(dir) = &args.cache_dir {
+        match is_creatable_within_base(&base_path, &base_path.join(dir)) {
+            Ok(true) => {}
+            Ok(false) => {
+                print_error("cache_dir escapes project root".to_string());
+                return;
+            }
+            Err(e) => {
+                print_error(format!("Invalid cache_dir: {}", e));
+                return;
+            }
+        }
+    }
+
+    let cache_stats = args
+        .cache_dir
+        .as_ref()
+        .map(|dir| evict_cache(&base_path, &cache_root(&base_path, dir), config.cache_max_size));
+
+    match search_in_directory(&search_root, &regex, &terms, require_all_terms, &config, &args, &base_path) {
+        Ok(SearchOutcome { truncated, truncated_reason, total_count, timed_out, stats, warnings, exclusions, threads, .. })
+            if args.stream =>
+        {
+            print_stream_record(&StreamRecord::Summary {
+                truncated,
+                truncated_reason,
+                total_count,
+                total_matches: stats.total_matches,
+                files_scanned: stats.files_scanned,
+                files_matched: stats.files_matched,
+                files_skipped: stats.files_skipped,
+                duplicates_skipped: stats.duplicates_skipped,
+                duplicate_results: (stats.duplicate_results > 0).then_some(stats.duplicate_results),
+                elapsed_ms: started.elapsed().as_millis() as u64,
+                timed_out,
+                warnings,
+                warnings_dropped: (stats.warnings_dropped > 0).then_some(stats.warnings_dropped),
+                long_lines_skipped: (stats.long_lines_skipped > 0).then_some(stats.long_lines_skipped),
+                exclusions,
+                threads: args.explain.then_some(threads),
+            });
+            if args.print_stats == Some(true) {
+                print_stats_line(&stats, started.elapsed());
+            }
+        }
+        Ok(SearchOutcome {
+            results,
+            truncated,
+            truncated_reason,
+            total_count,
+            timed_out,
+            stats,
+            warnings,
+            replacements,
+            slow_files,
+            exclusions,
+            directories,
+            summary,
+            threads,
+            line_window,
+            walk_cache,
+        }) => {
+            let file_groups = (args.group_by.as_deref() == Some("file")).then(|| group_by_file(&results));
+            let grouped_result =
+                (args.group_by.as_deref() == Some("directory")).then(|| group_by_directory(&results));
+            let (result, blocks, byte_matches) = if args.merge_context {
+                (None, Some(merge_into_blocks(results)), None)
+            } else if directories.is_some() || summary.is_some() {
+                (None, None, None)
+            } else if config.byte_pattern.is_some() {
+                (None, None, Some(results.into_iter().map(ByteMatch::from).collect()))
+            } else {
+                (Some(results), None, None)
+            };
+            if args.format.as_deref() == Some("text") {
+                let truncated_suffix = truncated_reason.map(|reason| format!(" truncated={}", reason)).unwrap_or_default();
+                // 与 grep 相同，没有上下文行时不输出分隔行
+                let has_context =
+                    args.context_before.unwrap_or(config.context_lines) + args.context_after.unwrap_or(config.context_lines) > 0;
+                let separator = Some(args.context_separator.as_str()).filter(|separator| has_context && !separator.is_empty());
+                print!("{}", render_text(result.as_deref().unwrap_or_default(), separator));
+                for warning in query_warnings(&args, config.threads, truncated_reason, &stats) {
+                    eprintln!("[CodeSearcher] warning: {}", warning);
+                }
+                println!(
+                    "status=success matches={} files={} scanned={} elapsed={}ms{}",
+                    stats.total_matches,
+                    stats.files_matched,
+                    stats.files_scanned,
+                    started.elapsed().as_millis(),
+                    truncated_suffix
+                );
+                if args.print_stats == Some(true) {
+                    print_stats_line(&stats, started.elapsed());
+                }
+                return;
+            }
+            if args.format.as_deref() == Some("sarif") {
+                let terms: Vec<&str> = search_terms(&args)
+                    .into_iter()
+                    .chain(args.near.iter().flat_map(|near| [near.a.as_str(), near.b.as_str()]))
+                    .collect();
+                let notifications = truncated_reason
+                    .map(|reason| ("warning", format!("Results truncated: {}", reason)))
+                    .into_iter()
+                    .chain(warnings.iter().map(|w| ("warning", format!("{}: {}: {}", w.path, w.kind, w.message))))
+                    .chain(query_warnings(&args, config.threads, truncated_reason, &stats).into_iter().map(|w| ("note", w)))
+                    .collect();
+                // 结果路径相对规范化后的项目根目录 (见 display_path)
+                let canonical_base = base_path.canonicalize().unwrap_or_else(|_| base_path.clone());
+                let log = SarifLog::new(result.as_deref().unwrap_or_default(), &terms, &canonical_base, notifications);
+                if let Ok(json) = serde_json::to_string(&log) {
+                    println!("{}", json);
+                }
+                if args.print_stats == Some(true) {
+                    print_stats_line(&stats, started.elapsed());
+                }
+                return;
+            }
+            let output = Output {
+                status: "success".to_string(),
+                result,
+                blocks,
+                file_groups,
+                grouped_result,
+                directories,
+                summary,
+                line_window,
+                byte_matches,
+                files_modified: match args.replace {
+                    Some(_) if !args.dry_run => {
+                        Some(replacements.iter().filter(|r| r.modified).count())
+                    }
+                    _ => None,
+                },
+                replacements: args.replace.as_ref().map(|_| replacements),
+                truncated: if truncated { Some(true) } else { None },
+                truncated_reason,
+                total_count,
+                total_matches: Some(stats.total_matches),
+                files_scanned: Some(stats.files_scanned),
+                files_matched: Some(stats.files_matched),
+                files_skipped: Some(stats.files_skipped),
+                duplicates_skipped: Some(stats.duplicates_skipped),
+                duplicate_results: (stats.duplicate_results > 0).then_some(stats.duplicate_results),
+                elapsed_ms: Some(started.elapsed().as_millis() as u64),
+                search_root: Some(absolute_path_string(&search_root)),
+                project_base: Some(absolute_path_string(&base_path)),
+                timed_out: if timed_out { Some(true) } else { None },
+                warnings: if warnings.is_empty() { None } else { Some(warnings) },
+                query_warnings: Some(query_warnings(&args, config.threads, truncated_reason, &stats)).filter(|w| !w.is_empty()),
+                warnings_dropped: (stats.warnings_dropped > 0).then_some(stats.warnings_dropped),
+                long_lines_skipped: (stats.long_lines_skipped > 0).then_some(stats.long_lines_skipped),
+                slow_files: if slow_files.is_empty() { None } else { Some(slow_files) },
+                cache_stats,
+                walk_cache,
+                exclusions,
+                threads: args.explain.then_some(threads),
+                ..Default::default()
+            };
+            if let Ok(json) = serde_json::to_string(&output) {
+                println!("{}", json);
+            }
+            if args.print_stats == Some(true) {
+                print_stats_line(&stats, started.elapsed());
+            }
+        }
+        Err(e) => print_error(format!("Search failed: {}", e)),
+    }
+}
+
+/// 编译 exclude_file_patterns，去掉可选的 "!" 前缀。任何一个 glob 无效都报错，而不是静默忽略
+fn build_exclude_globs(patterns: &[String], case_sensitive: bool) -> Result<GlobSet, String> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = pattern.strip_prefix('!').unwrap_or(pattern);
+        match GlobBuilder::new(glob).case_insensitive(!case_sensitive).build() {
+            Ok(glob) => builder.add(glob),
+            Err(e) => return Err(format!("Invalid exclude_file_patterns entry {}: {}", pattern, e)),
+        };
+    }
+    builder
+        .build()
+        .map_err(|e| format!("Invalid exclude_file_patterns: {}", e))
+}
+
+/// 解析 modified_after/modified_before：RFC 3339 时间、不带时区的日期时间 (按 UTC)、
+/// 纯日期 (当天 0 点 UTC)，或 "48h" 这类相对 now 往前推的时长 (单位 s/m/h/d/w)
+fn parse_time_filter(value: &str, now: SystemTime) -> Option<SystemTime> {
+    let value = value.trim();
+    if let Some(unit) = value.chars().last().filter(|c| c.is_ascii_alphabetic()) {
+        if let Ok(amount) = value[..value.len() - 1].parse::<u64>() {
+            let seconds = match unit.to_ascii_lowercase() {
+                's' => 1,
+                'm' => 60,
+                'h' => 60 * 60,
+                'd' => 24 * 60 * 60,
+                'w' => 7 * 24 * 60 * 60,
+                _ => return None,
+            };
+            return now.checked_sub(Duration::from_secs(amount.checked_mul(seconds)?));
+        }
+    }
+
+    let timestamp = if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        time.timestamp_millis()
+    } else if let Ok(time) = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S"))
+    {
+        time.and_utc().timestamp_millis()
+    } else {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?.and_hms_opt(0, 0, 0)?.and_utc().timestamp_millis()
+    };
+    let offset = Duration::from_millis(timestamp.unsigned_abs());
+    if timestamp >= 0 {
+        UNIX_EPOCH.checked_add(offset)
+    } else {
+        UNIX_EPOCH.checked_sub(offset)
+    }
+}
+
+/// near 搜索的已编译形式
+struct NearSearch {
+    a: (String, Regex),
+    b: (String, Regex),
+    combined: Regex,  // a|b，作为 SearchContext.regex 参与缓存键和统计
+    distance: usize,
+}
+
+/// 编译 near 的两个搜索词，大小写按两个词统一判断 (与多搜索词的处理一致)
+fn build_near(near: &NearArgs, args: &InputArgs) -> Result<NearSearch, regex::Error> {
+    let terms = [near.a.as_str(), near.b.as_str()];
+    let case_sensitive = is_case_sensitive(&terms, args);
+    Ok(NearSearch {
+        a: (near.a.clone(), compile_terms(&[&near.a], args, case_sensitive)?),
+        b: (near.b.clone(), compile_terms(&[&near.b], args, case_sensitive)?),
+        combined: compile_terms(&terms, args, case_sensitive)?,
+        distance: near.distance,
+    })
+}
+
+/// 全部搜索词：query 在前，其后是 queries
+fn search_terms(args: &InputArgs) -> Vec<&str> {
+    args.query
+        .iter()
+        .chain(args.queries.iter().flatten())
+        .map(String::as_str)
+        .collect()
+}
+
+/// 按排序后的顺序，每种扩展名只保留前 cap 个结果；丢弃了结果时返回 true
+fn cap_per_extension(results: &mut Vec<SearchResult>, cap: usize) -> bool {
+    let before = results.len();
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    results.retain(|result| {
+        let count = counts.entry(extension_key(&result.file_path)).or_insert(0);
+        *count += 1;
+        *count <= cap
+    });
+    results.len() < before
+}
+
+/// max_per_extension 的分组键：小写扩展名，无扩展名的文件为空串
+fn extension_key(file_path: &str) -> String {
+    Path::new(file_path)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+/// query_warnings：搜索词过短、正则能匹配任意行，或因命中的文件超过一半而被 max_results 截断时，提示收窄查询；
+/// sort="none" 下多线程翻页时提示改用稳定的排序。threads 是请求参数与 CODESEARCHER_THREADS 合并后的线程数
+fn query_warnings(args: &InputArgs, threads: usize, truncated_reason: Option<&str>, stats: &SearchStats) -> Vec<String> {
+    let terms = search_terms(args)
+        .into_iter()
+        .chain(args.near.iter().flat_map(|near| [near.a.as_str(), near.b.as_str()]));
+    let mut warnings = Vec::new();
+    for term in terms {
+        if term.chars().count() < MIN_QUERY_LENGTH {
+            warnings.push(format!(
+                "query {:?} is shorter than {} characters and will match most lines; use a longer or more specific term",
+                term, MIN_QUERY_LENGTH
+            ));
+        } else if args.use_regex && is_wildcard_pattern(term) {
+            warnings.push(format!(
+                "regex {:?} matches every line; add literal text to narrow the search",
+                term
+            ));
+        }
+    }
+    if args.offset.is_some_and(|offset| offset > 0) && args.sort == "none" && threads > 1 {
+        warnings.push(
+            "sort \"none\" orders files by thread scheduling, so pages requested with offset may repeat or skip results; use sort \"path\" for pagination".to_string(),
+        );
+    }
+    if truncated_reason == Some("max_results") && stats.files_matched * 2 > stats.files_scanned {
+        warnings.push(format!(
+            "{} of {} searched files matched and results were truncated at max_results; the query is probably too broad",
+            stats.files_matched, stats.files_scanned
+        ));
+    }
+    warnings
+}
+
+/// 只由 . * + ? 和首尾锚点组成，或能匹配空字符串 (因而匹配任意行) 的正则
+fn is_wildcard_pattern(pattern: &str) -> bool {
+    let body = pattern.trim_start_matches('^').trim_end_matches('$');
+    (!body.is_empty() && body.chars().all(|c| matches!(c, '.' | '*' | '+' | '?')))
+        || Regex::new(pattern).is_ok_and(|re| re.is_match(""))
+}
+
+/// 搜索词和 query_expansion 组成的单一正则 (operator=or 的语义)，一次遍历即可找出任意词的匹配
+fn build_regex(args: &InputArgs) -> Result<Regex, regex::Error> {
+    let terms: Vec<&str> = search_terms(args)
+        .into_iter()
+        .chain(args.query_expansion.iter().flatten().map(String::as_str))
+        .collect();
+    compile_terms(&terms, args, is_case_sensitive(&terms, args))
+}
+
+/// 非正则、非整词查询绕过正则引擎，使用与 build_regex 相同的搜索词和大小写规则；
+/// 结果不能保证与正则一致时为 None (见 LiteralMatcher::new)
+fn build_literal_matcher(args: &InputArgs) -> Option<LiteralMatcher> {
+    if args.use_regex || args.whole_word || args.near.is_some() {
+        return None;
+    }
+    let terms: Vec<&str> = search_terms(args)
+        .into_iter()
+        .chain(args.query_expansion.iter().flatten().map(String::as_str))
+        .collect();
+    LiteralMatcher::new(&terms, is_case_sensitive(&terms, args))
+}
+
+/// 有多个搜索词时为每个词单独编译正则，用于 operator=and 的文件过滤和 matched_term。
+/// 大小写按全部搜索词统一判断，保证和组合正则的行为一致
+fn build_term_regexes(args: &InputArgs) -> Result<Vec<(String, Regex)>, regex::Error> {
+    let terms = search_terms(args);
+    if terms.len() < 2 {
+        return Ok(Vec::new());
+    }
+    let all_terms: Vec<&str> = terms
+        .iter()
+        .copied()
+        .chain(args.query_expansion.iter().flatten().map(String::as_str))
+        .collect();
+    let case_sensitive = is_case_sensitive(&all_terms, args);
+    terms
+        .into_iter()
+        .map(|term| Ok((term.to_string(), compile_terms(&[term], args, case_sensitive)?)))
+        .collect()
+}
+
+/// case_sensitive=true 总是区分大小写；smart_case 只会在搜索词含大写字面字符时把不区分升级为区分
+fn is_case_sensitive(terms: &[&str], args: &InputArgs) -> bool {
+    args.case_sensitive
+        || (args.smart_case && terms.iter().any(|term| has_uppercase_literal(term, args.use_regex)))
+}
+
+fn compile_terms(terms: &[&str], args: &InputArgs, case_sensitive: bool) -> Result<Regex, regex::Error> {
+    let word_mode = args.word_mode.as_deref().unwrap_or("unicode");
+    let alternatives: Vec<String> = terms
+        .iter()
+        .map(|term| {
+            let alternative = if args.use_regex {
+                format!("(?:{})", term)
+            } else {
+                regex::escape(term)
+            };
+            if args.whole_word {
+                with_word_boundaries(term, alternative, word_mode, args.use_regex)
+            } else {
+                alternative
+            }
+        })
+        .collect();
+    let pattern = match alternatives.as_slice() {
+        [single] => single.clone(),
+        _ => format!("(?:{})", alternatives.join("|")),
+    };
+
+    let pattern = if case_sensitive {
+        pattern
+    } else {
+        format!("(?i){}", pattern)
+    };
+
+    RegexBuilder::new(&pattern)
+        .size_limit(REGEX_SIZE_LIMIT)
+        .dfa_size_limit(REGEX_DFA_SIZE_LIMIT)
+        .build()
+}
+
+/// 编译前逐个检查搜索词：长度上限，以及正则模式下的语法。
+/// 语法错误报告在该搜索词中的字符位置 (从 1 开始) 和修改建议，而不是组合后整个正则的报错
+fn validate_patterns(patterns: &[&str], use_regex: bool) -> Result<(), String> {
+    for pattern in patterns {
+        let length = pattern.chars().count();
+        if length > MAX_PATTERN_LENGTH {
+            return Err(format!(
+                "Pattern too long: {} characters (max {})",
+                length, MAX_PATTERN_LENGTH
+            ));
+        }
+        if !use_regex {
+            continue;
+        }
+        if let Err(e) = regex_syntax::ast::parse::Parser::new().parse(pattern) {
+            let column = pattern[..e.span().start.offset].chars().count() + 1;
+            return Err(format!(
+                "Invalid regex {:?} at character {}: {}. Hint: {}",
+                pattern,
+                column,
+                e.kind(),
+                regex_hint(e.kind())
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn regex_hint(kind: &regex_syntax::ast::ErrorKind) -> &'static str {
+    use regex_syntax::ast::ErrorKind;
+    match kind {
+        ErrorKind::GroupUnclosed | ErrorKind::GroupUnopened => {
+            "parentheses are unbalanced; escape a literal parenthesis as \\( or \\)"
+        }
+        ErrorKind::ClassUnclosed => "unclosed character class; escape a literal bracket as \\[",
+        ErrorKind::RepetitionMissing => {
+            "nothing to repeat before *, + or ?; escape the symbol with \\ to match it literally"
+        }
+        ErrorKind::RepetitionCountUnclosed
+        | ErrorKind::RepetitionCountInvalid
+        | ErrorKind::RepetitionCountDecimalEmpty => "invalid {n,m} repetition; escape a literal brace as \\{",
+        ErrorKind::EscapeUnrecognized | ErrorKind::EscapeUnexpectedEof => {
+            "unknown escape sequence; write a literal backslash as \\\\"
+        }
+        ErrorKind::UnsupportedLookAround => "rewrite the pattern without (?=, (?!, (?<= or (?<!",
+        ErrorKind::UnsupportedBackreference => "rewrite the pattern without backreferences such as \\1",
+        _ => "set use_regex to false to search for the text literally",
+    }
+}
+
+/// 通过了 validate_patterns 之后剩下的编译错误 (主要是超出大小上限) 的说明
+fn regex_error_message(e: &regex::Error) -> String {
+    match e {
+        regex::Error::CompiledTooBig(limit) => format!(
+            "Pattern too complex: compiled regex exceeds the {} byte limit; simplify the pattern or use fewer terms",
+            limit
+        ),
+        _ => format!("Invalid regex: {}", e),
+    }
+}
+
+/// whole_word 时给单个搜索词加上词边界。边界都是零宽断言，匹配的起止位置 (match_column 等) 不受影响
+fn with_word_boundaries(term: &str, alternative: String, word_mode: &str, is_regex: bool) -> String {
+    match word_mode {
+        "none" => alternative,
+        // ASCII 词边界：é、中 这类字符和 - 一样视为分隔符。首尾字符本身是分隔符时该侧不加边界，
+        // 否则 (?-u:\b) 反而要求相邻字符是词字符，foo- 或 café 后面紧跟空格时匹配不到
+        "identifier" => {
+            let boundary = |c: Option<char>| {
+                if !is_regex && !c.is_some_and(|c| c.is_ascii_alphanumeric() || c == '_') {
+                    ""
+                } else {
+                    r"(?-u:\b)"
+                }
+            };
+            format!(
+                "{}{}{}",
+                boundary(term.chars().next()),
+                alternative,
+                boundary(term.chars().next_back())
+            )
+        }
+        _ => {
+            // 中日文、泰文等不用空格分词，字符之间没有 \b；正则搜索词无法判断首尾字符，仍然两侧都加
+            let boundary = |c: Option<char>| {
+                if !is_regex && c.is_some_and(is_unsegmented_char) {
+                    ""
+                } else {
+                    r"\b"
+                }
+            };
+            format!(
+                "{}{}{}",
+                boundary(term.chars().next()),
+                alternative,
+                boundary(term.chars().next_back())
+            )
+        }
+    }
+}
+
+/// 不用空格分词的文字：CJK 统一表意文字 (含扩展区和兼容区)、平假名、片假名、泰文
+fn is_unsegmented_char(c: char) -> bool {
+    matches!(
+        c,
+        '\u{3040}'..='\u{30FF}'
+            | '\u{3400}'..='\u{4DBF}'
+            | '\u{4E00}'..='\u{9FFF}'
+            | '\u{F900}'..='\u{FAFF}'
+            | '\u{0E00}'..='\u{0E7F}'
+            | '\u{20000}'..='\u{2FFFF}'
+    )
+}
+
+/// 判断 query 中是否有大写的字面字符 (smart_case 使用)
+/// 正则模式下跳过转义序列 (\S、\W、\p{Lu} 等)、分组名和 [:upper:] 这类字符类名，只看真正的字面字符
+fn has_uppercase_literal(query: &str, is_regex: bool) -> bool {
+    if !is_regex {
+        return query.chars().any(|c| c.is_uppercase());
+    }
+
+    let chars: Vec<char> = query.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => {
+                // 转义字母 (\S、\W、\B、\p{Lu}、\x41 …) 都是元字符，转义标点是非字母字面量，都不会是大写字面量
+                i += 1;
+                match chars.get(i) {
+                    Some('p' | 'P' | 'x' | 'u' | 'U') if chars.get(i + 1) == Some(&'{') => {
+                        while i < chars.len() && chars[i] != '}' {
+                            i += 1;
+                        }
+                    }
+                    Some('x') => i += 2,
+                    Some('p' | 'P') => i += 1,
+                    _ => {}
+                }
+            }
+            '(' if chars.get(i + 1) == Some(&'?') => {
+                // (?P<name>...) / (?<name>...) 的分组名以及 (?i) 这类标志
+                i += 2;
+                while i < chars.len() && !matches!(chars[i], ':' | ')' | '>') {
+                    i += 1;
+                }
+            }
+            '[' if chars.get(i + 1) == Some(&':') => {
+                while i < chars.len() && chars[i] != ']' {
+                    i += 1;
+                }
+            }
+            c if c.is_uppercase() => return true,
+            _ => {}
+        }
+        i += 1;
+    }
+    false
+}
+
+/// 物理文件标识：Unix 上是 (dev, inode)，其它平台退化为规范化路径
+#[cfg(unix)]
+type FileIdentity = (u64, u64);
+#[cfg(not(unix))]
+type FileIdentity = PathBuf;
+
+#[cfg(unix)]
+fn file_identity(_file_path: &Path, metadata: &fs::Metadata) -> FileIdentity {
+    use std::os::unix::fs::MetadataExt;
+    (metadata.dev(), metadata.ino())
+}
+
+#[cfg(not(unix))]
+fn file_identity(file_path: &Path, _metadata: &fs::Metadata) -> FileIdentity {
+    file_path.canonicalize().unwrap_or_else(|_| file_path.to_path_buf())
+}
+
+/// 单个文件搜索所需的只读参数，每个工作线程各持有一份
+#[derive(Clone)]
+struct SearchContext {
+    regex: Regex,
+    literal: Option<Arc<LiteralMatcher>>,  // 字面量快速路径，与 regex 的匹配结果相同
+    terms: Vec<(String, Regex)>,  // 多个搜索词时各自的正则，用于 matched_term
+    require_all_terms: bool,  // operator=and：文件必须包含全部搜索词
+    project_base: PathBuf,
+    canonical_base: Option<PathBuf>,  // 解析符号链接后的项目根目录，用于统一结果路径的写法
+    context_before: usize,
+    context_after: usize,
+    max_line_length: usize,
+    ignore_comments: bool,
+    comment_style: Option<String>,
+    line_format: &'static str,  // LINE_FORMATS 之一，已合并 preserve_whitespace
+    include_spans: bool,
+    capture_groups: bool,  // use_regex 且组合正则含捕获组
+    invert_match: bool,
+    max_matches_per_file: Option<usize>,
+    cache_dir: Option<PathBuf>,
+    max_file_size: Option<u64>,
+    max_total_bytes_searched: Option<u64>,
+    max_results: usize,  // 收满后工作线程只计数不再构造结果；需要排序时为 usize::MAX
+    replace: Option<Arc<ReplaceOptions>>,
+    file_replacements: Arc<Mutex<Vec<FileReplacement>>>,
+    deadline: Option<Instant>,
+    counters: Arc<ScanCounters>,
+    visited: Arc<Mutex<HashSet<FileIdentity>>>,
+    modified_after: Option<SystemTime>,
+    modified_before: Option<SystemTime>,
+    warnings: Arc<Mutex<Vec<SearchWarning>>>,
+    slow_file_threshold: Duration,
+    slow_files: Arc<Mutex<Vec<SlowFileReport>>>,
+    file_reader: Option<Arc<FileReaderPlugin>>,
+    case_sensitive_paths: bool,  // 与 FileFilters 一致，决定插件是否处理 .PDF 这类大写扩展名
+    absolute_paths: bool,
+    near: Option<Arc<NearSearch>>,
+    context_separator: Option<String>,  // 需要插入分隔行时才有值
+    enclosing_symbol: bool,
+    file_metadata: bool,
+    max_scan_line_length: Option<usize>,
+    exclusions: Option<Arc<Mutex<BTreeMap<&'static str, ExclusionSummary>>>>,  // 仅 explain 时存在
+    file_match_counts: Option<Arc<Mutex<FileMatchCounts>>>,  // 仅 output_mode=dirs 时存在
+    matching_files: Option<Arc<Mutex<HashSet<PathBuf>>>>,  // 仅 summary_only 时存在，只需要有匹配的文件本身
+    reported_files: Arc<Mutex<HashSet<String>>>,  // 已计入匹配的文件 (显示路径)，用于丢弃以同一路径重复到达的文件
+    line_range: Option<(usize, usize)>,  // 请求的 line_start/line_end (1 起，含两端)，缺省一端时不限制该端
+    line_window: Arc<Mutex<Option<LineWindow>>>,  // 搜索到该文件后记录钳制后的实际范围
+    byte_pattern: Option<Arc<memmem::Finder<'static>>>,  // mode=bytes 时按字节搜索，不走文本流程
+}
+
+impl SearchContext {
+    /// 是否已超出时间预算。第一个发现超时的线程置位共享标志，其余线程之后只读标志
+    fn out_of_time(&self) -> bool {
+        if self.counters.timed_out.load(Ordering::Relaxed) {
+            return true;
+        }
+        if self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            self.counters.timed_out.store(true, Ordering::Relaxed);
+            return true;
+        }
+        false
+    }
+
+    /// 遍历是否应当停止：超时，或已读取的字节数达到 max_total_bytes_searched。
+    /// 字节上限只阻止开始新文件，正在搜索的文件会完整搜索完
+    fn should_stop(&self) -> bool {
+        self.out_of_time() || self.counters.byte_limit_reached.load(Ordering::Relaxed)
+    }
+
+    /// 计入本文件读取的字节数；此前已达到上限时返回 false，调用方不再搜索该文件
+    fn reserve_bytes(&self, len: u64) -> bool {
+        let Some(max) = self.max_total_bytes_searched else {
+            return true;
+        };
+        if self.counters.bytes_searched.fetch_add(len, Ordering::Relaxed) >= max {
+            self.counters.byte_limit_reached.store(true, Ordering::Relaxed);
+            return false;
+        }
+        true
+    }
+
+    /// 搜索单个文件，匹配结果发送给汇总线程。
+    /// 过大或非 UTF-8 (二进制) 的文件计入 files_skipped；其它读取失败以 Err 返回，由调用方决定如何处理。
+    /// 耗时超过 slow_file_threshold 的文件记入 slow_files
+    fn search_file(&self, file_path: &Path, tx: &mpsc::SyncSender<SearchResult>) -> io::Result<()> {
+        let started = Instant::now();
+        let match_count = self.scan_file(file_path, tx)?;
+        let elapsed = started.elapsed();
+        if elapsed >= self.slow_file_threshold {
+            if let Ok(mut slow_files) = self.slow_files.lock() {
+                slow_files.push(SlowFileReport {
+                    path: self.display_path(file_path),
+                    duration_ms: elapsed.as_millis() as u64,
+                    match_count,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// 搜索遍历得到的文件，读取失败计入 files_skipped 并记为警告
+    fn search_walked_file(&self, file_path: &Path, tx: &mpsc::SyncSender<SearchResult>) {
+        if let Err(e) = self.search_file(file_path, tx) {
+            self.counters.files_skipped.fetch_add(1, Ordering::Relaxed);
+            self.warn(self.display_path(file_path), warning_kind(&e), e.to_string());
+        }
+    }
+
+    /// search_file 的实际工作，返回本文件的匹配数 (跳过的文件为 0)
+    fn scan_file(&self, file_path: &Path, tx: &mpsc::SyncSender<SearchResult>) -> io::Result<usize> {
+        let counters = &self.counters;
+        let metadata = fs::metadata(file_path)?;
+        // 同一物理文件只搜索一次 (符号链接目录、重复的文件列表项)
+        let first_visit = self
+            .visited
+            .lock()
+            .map(|mut visited| visited.insert(file_identity(file_path, &metadata)))
+            .unwrap_or(true);
+        if !first_visit {
+            counters.duplicates_skipped.fetch_add(1, Ordering::Relaxed);
+            return Ok(0);
+        }
+        if self.max_file_size.is_some_and(|max| metadata.len() > max) {
+            self.skip(file_path, "size_limit");
+            return Ok(0);
+        }
+        if !self.in_modified_range(&metadata) {
+            self.skip(file_path, "modified_range");
+            return Ok(0);
+        }
+
+        // 替换需要文件内容，不能走缓存；缓存的是整个文件的结果，限定行范围时也不能用。
+        // 缓存键不区分文本和字节搜索，字节搜索也不走缓存
+        let cache_path = self
+            .cache_path(file_path, &metadata)
+            .filter(|_| self.replace.is_none() && self.line_range.is_none() && self.byte_pattern.is_none());
+        if let Some(cached) = cache_path.as_deref().and_then(read_cached_results) {
+            counters.files_scanned.fetch_add(1, Ordering::Relaxed);
+            let match_count = cached.len();
+            self.deliver(file_path, &metadata, cached, match_count, tx);
+            return Ok(match_count);
+        }
+
+        if !self.reserve_bytes(metadata.len()) {
+            return Ok(0);
+        }
+        if let Some(pattern) = &self.byte_pattern {
+            return self.scan_bytes(file_path, &metadata, pattern, tx);
+        }
+        let file_reader = self.file_reader.as_deref().filter(|plugin| plugin.handles(file_path, self.case_sensitive_paths));
+        let raw_content = match file_reader.map_or_else(|| fs::read_to_string(file_path), |plugin| plugin.read(file_path)) {
+            Ok(c) => c,
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                self.skip(file_path, "binary");
+                self.warn(self.display_path(file_path), "decode_error", e.to_string());
+                return Ok(0);
+            }
+            Err(e) => return Err(e),
+        };
+        counters.files_scanned.fetch_add(1, Ordering::Relaxed);
+        // Windows 编辑器保存的文件常以 BOM 开头，不去掉会出现在第 1 行的 line_content 里，列号也会多算一位
+        let content = raw_content.strip_prefix(UTF8_BOM).unwrap_or(&raw_content);
+        // 比上限短的文件不可能有超长行，省掉逐行统计
+        if let Some(max) = self.max_scan_line_length.filter(|&max| content.len() > max) {
+            let long_lines = content.lines().filter(|line| line.len() > max).count();
+            counters.long_lines_skipped.fetch_add(long_lines, Ordering::Relaxed);
+        }
+
+        let comment_prefixes = if self.ignore_comments {
+            comment_prefixes(self.comment_style.as_deref(), file_path)
+        } else {
+            &[]
+        };
+        let window = self.line_range.map(|range| LineWindow::clamp(range, content.lines().count()));
+        if let (Some(window), Ok(mut slot)) = (window, self.line_window.lock()) {
+            *slot = Some(window);
+        }
+
+        // 整个文件中都没有出现时不必再逐行匹配；字面量查询下这一次查找通常比逐行快得多
+        if !self.invert_match && self.literal.is_some() && !self.is_match(content) {
+            return Ok(0);
+        }
+
+        if self.require_all_terms && !self.contains_all_terms(content, comment_prefixes, window) {
+            return Ok(0);
+        }
+
+        // 插件输出的是提取出的文本，不能写回原文件
+        if let Some(replace) = self.replace.as_ref().filter(|_| file_reader.is_none()) {
+            self.replace_in_file(replace, file_path, &raw_content, &metadata, comment_prefixes);
+        }
+
+        // 结果已经收满时只计数，不再构造 SearchResult
+        if counters.collected.load(Ordering::Relaxed) >= self.max_results {
+            let match_count = count_matches(content, self, comment_prefixes, window);
+            self.record_matches(file_path, match_count);
+            Ok(match_count)
+        } else {
+            let file_results = search_in_content(content, file_path, self, comment_prefixes, window);
+            // 达到单文件上限时 file_results 只是一部分，重新计数以保证 total_matches 准确
+            let capped = self.max_matches_per_file.is_some_and(|cap| file_results.len() >= cap);
+            let match_count = if capped {
+                count_matches(content, self, comment_prefixes, window)
+            } else {
+                file_results.len()
+            };
+            // 超时中断或被单文件上限截断的结果不完整，不能写入缓存
+            if let Some(cache_path) = cache_path.as_ref().filter(|_| !capped && !self.out_of_time()) {
+                write_cached_results(cache_path, &file_results);
+            }
+            self.deliver(file_path, &metadata, file_results, match_count, tx);
+            Ok(match_count)
+        }
+    }
+
+    /// mode=bytes：按原始字节搜索，不经过文件读取插件，也不要求是 UTF-8
+    fn scan_bytes(
+        &self,
+        file_path: &Path,
+        metadata: &fs::Metadata,
+        pattern: &memmem::Finder,
+        tx: &mpsc::SyncSender<SearchResult>,
+    ) -> io::Result<usize> {
+        let content = fs::read(file_path)?;
+        self.counters.files_scanned.fetch_add(1, Ordering::Relaxed);
+        let match_count = pattern.find_iter(&content).count();
+        // 结果已经收满时只计数，不再构造 SearchResult
+        if self.counters.collected.load(Ordering::Relaxed) >= self.max_results {
+            self.record_matches(file_path, match_count);
+            return Ok(match_count);
+        }
+        let shown_path = self.display_path(file_path);
+        let file_results = pattern
+            .find_iter(&content)
+            .take(self.max_matches_per_file.unwrap_or(usize::MAX))
+            .map(|offset| bytes::byte_result(&content, offset, pattern.needle().len(), &shown_path))
+            .collect();
+        self.deliver(file_path, metadata, file_results, match_count, tx);
+        Ok(match_count)
+    }
+
+    /// 计入统计，并在结果未收满时把本文件的结果逐条发送给汇总线程
+    /// 分隔行和文件元数据在这里而不是 search_in_content 中添加，缓存里保存的始终是不带它们的结果
+    fn deliver(
+        &self,
+        file_path: &Path,
+        metadata: &fs::Metadata,
+        mut file_results: Vec<SearchResult>,
+        match_count: usize,
+        tx: &mpsc::SyncSender<SearchResult>,
+    ) {
+        if let Some(separator) = &self.context_separator {
+            insert_context_separators(&mut file_results, separator);
+        }
+        if self.file_metadata {
+            add_file_metadata(&mut file_results, file_path, metadata);
+        }
+        let n = file_results.len();
+        let mtime = metadata.modified().ok();
+        for result in &mut file_results {
+            result.file_match_count = n;
+            result.file_mtime = mtime;
+        }
+        if self.record_matches(file_path, match_count)
+            && n > 0
+            && self.counters.collected.load(Ordering::Relaxed) < self.max_results
+        {
+            self.counters.collected.fetch_add(n, Ordering::Relaxed);
+            for result in file_results {
+                // 汇总线程提前退出时不再发送
+                if tx.send(result).is_err() {
+                    break;
+                }
+                let pending = self.counters.pending_results.fetch_add(1, Ordering::Relaxed) + 1;
+                self.counters.peak_pending_results.fetch_max(pending.max(0) as usize, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// 替换单个文件中 (非注释行上) 的全部匹配，保留原有换行符和 BOM。
+    /// dry_run 时只生成 diff；否则写临时文件再重命名覆盖原文件，并保留原文件权限
+    fn replace_in_file(
+        &self,
+        replace: &ReplaceOptions,
+        file_path: &Path,
+        raw_content: &str,
+        metadata: &fs::Metadata,
+        comment_prefixes: &[&str],
+    ) {
+        let bom = if raw_content.starts_with(UTF8_BOM) { UTF8_BOM } else { "" };
+        let content = &raw_content[bom.len()..];
+        let mut replaced = String::with_capacity(content.len());
+        let mut count = 0;
+        for segment in content.split_inclusive('\n') {
+            let body = segment.trim_end_matches(['\r', '\n']);
+            let line_ending = &segment[body.len()..];
+            if self.is_skipped_line(body, comment_prefixes) {
+                replaced.push_str(segment);
+                continue;
+            }
+            let n = self.regex.find_iter(body).count();
+            if n == 0 {
+                replaced.push_str(segment);
+                continue;
+            }
+            count += n;
+            let new_body = if replace.expand_captures {
+                self.regex.replace_all(body, replace.replacement.as_str())
+            } else {
+                self.regex.replace_all(body, regex::NoExpand(&replace.replacement))
+            };
+            replaced.push_str(&new_body);
+            replaced.push_str(line_ending);
+        }
+        if count == 0 {
+            return;
+        }
+
+        let relative_path = pathdiff::diff_paths(file_path, &self.project_base)
+            .unwrap_or_else(|| file_path.to_path_buf())
+            .to_string_lossy()
+            .into_owned();
+        let mut outcome = FileReplacement {
+            file_path: self.display_path(file_path),
+            replacements: count,
+            diff: None,
+            modified: false,
+            skipped: None,
+        };
+
+        let used = self.counters.replacements.fetch_add(count, Ordering::Relaxed);
+        if used + count > replace.max_replacements {
+            self.counters.replacements.fetch_sub(count, Ordering::Relaxed);
+            outcome.skipped = Some("max_replacements exceeded".to_string());
+        } else if replace.dry_run {
+            outcome.diff = Some(
+                similar::TextDiff::from_lines(content, &replaced)
+                    .unified_diff()
+                    .context_radius(3)
+                    .header(&format!("a/{}", relative_path), &format!("b/{}", relative_path))
+                    .to_string(),
+            );
+        } else {
+            let temp_path = file_path.with_extension(format!("codesearcher.{}.tmp", std::process::id()));
+            let written = fs::write(&temp_path, [bom, replaced.as_str()].concat())
+                .and_then(|_| fs::set_permissions(&temp_path, metadata.permissions()))
+                .and_then(|_| fs::rename(&temp_path, file_path));
+            match written {
+                Ok(()) => outcome.modified = true,
+                Err(e) => {
+                    let _ = fs::remove_file(&temp_path);
+                    self.counters.replacements.fetch_sub(count, Ordering::Relaxed);
+                    outcome.skipped = Some(format!("write failed: {}", e));
+                }
+            }
+        }
+
+        if let Ok(mut file_replacements) = self.file_replacements.lock() {
+            file_replacements.push(outcome);
+        }
+    }
+
+    /// 文件 (行范围内) 的非注释行中是否出现了全部搜索词 (operator=and)
+    fn contains_all_terms(&self, content: &str, comment_prefixes: &[&str], window: Option<LineWindow>) -> bool {
+        let mut missing: Vec<&Regex> = self.terms.iter().map(|(_, re)| re).collect();
+        let lines = content
+            .lines()
+            .enumerate()
+            .filter(|&(i, line)| in_window(window, i) && !self.is_skipped_line(line, comment_prefixes));
+        for (_, line) in lines {
+            missing.retain(|re| !re.is_match(line));
+            if missing.is_empty() {
+                return true;
+            }
+        }
+        missing.is_empty()
+    }
+
+    /// 该行是否应当报告：跳过注释行和超长行，invert_match 时取反
+    fn is_reported_line(&self, line: &str, comment_prefixes: &[&str]) -> bool {
+        !self.is_skipped_line(line, comment_prefixes) && self.is_match(line) != self.invert_match
+    }
+
+    /// 查询匹配：有字面量快速路径时不经过正则引擎
+    fn is_match(&self, haystack: &str) -> bool {
+        match &self.literal {
+            Some(literal) => literal.is_match(haystack),
+            None => self.regex.is_match(haystack),
+        }
+    }
+
+    fn find(&self, line: &str) -> Option<Span> {
+        match &self.literal {
+            Some(literal) => literal.find(line),
+            None => self.regex.find(line).map(Span::from),
+        }
+    }
+
+    fn find_iter<'a>(&'a self, line: &'a str) -> Box<dyn Iterator<Item = Span> + 'a> {
+        match &self.literal {
+            Some(literal) => literal.find_iter(line),
+            None => Box::new(self.regex.find_iter(line).map(Span::from)),
+        }
+    }
+
+    /// 不参与匹配的行：注释行 (ignore_comments) 和超过 max_scan_line_length 的行
+    fn is_skipped_line(&self, line: &str, comment_prefixes: &[&str]) -> bool {
+        self.max_scan_line_length.is_some_and(|max| line.len() > max) || is_comment_line(line, comment_prefixes)
+    }
+
+    /// 计入本文件的匹配数。同一显示路径的文件已经计入过时 (visited 没能识别出的重复到达)
+    /// 整个文件作为重复丢弃，只计入 duplicate_results，返回 false，调用方不应再发送它的结果
+    fn record_matches(&self, file_path: &Path, match_count: usize) -> bool {
+        if match_count == 0 {
+            return true;
+        }
+        let first_report = self
+            .reported_files
+            .lock()
+            .map(|mut reported| reported.insert(self.display_path(file_path)))
+            .unwrap_or(true);
+        if !first_report {
+            self.counters.duplicate_results.fetch_add(match_count, Ordering::Relaxed);
+            return false;
+        }
+        self.counters.total_matches.fetch_add(match_count, Ordering::Relaxed);
+        self.counters.files_matched.fetch_add(1, Ordering::Relaxed);
+        if let Some(Ok(mut counts)) = self.file_match_counts.as_ref().map(|counts| counts.lock()) {
+            counts.push((file_path.to_path_buf(), match_count));
+        }
+        if let Some(Ok(mut files)) = self.matching_files.as_ref().map(|files| files.lock()) {
+            files.insert(file_path.to_path_buf());
+        }
+        true
+    }
+
+    /// 按 reason 跳过一个文件：计入 files_skipped，explain 时同时计入 exclusions
+    fn skip(&self, file_path: &Path, reason: &'static str) {
+        self.counters.files_skipped.fetch_add(1, Ordering::Relaxed);
+        self.record_exclusion(file_path, reason);
+    }
+
+    /// explain 时按原因记录一个未搜索的条目，每类只保留前 MAX_EXCLUSION_EXAMPLES 个路径
+    fn record_exclusion(&self, path: &Path, reason: &'static str) {
+        let Some(Ok(mut exclusions)) = self.exclusions.as_ref().map(|e| e.lock()) else {
+            return;
+        };
+        let summary = exclusions.entry(reason).or_default();
+        summary.count += 1;
+        if summary.examples.len() < MAX_EXCLUSION_EXAMPLES {
+            summary.examples.push(self.display_path(path));
+        }
+    }
+
+    /// 记录一条警告，超出 MAX_WARNINGS 后只计数
+    fn warn(&self, path: String, kind: &str, message: String) {
+        let Ok(mut warnings) = self.warnings.lock() else {
+            return;
+        };
+        if warnings.len() < MAX_WARNINGS {
+            warnings.push(SearchWarning { path, kind: kind.to_string(), message });
+        } else {
+            self.counters.warnings_dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// 结果、警告中使用的路径：默认相对项目根目录，absolute_paths 时为绝对路径。
+    /// 两种写法都先规范化，同一文件经 .、..、符号链接等不同写法到达时统一成规范路径
+    /// (因此 absolute_paths 给出的是解析符号链接之后的实际路径，而不是请求中的写法)；
+    /// 规范化后不在项目根目录内 (如指向外部的符号链接) 或文件已不存在时保留原写法
+    fn display_path(&self, path: &Path) -> String {
+        let canonical = self.canonical_base.as_ref().and_then(|base| {
+            path.canonicalize().ok().filter(|p| p.starts_with(base)).map(|p| (p, base))
+        });
+        let (path, base) = match &canonical {
+            Some((path, base)) => (path.as_path(), base.as_path()),
+            None => (path, self.project_base.as_path()),
+        };
+        let shown = if self.absolute_paths {
+            std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+        } else {
+            pathdiff::diff_paths(path, base).unwrap_or_else(|| path.to_path_buf())
+        };
+        shown.to_string_lossy().into_owned()
+    }
+
+    /// mtime 是否落在 modified_after..=modified_before 内；取不到 mtime 的文件在设置了范围时视为不满足
+    fn in_modified_range(&self, metadata: &fs::Metadata) -> bool {
+        if self.modified_after.is_none() && self.modified_before.is_none() {
+            return true;
+        }
+        let Ok(mtime) = metadata.modified() else {
+            return false;
+        };
+        self.modified_after.is_none_or(|after| mtime >= after)
+            && self.modified_before.is_none_or(|before| mtime <= before)
+    }
+
+    /// 缓存文件路径：SHA-256(文件路径 + mtime + 大小 + 正则 + 影响结果的选项)。
+    /// 文件被修改后 mtime 变化，旧的缓存项自然不会再命中
+    fn cache_path(&self, file_path: &Path, metadata: &fs::Metadata) -> Option<PathBuf> {
+        let cache_dir = self.cache_dir.as_ref()?;
+        let mtime = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+
+        let mut hasher = Sha256::new();
+        for part in [
+            CACHE_FORMAT_VERSION,
+            file_path.to_string_lossy().as_ref(),
+            self.project_base.to_string_lossy().as_ref(),
+            &mtime.to_string(),
+            &metadata.len().to_string(),
+            self.regex.as_str(),
+            &self.context_before.to_string(),
+            &self.context_after.to_string(),
+            &self.max_line_length.to_string(),
+            &self.ignore_comments.to_string(),
+            self.comment_style.as_deref().unwrap_or(""),
+            self.line_format,
+            &self.include_spans.to_string(),
+            &self.require_all_terms.to_string(),
+            &self.invert_match.to_string(),
+            &self.max_matches_per_file.unwrap_or(0).to_string(),
+            &self.absolute_paths.to_string(),
+            &self.enclosing_symbol.to_string(),
+            &self.max_scan_line_length.unwrap_or(0).to_string(),
+            &self.near.as_ref().map_or(String::new(), |near| {
+                format!("{}\0{}\0{}", near.a.1.as_str(), near.b.1.as_str(), near.distance)
+            }),
+        ] {
+            hasher.update(part.as_bytes());
+            hasher.update([0u8]);
+        }
+        let hash: String = hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect();
+        Some(cache_dir.join(format!("{}.json", hash)))
+    }
+}
+
+/// 读取缓存的单文件结果，缓存不存在或损坏时返回 None。
+/// 命中时刷新缓存文件的 mtime，淘汰按 mtime 进行，相当于 LRU
+fn read_cached_results(cache_path: &Path) -> Option<Vec<SearchResult>> {
+    let json = fs::read_to_string(cache_path).ok()?;
+    let results = serde_json::from_str(&json).ok()?;
+    if let Ok(file) = fs::File::options().write(true).open(cache_path) {
+        let _ = file.set_modified(SystemTime::now());
+    }
+    Some(results)
+}
+
+/// 请求中的 cache_dir 对应的缓存文件目录
+fn cache_root(project_base: &Path, cache_dir: &str) -> PathBuf {
+    project_base.join(cache_dir).join(CACHE_SUBDIR)
+}
+
+/// 是否为缓存自己写入的文件：结果缓存 "<SHA-256>.json" 或遍历缓存 "walk-<SHA-256>.json"
+fn is_cache_file_name(name: &str) -> bool {
+    let Some(stem) = name.strip_suffix(".json") else {
+        return false;
+    };
+    let hash = stem.strip_prefix("walk-").unwrap_or(stem);
+    hash.len() == 64 && hash.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+/// 缓存总大小超过上限时按 mtime 从旧到新删除缓存项，一直删到上限的 80% 以下，
+/// 留出余量避免之后每次搜索都触发淘汰。单个缓存项删除失败直接跳过。
+/// 只统计和删除 cache_dir 中按缓存命名规则生成的文件，cache_dir 不在项目根目录内时什么都不做
+fn evict_cache(project_base: &Path, cache_dir: &Path, max_size: u64) -> CacheStats {
+    let mut stats = CacheStats { max_size_bytes: max_size, ..Default::default() };
+    if !is_within_base(project_base, cache_dir).unwrap_or(false) {
+        return stats;
+    }
+    let mut entries: Vec<(PathBuf, u64, SystemTime)> = fs::read_dir(cache_dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name().to_str().is_some_and(is_cache_file_name))
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok().filter(|m| m.is_file())?;
+            let mtime = metadata.modified().unwrap_or(UNIX_EPOCH);
+            Some((entry.path(), metadata.len(), mtime))
+        })
+        .collect();
+
+    stats.entries = entries.len();
+    stats.size_bytes = entries.iter().map(|(_, size, _)| size).sum();
+    if stats.size_bytes <= max_size {
+        return stats;
+    }
+
+    let low_water = max_size / 5 * 4;
+    entries.sort_by_key(|(_, _, mtime)| *mtime);
+    for (path, size, _) in entries {
+        if stats.size_bytes < low_water {
+            break;
+        }
+        if fs::remove_file(&path).is_ok() {
+            stats.entries -= 1;
+            stats.size_bytes -= size;
+            stats.evicted_entries += 1;
+            stats.evicted_bytes += size;
+        }
+    }
+    stats
+}
+
+/// 写入单文件结果缓存：先写临时文件再重命名，避免并发读到写了一半的内容。
+/// 缓存只是优化，写入失败直接忽略
+fn write_cached_results(cache_path: &Path, results: &[SearchResult]) {
+    let Ok(json) = serde_json::to_string(results) else {
+        return;
+    };
+    if let Some(dir) = cache_path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    let temp_path = cache_path.with_extension(format!("{}.tmp", std::process::id()));
+    if fs::write(&temp_path, json).is_ok() && fs::rename(&temp_path, cache_path).is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+}
+
+fn search_in_directory(
+    path: &Path,
+    query_regex: &Regex,
+    terms: &[(String, Regex)],
+    require_all_terms: bool,
+    config: &AppConfig,
+    args: &InputArgs,
+    project_base: &Path,
+) -> Result<SearchOutcome, io::Error> {
+    // 有界通道：汇总线程跟不上时工作线程阻塞，避免大目录下结果在通道中无限堆积
+    let (tx, rx) = mpsc::sync_channel::<SearchResult>(RESULT_CHANNEL_CAPACITY);
+    let max_results = config.max_results;
+    let offset = args.offset.unwrap_or(0);
+    // 分页时需要收集到本页末尾为止的全部结果
+    let page_end = offset.saturating_add(max_results);
+    let counters = Arc::new(ScanCounters::default());
+    let file_replacements = Arc::new(Mutex::new(Vec::new()));
+    let warnings = Arc::new(Mutex::new(Vec::new()));
+    let slow_files = Arc::new(Mutex::new(Vec::new()));
+    let exclusions = args.explain.then(|| Arc::new(Mutex::new(BTreeMap::new())));
+    let dirs_mode = args.output_mode.as_deref() == Some("dirs");
+    let summary_only = args.summary_only == Some(true);
+    // 两种模式都不需要结果本身，上限为 0 时所有文件都只计数
+    let count_only = dirs_mode || summary_only;
+    let file_match_counts = dirs_mode.then(|| Arc::new(Mutex::new(Vec::new())));
+    let matching_files = summary_only.then(|| Arc::new(Mutex::new(HashSet::new())));
+    let per_extension_cap = args.max_per_extension.filter(|&cap| cap > 0);
+    // 不需要排序也不按扩展名限额时，收集到本页末尾即可停止
+    let collect_page_only = args.stream || (args.sort == "none" && per_extension_cap.is_none());
+    let line_window = Arc::new(Mutex::new(None));
+    let context = SearchContext {
+        regex: query_regex.clone(),
+        literal: build_literal_matcher(args).map(Arc::new),
+        terms: terms.to_vec(),
+        require_all_terms,
+        project_base: project_base.to_path_buf(),
+        canonical_base: project_base.canonicalize().ok(),
+        context_before: args.context_before.unwrap_or(config.context_lines),
+        context_after: args.context_after.unwrap_or(config.context_lines),
+        max_line_length: args.max_line_length,
+        ignore_comments: args.ignore_comments,
+        comment_style: args.comment_style.clone(),
+        line_format: LINE_FORMATS
+            .iter()
+            .copied()
+            .find(|&format| args.line_format.as_deref() == Some(format))
+            .unwrap_or(if args.preserve_whitespace { "raw" } else { "trim" }),
+        include_spans: args.include_spans,
+        capture_groups: args.use_regex && query_regex.captures_len() > 1,
+        invert_match: args.invert_match,
+        max_matches_per_file: args.max_matches_per_file.filter(|&n| n > 0),
+        cache_dir: args.cache_dir.as_ref().map(|dir| cache_root(project_base, dir)),
+        max_file_size: match config.max_file_size {
+            0 => None,
+            size => Some(size as u64),
+        },
+        max_total_bytes_searched: match config.max_total_bytes_searched {
+            0 => None,
+            size => Some(size as u64),
+        },
+        // 排序和按扩展名限额必须看到全部结果，才能保证截断后留下的是排在最前的那些；
+        // 汇总线程边收边裁剪，内存中最多保留 2 * (offset + limit) 条
+        max_results: if count_only {
+            0
+        } else if collect_page_only {
+            page_end
+        } else {
+            usize::MAX
+        },
+        replace: args.replace.as_ref().map(|replacement| {
+            Arc::new(ReplaceOptions {
+                replacement: replacement.clone(),
+                expand_captures: args.use_regex,
+                dry_run: args.dry_run,
+                max_replacements: args.max_replacements,
+            })
+        }),
+        file_replacements: file_replacements.clone(),
+        deadline: match args.timeout_ms {
+            0 => None,
+            ms => Some(Instant::now() + Duration::from_millis(ms as u64)),
+        },
+        counters: counters.clone(),
+        visited: Arc::new(Mutex::new(HashSet::new())),
+        modified_after: config.modified_after,
+        modified_before: config.modified_before,
+        warnings: warnings.clone(),
+        slow_file_threshold: config.slow_file_threshold,
+        slow_files: slow_files.clone(),
+        file_reader: config.file_reader.clone(),
+        case_sensitive_paths: config.case_sensitive_paths,
+        absolute_paths: args.absolute_paths,
+        near: config.near.clone(),
+        enclosing_symbol: args.enclosing_symbol,
+        file_metadata: args.file_metadata,
+        max_scan_line_length: Some(args.max_scan_line_length).filter(|&max| max > 0),
+        context_separator: Some(args.context_separator.clone()).filter(|separator| {
+            // format=text 时分隔行由 render_text 按实际相邻关系输出
+            !separator.is_empty()
+                && !args.merge_context
+                && args.format.as_deref() != Some("text")
+                && args.context_before.unwrap_or(config.context_lines) + args.context_after.unwrap_or(config.context_lines) > 0
+        }),
+        exclusions: exclusions.clone(),
+        file_match_counts: file_match_counts.clone(),
+        matching_files: matching_files.clone(),
+        reported_files: Arc::new(Mutex::new(HashSet::new())),
+        line_range: (args.line_start.is_some() || args.line_end.is_some())
+            .then(|| (args.line_start.unwrap_or(1), args.line_end.unwrap_or(usize::MAX))),
+        line_window: line_window.clone(),
+        byte_pattern: config.byte_pattern.clone(),
+    };
+
+    // 汇总线程：普通模式下收集结果，流式模式下作为唯一的 stdout 写入者逐条输出，
+    // 保证多个工作线程的结果不会在同一行内交错
+    let stream = args.stream;
+    // 排序或按扩展名限额时工作线程发送全部结果，汇总线程只保留排在本页末尾之前的那些：
+    // 缓冲超过 2 * page_end 时排序、按扩展名限额后截断到 page_end。
+    // sort=none 按文件首次到达的先后排列，裁剪后留下的文件相对顺序不变，同样适用
+    let prune_sort = (!stream && !collect_page_only).then(|| args.sort.clone());
+    let max_output_bytes = args.max_output_bytes;
+    let collector_counters = counters.clone();
+    let collector = thread::spawn(move || {
+        let mut collected = Collected::default();
+        let results = &mut collected.results;
+        let mut emitted_bytes = 0;
+        let mut skipped = 0;
+        // 每种扩展名到达的结果数，用于计算限额后的结果总数
+        let mut per_extension: HashMap<String, usize> = HashMap::new();
+        for result in rx {
+            collector_counters.pending_results.fetch_sub(1, Ordering::Relaxed);
+            if !stream {
+                collected.received += 1;
+                if per_extension_cap.is_some() {
+                    *per_extension.entry(extension_key(&result.file_path)).or_default() += 1;
+                }
+                results.push(result);
+                if let Some(sort) = prune_sort.as_deref().filter(|_| results.len() > page_end.saturating_mul(2)) {
+                    sort_results(results, sort);
+                    if let Some(cap) = per_extension_cap {
+                        collected.extension_capped |= cap_per_extension(results, cap);
+                    }
+                    if results.len() > page_end {
+                        results.truncate(page_end);
+                        collected.pruned = true;
+                    }
+                }
+            } else if skipped < offset {
+                skipped += 1;
+            } else if collected.emitted < max_results && !collected.over_budget {
+                let Ok(json) = serde_json::to_string(&StreamRecord::Match(&result)) else {
+                    continue;
+                };
+                // 放不下的结果整条丢弃，之后的结果也不再输出，保持到达顺序
+                if max_output_bytes > 0 && emitted_bytes + json.len() + 1 > max_output_bytes {
+                    collected.over_budget = true;
+                    continue;
+                }
+                emitted_bytes += json.len() + 1;
+                print_json_line(&json);
+                collected.emitted += 1;
+            }
+        }
+        if let Some(cap) = per_extension_cap {
+            collected.received = per_extension.values().map(|&count| count.min(cap)).sum();
+        }
+        collected
+    });
+
+    let case_sensitive = config.case_sensitive_paths;
+    let fold_all = |names: &HashSet<String>| -> HashSet<String> {
+        names.iter().map(|name| fold_path_case(name, case_sensitive).into_owned()).collect()
+    };
+    let filters = FileFilters {
+        allowed_extensions: fold_all(&config.allowed_extensions),
+        always_include_filenames: fold_all(&config.always_include_filenames),
+        test_file_globs: args
+            .ignore_test_files
+            .unwrap_or(config.ignore_test_files)
+            .then(|| build_test_file_globs(case_sensitive)),
+        exclude_globs: config.exclude_globs.clone(),
+        project_base: project_base.to_path_buf(),
+        // 跟随符号链接时逐个检查文件解析后的实际位置，与解析符号链接后的项目根目录比较
+        canonical_base: args.follow_symlinks.then(|| project_base.canonicalize().ok()),
+        case_sensitive,
+    };
+
+    let tracked = if args.tracked_only {
+        match git_tracked_files(path) {
+            Ok(files) => Some(files),
+            Err(e) => {
+                context.warn(
+                    context.display_path(path),
+                    "git_unavailable",
+                    format!("{}; falling back to directory walk", e),
+                );
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    // 显式文件列表始终在当前线程中顺序搜索
+    let mut threads = 1;
+    let mut walk_cache = args.cache_dir.as_ref().map(|_| "disabled");
+    if let Some(files) = &args.files {
+        // 显式文件列表：不遍历目录，也不做扩展名和测试文件过滤，调用方点名的文件总是搜索
+        for file in files {
+            if context.should_stop() {
+                break;
+            }
+            context.counters.files_walked.fetch_add(1, Ordering::Relaxed);
+            let file_path = project_base.join(file);
+            let outcome = match is_within_base(project_base, &file_path) {
+                Ok(false) => {
+                    context.warn(file.clone(), "outside_project", "path escapes project root".to_string());
+                    continue;
+                }
+                Ok(true) if file_path.is_dir() => {
+                    Err(io::Error::new(io::ErrorKind::InvalidInput, "is a directory"))
+                }
+                Ok(true) => context.search_file(&file_path, &tx),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = outcome {
+                context.warn(file.clone(), warning_kind(&e), e.to_string());
+            }
+        }
+        drop(tx);
+    } else if let Some(tracked) = tracked {
+        // git 跟踪的文件不再经过 ignore 规则，按线程数切分后并行搜索
+        let chunk_size = tracked.len().div_ceil(config.threads).max(1);
+        threads = tracked.chunks(chunk_size).len().max(1);
+        thread::scope(|scope| {
+            for chunk in tracked.chunks(chunk_size) {
+                let tx = tx.clone();
+                let (context, filters) = (&context, &filters);
+                scope.spawn(move || {
+                    search_tracked_files(chunk, path, args, &config.ignored_folders, filters, context, &tx)
+                });
+            }
+        });
+        drop(tx);
+    } else {
+        // explain 需要遍历器交出的全部条目，不使用遍历缓存
+        let walk_cache_path = args
+            .cache_dir
+            .as_ref()
+            .filter(|_| !args.explain)
+            .map(|dir| walk_cache::cache_path(&cache_root(project_base, dir), &walk_cache_key(path, project_base, args, config)));
+        if let Some(files) = walk_cache_path.as_deref().and_then(walk_cache::load) {
+            walk_cache = Some("hit");
+            let chunk_size = files.len().div_ceil(config.threads).max(1);
+            threads = files.chunks(chunk_size).len().max(1);
+            thread::scope(|scope| {
+                for chunk in files.chunks(chunk_size) {
+                    let tx = tx.clone();
+                    let (context, filters) = (&context, &filters);
+                    scope.spawn(move || search_cached_files(chunk, filters, context, &tx));
+                }
+            });
+            drop(tx);
+        } else {
+            let mut walk_builder = WalkBuilder::new(path);
+            // 文件大小在 search_file 里检查，这样被跳过的大文件可以计入统计
+            // WalkBuilder 的深度 0 只有根目录条目本身，这里 +1 让 max_depth=0 表示根目录下的文件
+            walk_builder
+                .hidden(!args.include_hidden)
+                .git_ignore(args.respect_gitignore)
+                .git_global(args.respect_gitignore)
+                .git_exclude(args.respect_gitignore)
+                // 跟随符号链接时，目录环由 ignore 自身检测，重复到达的文件由 visited 集合去重
+                .follow_links(args.follow_symlinks)
+                .ignore(true)
+                .max_depth(args.max_depth.map(|d| d + 1))
+                .threads(config.threads);
+            // .searchignore / .rgignore 语法与 .gitignore 相同，搜索目录及其上级目录中的都会生效，不需要 git 仓库
+            walk_builder.add_custom_ignore_filename(".searchignore");
+            walk_builder.add_custom_ignore_filename(".rgignore");
+
+            if let Some(ignore_file) = &args.ignore_file {
+                if let Some(e) = walk_builder.add_ignore(project_base.join(ignore_file)) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("Invalid ignore_file {}: {}", ignore_file, e),
+                    ));
+                }
+            }
+
+            // add_ignore 只接受忽略文件路径，文件夹名必须在遍历时按名称过滤
+            let ignored_folders = config.ignored_folders.clone();
+            // 缓存文件本身会包含搜索词，且每次请求都不同，使用缓存时不进入缓存子目录
+            let skip_cache_dir = args.cache_dir.is_some();
+            walk_builder.filter_entry(move |entry| {
+                // 显式指定的 search_path 本身 (depth 0) 不受影响
+                let is_dir = entry.depth() > 0 && entry.file_type().is_some_and(|ft| ft.is_dir());
+                !(is_dir
+                    && entry.file_name().to_str().is_some_and(|name| {
+                        ignored_folders.contains(name) || (skip_cache_dir && name == CACHE_SUBDIR)
+                    }))
+            });
+
+            // explain：记录遍历器交出的全部条目，之后据此找出被遍历器自身过滤掉的条目
+            let walked: Option<Mutex<HashSet<PathBuf>>> = args.explain.then(|| Mutex::new(HashSet::new()));
+            // 缓存目录在遍历前创建，首次写入缓存时才创建会改变其上级目录的 mtime，使刚写入的缓存立即失效
+            let recorder = walk_cache_path.as_deref().map(|cache_path| {
+                if let Some(dir) = cache_path.parent() {
+                    let _ = fs::create_dir_all(dir);
+                }
+                WalkRecorder::default()
+            });
+            threads = config.threads;
+            if threads == 1 {
+                // 单线程时不经过并行遍历器，按目录顺序在当前线程中逐个搜索
+                for entry in walk_builder.build() {
+                    if let WalkState::Quit = visit_walk_entry(entry, &context, &filters, walked.as_ref(), recorder.as_ref(), &tx) {
+                        break;
+                    }
+                }
+                drop(tx);
+            } else {
+                let (context, filters, walked, recorder) = (&context, &filters, walked.as_ref(), recorder.as_ref());
+                walk_builder.build_parallel().run(move || {
+                    let tx = tx.clone();
+                    Box::new(move |entry| visit_walk_entry(entry, context, filters, walked, recorder, &tx))
+                });
+            }
+
+            // 超时或字节上限中断时主遍历不完整，无法区分被过滤和未到达的条目
+            if let Some(walked) = walked {
+                if !context.should_stop() {
+                    let walked = walked.into_inner().unwrap_or_default();
+                    explain_walk_exclusions(path, args, &config.ignored_folders, &walked, &context);
+                }
+            }
+
+            // 超时或字节上限中断时文件列表不完整，不能写入缓存
+            if let (Some(recorder), Some(cache_path)) = (recorder, &walk_cache_path) {
+                walk_cache = Some("miss");
+                if !context.should_stop() {
+                    let ancestors: Vec<PathBuf> = path
+                        .ancestors()
+                        .skip(1)
+                        .take_while(|dir| dir.starts_with(project_base))
+                        .map(Path::to_path_buf)
+                        .collect();
+                    let ignore_files: Vec<PathBuf> = args.ignore_file.iter().map(|file| project_base.join(file)).collect();
+                    recorder.save(cache_path, &ancestors, &ignore_files);
+                }
+            }
+        }
+    }
+
+    let Collected { mut results, emitted, mut over_budget, received, pruned, extension_capped } = collector
+        .join()
+        .map_err(|_| io::Error::other("result collector panicked"))?;
+    if !stream {
+        sort_results(&mut results, &args.sort);
+    }
+    let extension_capped =
+        per_extension_cap.is_some_and(|cap| cap_per_extension(&mut results, cap)) || extension_capped;
+    // 只有限额后剩下的结果装得进本页时，少掉的结果才归因于扩展名限额
+    let capped_by_extension = extension_capped && !pruned && results.len() <= page_end;
+    // 收集了全部结果 (需要排序或限额) 时按汇总线程收到的条数计，否则按 total_matches 估计
+    let total_count = (args.offset.is_some() || args.limit.is_some()).then(|| {
+        let available = if collect_page_only {
+            counters.total_matches.load(Ordering::Relaxed)
+        } else {
+            received
+        };
+        available.min(MAX_TOTAL_COUNT)
+    });
+    results.truncate(page_end);
+    results.drain(..offset.min(results.len()));
+    if !stream {
+        over_budget = fit_output_budget(&mut results, args.max_output_bytes);
+    }
+    let kept = if stream { emitted } else { results.len() };
+
+    let stats = SearchStats {
+        total_matches: counters.total_matches.load(Ordering::Relaxed),
+        files_walked: counters.files_walked.load(Ordering::Relaxed),
+        files_scanned: counters.files_scanned.load(Ordering::Relaxed),
+        files_matched: counters.files_matched.load(Ordering::Relaxed),
+        files_skipped: counters.files_skipped.load(Ordering::Relaxed),
+        duplicates_skipped: counters.duplicates_skipped.load(Ordering::Relaxed),
+        duplicate_results: counters.duplicate_results.load(Ordering::Relaxed),
+        warnings_dropped: counters.warnings_dropped.load(Ordering::Relaxed),
+        long_lines_skipped: counters.long_lines_skipped.load(Ordering::Relaxed),
+        peak_pending_results: counters.peak_pending_results.load(Ordering::Relaxed),
+    };
+    let timed_out = counters.timed_out.load(Ordering::Relaxed);
+    let truncated_reason = if timed_out {
+        Some("timeout")
+    } else if counters.byte_limit_reached.load(Ordering::Relaxed) {
+        Some("max_bytes")
+    } else if over_budget {
+        Some("max_output_bytes")
+    } else if capped_by_extension {
+        Some("max_per_extension")
+    } else if !count_only && stats.total_matches > offset + kept {
+        Some("max_results")
+    } else {
+        None
+    };
+    let truncated = truncated_reason.is_some();
+
+    // 工作线程都已结束，Mutex 中的替换结果不会再变化
+    let mut replacements = file_replacements
+        .lock()
+        .map(|mut guard| std::mem::take(&mut *guard))
+        .unwrap_or_default();
+    replacements.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+    let mut warnings = warnings
+        .lock()
+        .map(|mut guard| std::mem::take(&mut *guard))
+        .unwrap_or_default();
+    // 并行遍历时警告的先后顺序不确定，显式文件列表则保持调用方给出的顺序
+    if args.files.is_none() {
+        warnings.sort_by(|a, b| a.path.cmp(&b.path));
+    }
+    let mut slow_files: Vec<SlowFileReport> = slow_files
+        .lock()
+        .map(|mut guard| std::mem::take(&mut *guard))
+        .unwrap_or_default();
+    slow_files.sort_by(|a, b| b.duration_ms.cmp(&a.duration_ms).then_with(|| a.path.cmp(&b.path)));
+    let exclusions = exclusions.map(|exclusions| {
+        let mut exclusions = exclusions
+            .lock()
+            .map(|mut guard| std::mem::take(&mut *guard))
+            .unwrap_or_default();
+        for summary in exclusions.values_mut() {
+            summary.examples.sort();
+        }
+        exclusions
+    });
+    let directories = file_match_counts.map(|counts| {
+        let counts = counts.lock().map(|mut guard| std::mem::take(&mut *guard)).unwrap_or_default();
+        summarize_directories(counts, project_base, args.dir_depth.unwrap_or(DEFAULT_DIR_DEPTH))
+    });
+    let summary = matching_files.map(|files| {
+        let files = files.lock().map(|mut guard| std::mem::take(&mut *guard)).unwrap_or_default();
+        let mut matching_files: Vec<String> = files.iter().map(|path| context.display_path(path)).collect();
+        matching_files.sort();
+        SearchSummary { total_matches: stats.total_matches, matching_files, truncated }
+    });
+    let line_window = line_window.lock().ok().and_then(|window| *window);
 
-    match search_in_directory(&search_root, &regex, &config, &args, &base_path) {
-        Ok((results, truncated)) => {
-            let output = Output {
-                status: "success".to_string(),
-                result: Some(results),
-                error: None,
-                truncated: if truncated { Some(true) } else { None },
+    Ok(SearchOutcome {
+        results,
+        truncated,
+        truncated_reason,
+        total_count,
+        timed_out,
+        stats,
+        warnings,
+        replacements,
+        slow_files,
+        exclusions,
+        directories,
+        summary,
+        threads,
+        line_window,
+        walk_cache,
+    })
+}
+
+/// 处理遍历器交出的一个条目：记录遍历错误，过滤后搜索文件。单线程和并行遍历共用
+fn visit_walk_entry(
+    entry: Result<ignore::DirEntry, ignore::Error>,
+    context: &SearchContext,
+    filters: &FileFilters,
+    walked: Option<&Mutex<HashSet<PathBuf>>>,
+    recorder: Option<&WalkRecorder>,
+    tx: &mpsc::SyncSender<SearchResult>,
+) -> WalkState {
+    if context.should_stop() {
+        return WalkState::Quit;
+    }
+    let entry = match entry {
+        Ok(e) => e,
+        Err(e) => {
+            let kind = match e.io_error().map(io::Error::kind) {
+                Some(io::ErrorKind::PermissionDenied) => "permission_denied",
+                _ => "walk_error",
             };
-            if let Ok(json) = serde_json::to_string(&output) {
-                println!("{}", json);
-            }
+            let path = walk_error_path(&e).map(|p| context.display_path(p)).unwrap_or_default();
+            context.warn(path, kind, e.to_string());
+            return WalkState::Continue;
         }
-        Err(e) => print_error(format!("Search failed: {}", e)),
+    };
+    if let Some(Ok(mut walked)) = walked.map(|w| w.lock()) {
+        walked.insert(entry.path().to_path_buf());
     }
+    // 目录在交给回调之后才会被读取，此时记下的 mtime 早于读取
+    if let (Some(recorder), Some(file_type)) = (recorder, entry.file_type()) {
+        if file_type.is_dir() {
+            recorder.record_dir(entry.path());
+        } else if file_type.is_file() {
+            recorder.record_file(entry.path());
+        }
+    }
+
+    if !entry.file_type().map(|ft| ft.is_file()).unwrap_or(false) {
+        return WalkState::Continue;
+    }
+
+    context.counters.files_walked.fetch_add(1, Ordering::Relaxed);
+    let file_path = entry.path();
+    if let Some(reason) = filters.skip_reason(file_path) {
+        context.skip(file_path, reason);
+        return WalkState::Continue;
+    }
+
+    if filters.escapes_project(file_path) {
+        context.warn(
+            context.display_path(file_path),
+            "outside_project",
+            "symlink target escapes project root".to_string(),
+        );
+        return WalkState::Continue;
+    }
+
+    context.search_walked_file(file_path, tx);
+    WalkState::Continue
 }
 
-fn build_regex(args: &InputArgs) -> Result<Regex, regex::Error> {
-    let mut pattern = regex::escape(&args.query);
+/// 目录遍历和 tracked_only 共用的逐文件过滤：扩展名白名单、测试文件、exclude_file_patterns
+#[derive(Clone)]
+struct FileFilters {
+    allowed_extensions: HashSet<String>,
+    always_include_filenames: HashSet<String>,
+    test_file_globs: Option<GlobSet>,  // 只在忽略测试文件时存在
+    exclude_globs: Option<GlobSet>,
+    project_base: PathBuf,
+    canonical_base: Option<Option<PathBuf>>,  // 只在跟随符号链接时存在，项目根目录无法解析时内层为 None
+    case_sensitive: bool,  // 不区分时 allowed_extensions 和 always_include_filenames 已转为小写
+}
 
-    if args.whole_word {
-        pattern = format!(r"\b{}\b", pattern);
+impl FileFilters {
+    /// 文件应被跳过时返回原因 (与 exclusions 的分类一致)
+    fn skip_reason(&self, file_path: &Path) -> Option<&'static str> {
+        if !self.allowed_extensions.is_empty()
+            && !file_path
+                .extension()
+                .and_then(|s| s.to_str())
+                .is_some_and(|ext| self.allowed_extensions.contains(fold_path_case(ext, self.case_sensitive).as_ref()))
+            && !file_path
+                .file_name()
+                .and_then(|s| s.to_str())
+                .is_some_and(|name| self.always_include_filenames.contains(fold_path_case(name, self.case_sensitive).as_ref()))
+        {
+            return Some("extension");
+        }
+        if self.test_file_globs.as_ref().is_some_and(|globs| {
+            file_path.file_name().is_some_and(|name| globs.is_match(name))
+        }) {
+            return Some("test_file");
+        }
+        if self.exclude_globs.as_ref().is_some_and(|globs| {
+            let relative = file_path.strip_prefix(&self.project_base).unwrap_or(file_path);
+            globs.is_match(relative)
+        }) {
+            return Some("exclude_pattern");
+        }
+        None
     }
 
-    let pattern = if args.case_sensitive {
-        pattern
+    /// 跟随符号链接时，文件解析后的实际位置是否在项目根目录之外
+    fn escapes_project(&self, file_path: &Path) -> bool {
+        self.canonical_base.as_ref().is_some_and(|base| {
+            !base.as_ref().is_some_and(|base| {
+                file_path.canonicalize().is_ok_and(|canonical| canonical.starts_with(base))
+            })
+        })
+    }
+}
+
+/// 路径过滤不区分大小写时统一转为小写比较
+fn fold_path_case(name: &str, case_sensitive: bool) -> Cow<'_, str> {
+    if case_sensitive {
+        Cow::Borrowed(name)
     } else {
-        format!("(?i){}", pattern)
-    };
+        Cow::Owned(name.to_lowercase())
+    }
+}
 
-    Regex::new(&pattern)
+/// TEST_FILE_PATTERNS 编译成的 GlobSet，只匹配文件名
+fn build_test_file_globs(case_sensitive: bool) -> GlobSet {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in TEST_FILE_PATTERNS {
+        if let Ok(glob) = GlobBuilder::new(pattern).case_insensitive(!case_sensitive).build() {
+            builder.add(glob);
+        }
+    }
+    builder.build().unwrap_or_else(|_| GlobSet::empty())
 }
 
-fn search_in_directory(
-    path: &Path,
-    query_regex: &Regex,
-    config: &AppConfig,
+/// 列出 search_root 下 git 跟踪的文件。git ls-files 在 search_root 中运行时输出相对 search_root 的路径
+/// (而不是相对仓库根目录)，直接拼接即可，仓库根目录与项目根目录不同也不受影响
+fn git_tracked_files(search_root: &Path) -> Result<Vec<PathBuf>, String> {
+    let output = Command::new("git")
+        .args(["ls-files", "-z"])
+        .current_dir(search_root)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("git is not available: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git ls-files failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(output
+        .stdout
+        .split(|b| *b == 0)
+        .filter(|path| !path.is_empty())
+        .map(|path| search_root.join(String::from_utf8_lossy(path).as_ref()))
+        .collect())
+}
+
+/// tracked_only：搜索一批 git 跟踪的文件。目录遍历时由遍历器处理的 max_depth、IGNORED_FOLDERS 和隐藏文件在这里按路径判断；
+/// 已从工作区删除的文件和子模块直接跳过
+fn search_tracked_files(
+    files: &[PathBuf],
+    search_root: &Path,
     args: &InputArgs,
-    project_base: &Path,
-) -> Result<(Vec<SearchResult>, bool), io::Error> {
-    let mut walk_builder = WalkBuilder::new(path);
-    walk_builder.hidden(false).git_ignore(true).max_filesize(Some(MAX_FILE_SIZE));
-
-    for ignored in &config.ignored_folders {
-        walk_builder.add_ignore(ignored);
-    }
-
-    let (tx, rx) = mpsc::channel();
-    let query_regex = query_regex.clone();
-    let project_base_buf = project_base.to_path_buf();
-    let allowed_extensions = config.allowed_extensions.clone();
-    let context_lines = args.context_lines;
-
-    walk_builder.build_parallel().run(move || {
-        let tx = tx.clone();
-        let query_regex = query_regex.clone();
-        let project_base = project_base_buf.clone();
-        let allowed_extensions = allowed_extensions.clone();
-
-        Box::new(move |entry| {
-            let entry = match entry {
-                Ok(e) => e,
-                Err(_) => return WalkState::Continue,
-            };
+    ignored_folders: &HashSet<String>,
+    filters: &FileFilters,
+    context: &SearchContext,
+    tx: &mpsc::SyncSender<SearchResult>,
+) {
+    for file_path in files {
+        if context.should_stop() {
+            return;
+        }
+        if !file_path.is_file() {
+            continue;
+        }
+        context.counters.files_walked.fetch_add(1, Ordering::Relaxed);
+        let relative = file_path.strip_prefix(search_root).unwrap_or(file_path);
+        let folders: Vec<&str> = relative
+            .parent()
+            .map(|parent| parent.iter().filter_map(|c| c.to_str()).collect())
+            .unwrap_or_default();
+        if args.max_depth.is_some_and(|depth| folders.len() > depth) {
+            continue;
+        }
+        if folders.iter().any(|folder| ignored_folders.contains(*folder)) {
+            context.record_exclusion(file_path, "ignored_folder");
+            continue;
+        }
+        if !args.include_hidden && relative.iter().any(|c| c.to_string_lossy().starts_with('.')) {
+            context.record_exclusion(file_path, "hidden");
+            continue;
+        }
+        if let Some(reason) = filters.skip_reason(file_path) {
+            context.skip(file_path, reason);
+            continue;
+        }
+        context.search_walked_file(file_path, tx);
+    }
+}
 
-            if !entry.file_type().map(|ft| ft.is_file()).unwrap_or(false) {
-                return WalkState::Continue;
-            }
+/// 遍历缓存命中时搜索缓存的文件列表：缓存之后可能被删除的文件重新 stat 后跳过，
+/// 逐文件过滤照常进行，统计与完整遍历时一致
+fn search_cached_files(
+    files: &[CachedFile],
+    filters: &FileFilters,
+    context: &SearchContext,
+    tx: &mpsc::SyncSender<SearchResult>,
+) {
+    for file in files {
+        if context.should_stop() {
+            return;
+        }
+        if !file.path.is_file() {
+            continue;
+        }
+        context.counters.files_walked.fetch_add(1, Ordering::Relaxed);
+        if let Some(reason) = filters.skip_reason(&file.path) {
+            context.skip(&file.path, reason);
+            continue;
+        }
+        if filters.escapes_project(&file.path) {
+            context.warn(
+                context.display_path(&file.path),
+                "outside_project",
+                "symlink target escapes project root".to_string(),
+            );
+            continue;
+        }
+        context.search_walked_file(&file.path, tx);
+    }
+}
 
-            let file_path = entry.path();
-            if !allowed_extensions.is_empty() {
-                if let Some(ext) = file_path.extension().and_then(|s| s.to_str()) {
-                    if !allowed_extensions.contains(ext) {
-                        return WalkState::Continue;
-                    }
-                } else {
-                    return WalkState::Continue;
-                }
-            }
+/// 遍历缓存的键：搜索根目录和所有影响遍历器交出哪些条目的设置。逐文件过滤 (扩展名、exclude 等) 在复用时重新执行，不计入
+fn walk_cache_key(path: &Path, project_base: &Path, args: &InputArgs, config: &AppConfig) -> Vec<String> {
+    let mut ignored_folders: Vec<&str> = config.ignored_folders.iter().map(String::as_str).collect();
+    ignored_folders.sort_unstable();
+    vec![
+        path.to_string_lossy().into_owned(),
+        project_base.to_string_lossy().into_owned(),
+        args.include_hidden.to_string(),
+        args.respect_gitignore.to_string(),
+        args.follow_symlinks.to_string(),
+        args.max_depth.map_or(String::new(), |depth| depth.to_string()),
+        args.ignore_file.clone().unwrap_or_default(),
+        ignored_folders.join("\0"),
+    ]
+}
 
-            if let Ok(content) = fs::read_to_string(file_path) {
-                let file_results = search_in_content(
-                    &content,
-                    &query_regex,
-                    file_path,
-                    &project_base,
-                    context_lines,
-                );
-                if !file_results.is_empty() {
-                    let _ = tx.send(file_results);
-                }
-            }
-            WalkState::Continue
-        })
+/// explain 模式下补充统计遍历器自身过滤掉的条目：不带任何过滤规则重新遍历一次，
+/// 主遍历中没有出现过的条目按 ignored_folder、hidden、gitignore (包括 .ignore、.searchignore、ignore_file) 归类，
+/// 被过滤的目录只计一次，不再进入
+fn explain_walk_exclusions(
+    path: &Path,
+    args: &InputArgs,
+    ignored_folders: &HashSet<String>,
+    walked: &HashSet<PathBuf>,
+    context: &SearchContext,
+) {
+    let mut walker = WalkDir::new(path).follow_links(args.follow_symlinks);
+    if let Some(depth) = args.max_depth {
+        walker = walker.max_depth(depth + 1);
+    }
+    let entries = walker.into_iter().filter_entry(|entry| {
+        if entry.depth() == 0 || walked.contains(entry.path()) {
+            return true;
+        }
+        let name = entry.file_name().to_string_lossy();
+        let reason = if entry.file_type().is_dir() && ignored_folders.contains(name.as_ref()) {
+            "ignored_folder"
+        } else if !args.include_hidden && name.starts_with('.') {
+            "hidden"
+        } else {
+            "gitignore"
+        };
+        context.record_exclusion(entry.path(), reason);
+        false
     });
+    // 读取失败的目录在主遍历中已经记入 warnings
+    for _ in entries {}
+}
 
-    let mut results: Vec<SearchResult> = rx.into_iter().flatten().collect();
+/// 按 max_output_bytes 截断结果：依次累计每条结果序列化后的大小 (含分隔符)，
+/// 从第一条放不下的结果开始整条丢弃。返回是否发生了截断
+fn fit_output_budget(results: &mut Vec<SearchResult>, max_output_bytes: usize) -> bool {
+    if max_output_bytes == 0 {
+        return false;
+    }
+    let mut used = 0;
+    let overflow = results.iter().position(|result| {
+        used += serde_json::to_string(result).map_or(0, |json| json.len()) + 1;
+        used > max_output_bytes
+    });
+    match overflow {
+        Some(kept) => {
+            results.truncate(kept);
+            true
+        }
+        None => false,
+    }
+}
 
-    let truncated = if results.len() > config.max_results {
-        results.truncate(config.max_results);
-        true
-    } else {
-        false
+/// 按 sort 模式排序。各模式都以路径、行号、列号 (邻近搜索再加 near_lines) 作为次要排序键，
+/// 结果集相同时多次运行的顺序完全一致，offset/limit 分页才不会在页与页之间重复或漏掉结果
+fn sort_results(results: &mut [SearchResult], mode: &str) {
+    let by_position = |a: &SearchResult, b: &SearchResult| {
+        a.file_path
+            .cmp(&b.file_path)
+            .then(a.line_number.cmp(&b.line_number))
+            .then(a.match_column.cmp(&b.match_column))
+            .then(a.near_lines.cmp(&b.near_lines))
     };
+    match mode {
+        "path" => results.sort_by(by_position),
+        "matches" => results.sort_by(|a, b| b.file_match_count.cmp(&a.file_match_count).then(by_position(a, b))),
+        // 取不到修改时间的排在最后
+        "mtime" => results.sort_by(|a, b| b.file_mtime.cmp(&a.file_mtime).then(by_position(a, b))),
+        // "none"：文件按结果到达的先后排列，但同一文件的结果保持连续 (工作线程逐条发送，到达时可能交错)，
+        // 与单线程顺序遍历的形状一致，截断只会切开最后一个文件
+        _ => {
+            let mut first_seen: HashMap<String, usize> = HashMap::new();
+            for (i, result) in results.iter().enumerate() {
+                first_seen.entry(result.file_path.clone()).or_insert(i);
+            }
+            results.sort_by_key(|result| first_seen[&result.file_path]);
+        }
+    }
+}
 
-    Ok((results, truncated))
+/// 诊断输出用的绝对路径：能规范化时解析符号链接和 ..，否则按当前目录拼接
+fn absolute_path_string(path: &Path) -> String {
+    path.canonicalize()
+        .or_else(|_| std::path::absolute(path))
+        .unwrap_or_else(|_| path.to_path_buf())
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// 检查 path 规范化 (解析 ..、符号链接) 之后是否仍位于项目根目录内
+fn is_within_base(project_base: &Path, path: &Path) -> io::Result<bool> {
+    let canonical_base = project_base.canonicalize()?;
+    Ok(path.canonicalize()?.starts_with(canonical_base))
+}
+
+/// 同 is_within_base，但允许 path 尚不存在：检查最近的已存在上级目录，
+/// 不存在的部分只能由普通路径名组成 (其中的 .. 无法规范化，直接视为越界)
+fn is_creatable_within_base(project_base: &Path, path: &Path) -> io::Result<bool> {
+    let mut existing = path;
+    while fs::symlink_metadata(existing).is_err() {
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(_)) => existing = parent,
+            _ => return Ok(false),
+        }
+    }
+    is_within_base(project_base, existing)
+}
+
+/// 遍历错误中携带的路径 (ignore::Error 可能层层包装)
+fn walk_error_path(e: &ignore::Error) -> Option<&Path> {
+    match e {
+        ignore::Error::WithPath { path, .. } => Some(path),
+        ignore::Error::WithDepth { err, .. } | ignore::Error::WithLineNumber { err, .. } => walk_error_path(err),
+        ignore::Error::Loop { child, .. } => Some(child),
+        _ => None,
+    }
+}
+
+fn warning_kind(e: &io::Error) -> &'static str {
+    match e.kind() {
+        io::ErrorKind::NotFound => "not_found",
+        io::ErrorKind::PermissionDenied => "permission_denied",
+        io::ErrorKind::InvalidInput => "not_a_file",
+        io::ErrorKind::InvalidData => "decode_error",
+        io::ErrorKind::TimedOut => "timeout",
+        _ => "read_error",
+    }
+}
+
+fn count_matches(content: &str, ctx: &SearchContext, comment_prefixes: &[&str], window: Option<LineWindow>) -> usize {
+    if let Some(near) = &ctx.near {
+        let lines: Vec<&str> = content.lines().map(|line| line.trim_end_matches('\r')).collect();
+        return near_pairs(&lines, near, ctx, comment_prefixes)
+            .into_iter()
+            .filter(|&(a, b)| in_window(window, a) && in_window(window, b))
+            .count();
+    }
+    content
+        .lines()
+        .enumerate()
+        .take_while(|_| !ctx.out_of_time())
+        .filter(|&(i, line)| in_window(window, i) && ctx.is_reported_line(line, comment_prefixes))
+        .count()
+}
+
+/// 第 i 行 (0 起) 是否在 line_start/line_end 范围内，未指定范围时总是 true
+fn in_window(window: Option<LineWindow>, i: usize) -> bool {
+    window.is_none_or(|window| window.contains(i))
+}
+
+/// 注释前缀：显式指定 comment_style 时只用它，否则按文件扩展名推断
+fn comment_prefixes(style: Option<&str>, file_path: &Path) -> &'static [&'static str] {
+    match style {
+        Some("//") => &["//"],
+        Some("#") => &["#"],
+        Some("--") => &["--"],
+        Some("/*") => &["/*"],
+        Some(_) => &[],
+        None => match file_path.extension().and_then(|s| s.to_str()).unwrap_or("") {
+            "rs" | "js" | "jsx" | "ts" | "tsx" | "java" | "go" | "c" | "h" | "cpp" | "hpp"
+            | "cs" | "kt" | "swift" | "scala" | "dart" => &["//", "/*"],
+            "css" | "scss" | "less" => &["/*"],
+            "py" | "sh" | "bash" | "rb" | "pl" | "toml" | "yml" | "yaml" | "r" | "ps1"
+            | "env" | "conf" => &["#"],
+            "sql" | "lua" | "hs" => &["--"],
+            _ => &[],
+        },
+    }
+}
+
+fn is_comment_line(line: &str, comment_prefixes: &[&str]) -> bool {
+    let trimmed = line.trim_start();
+    comment_prefixes.iter().any(|p| trimmed.starts_with(p))
 }
 
 fn search_in_content(
     content: &str,
-    regex: &Regex,
     file_path: &Path,
-    project_base: &Path,
-    context_lines: usize,
+    ctx: &SearchContext,
+    comment_prefixes: &[&str],
+    window: Option<LineWindow>,
 ) -> Vec<SearchResult> {
-    let lines: Vec<&str> = content.lines().collect();
+    // lines() 只在 \r\n 成对出现时去掉 \r，孤立的行尾 \r (例如文件最后一行) 需要显式去掉，
+    // 否则会原样进入 line_content / context 并破坏下游解析
+    let lines: Vec<&str> = content.lines().map(|line| line.trim_end_matches('\r')).collect();
     let mut results = Vec::new();
-    
-    let relative_path = pathdiff::diff_paths(file_path, project_base)
-        .unwrap_or_else(|| file_path.to_path_buf());
-
-    for (i, line) in lines.iter().enumerate() {
-        if let Some(mat) = regex.find(line) {
-            let context_before = if i >= context_lines {
-                lines[i.saturating_sub(context_lines)..i]
-                    .iter()
-                    .map(|s| s.to_string())
-                    .collect()
-            } else {
-                lines[0..i].iter().map(|s| s.to_string()).collect()
-            };
+    // display_path 需要解析符号链接，只在文件有结果时才计算
+    let shown_path = OnceCell::new();
+    let shown_path = || shown_path.get_or_init(|| ctx.display_path(file_path)).as_str();
 
-            let end = std::cmp::min(i + 1 + context_lines, lines.len());
-            let context_after = lines[i + 1..end]
-                .iter()
-                .map(|s| s.to_string())
-                .collect();
-
-            results.push(SearchResult {
-                file_path: relative_path.to_string_lossy().into_owned(),
-                line_number: i + 1,
-                line_content: line.trim().to_string(),
-                context_before,
-                context_after,
-                match_column: mat.start(),
-            });
+    if let Some(near) = &ctx.near {
+        // 限定行范围时两个搜索词所在的行都必须在范围内
+        let pairs = near_pairs(&lines, near, ctx, comment_prefixes)
+            .into_iter()
+            .filter(|&(a, b)| in_window(window, a) && in_window(window, b));
+        for (a, b) in pairs {
+            if ctx.max_matches_per_file.is_some_and(|cap| results.len() >= cap) {
+                break;
+            }
+            // 结果行是靠前的一行，匹配位置取该行上对应的搜索词
+            let (start, end) = (a.min(b), a.max(b));
+            let (term, term_regex) = if start == a { &near.a } else { &near.b };
+            let mat = term_regex.find(lines[start]).map(Span::from);
+            let mut result = line_result(&lines, start, end, mat, ctx, shown_path());
+            result.matched_term = Some(term.clone());
+            result.near_lines = Some([a + 1, b + 1]);
+            results.push(result);
+        }
+        add_enclosing_symbols(&mut results, &lines, file_path, ctx);
+        set_line_ending(&mut results, content);
+        return results;
+    }
+
+    // 只有匹配行限定在范围内，上下文仍从整个文件中取
+    let range = window.map_or(0..lines.len(), |window| window.line_start - 1..window.line_end.min(lines.len()));
+    for (i, line) in lines.iter().enumerate().take(range.end).skip(range.start) {
+        if ctx.out_of_time() || ctx.max_matches_per_file.is_some_and(|cap| results.len() >= cap) {
+            break;
         }
+        if ctx.is_skipped_line(line, comment_prefixes) {
+            continue;
+        }
+        // invert_match 时报告不匹配的行，没有匹配位置
+        let mat = match (ctx.find(line), ctx.invert_match) {
+            (Some(mat), false) => Some(mat),
+            (None, true) => None,
+            _ => continue,
+        };
+        results.push(line_result(&lines, i, i, mat, ctx, shown_path()));
     }
 
+    add_enclosing_symbols(&mut results, &lines, file_path, ctx);
+    set_line_ending(&mut results, content);
     results
 }
 
+/// 按整个文件的换行符统一设置 line_ending，没有结果时不扫描
+fn set_line_ending(results: &mut [SearchResult], content: &str) {
+    if results.is_empty() {
+        return;
+    }
+    let line_breaks = content.matches('\n').count();
+    let crlf = content.matches("\r\n").count();
+    let line_ending = match crlf {
+        0 => "lf",
+        n if n == line_breaks => "crlf",
+        _ => "mixed",
+    };
+    for result in results {
+        result.line_ending = Some(line_ending.to_string());
+    }
+}
+
+/// enclosing_symbol：为每个结果补上其上方最近的定义行，扩展名不受支持时什么也不做
+fn add_enclosing_symbols(results: &mut [SearchResult], lines: &[&str], file_path: &Path, ctx: &SearchContext) {
+    if !ctx.enclosing_symbol {
+        return;
+    }
+    let Some(pattern) = symbols::definition_pattern(file_path) else {
+        return;
+    };
+    for result in results {
+        if let Some((i, text)) = symbols::find_enclosing(lines, result.line_number - 1, pattern) {
+            let text = text.trim();
+            result.enclosing_line = Some(i + 1);
+            result.enclosing_text = Some(
+                truncate_line(text, 0, ctx.max_line_length).map_or_else(|| text.to_string(), |t| t.text),
+            );
+        }
+    }
+}
+
+/// near 搜索：找出 a 所在行与 b 所在行相距不超过 distance 的全部 (a 行, b 行) 组合 (0 起)。
+/// 同一对行只算一次 (两行都同时匹配 a 和 b 时不会因交换而重复)，按靠前的行、再按靠后的行排序
+fn near_pairs(lines: &[&str], near: &NearSearch, ctx: &SearchContext, comment_prefixes: &[&str]) -> Vec<(usize, usize)> {
+    let matching = |re: &Regex| -> Vec<usize> {
+        lines
+            .iter()
+            .enumerate()
+            .take_while(|_| !ctx.out_of_time())
+            .filter(|(_, line)| !ctx.is_skipped_line(line, comment_prefixes) && re.is_match(line))
+            .map(|(i, _)| i)
+            .collect()
+    };
+    let a_lines = matching(&near.a.1);
+    let b_lines = matching(&near.b.1);
+
+    let mut pairs: BTreeMap<(usize, usize), (usize, usize)> = BTreeMap::new();
+    for &a in &a_lines {
+        // b_lines 有序，二分找到窗口起点
+        let from = b_lines.partition_point(|&b| b + near.distance < a);
+        for &b in b_lines[from..].iter().take_while(|&&b| b <= a + near.distance) {
+            pairs.entry((a.min(b), a.max(b))).or_insert((a, b));
+        }
+    }
+    pairs.into_values().collect()
+}
+
+/// 按 line_format 去掉行首和/或行尾的空白
+fn format_line<'a>(line: &'a str, line_format: &str) -> &'a str {
+    match line_format {
+        "trim" => line.trim(),
+        "trim_start" => line.trim_start(),
+        "trim_end" => line.trim_end(),
+        _ => line,
+    }
+}
+
+/// 为第 i 行 (0 起) 构造一条结果。context_after 从 span_end 行之后开始计算，
+/// 普通匹配时 span_end == i；near 搜索时 i..=span_end 整段都放进结果，作为合并的上下文块
+fn line_result(
+    lines: &[&str],
+    i: usize,
+    span_end: usize,
+    mat: Option<Span>,
+    ctx: &SearchContext,
+    shown_path: &str,
+) -> SearchResult {
+    let line = &lines[i];
+    let max_line_length = ctx.max_line_length;
+    let mut line_truncated = false;
+    let mut context_line = |s: &str| {
+        let s = format_line(s, ctx.line_format);
+        match truncate_line(s, 0, max_line_length) {
+            Some(truncated) => {
+                line_truncated = true;
+                truncated.text
+            }
+            None => s.to_string(),
+        }
+    };
+
+    let context_before = lines[i.saturating_sub(ctx.context_before)..i]
+        .iter()
+        .map(|s| context_line(s))
+        .collect();
+
+    let end = std::cmp::min(span_end + 1 + ctx.context_after, lines.len());
+    let context_after = lines[i + 1..end]
+        .iter()
+        .map(|s| context_line(s))
+        .collect();
+
+    let mut highlight_spans: Option<Vec<[usize; 2]>> = if ctx.include_spans && mat.is_some() {
+        Some(ctx.find_iter(line).map(|m| [m.start, m.end]).collect())
+    } else {
+        None
+    };
+
+    // 超长行以匹配位置为中心截断，match_column 和 highlight_spans 改为相对截断后的内容。
+    // 正则给出的是字节偏移，输出前统一换算成字符偏移
+    let trimmed = format_line(line, ctx.line_format);
+    let leading = match ctx.line_format {
+        "trim" | "trim_start" => line.len() - line.trim_start().len(),
+        _ => 0,
+    };
+    let focus = mat.map_or(0, |m| m.start.saturating_sub(leading));
+    // match_range：匹配在 line_content 中的 [start, end) 字符区间 (截断时超出窗口的部分收缩到窗口边界)
+    let (line_content, match_column, match_column_utf16, original_match_column, original_match_column_utf16, match_range) =
+        match truncate_line(trimmed, focus, max_line_length) {
+            Some(truncated) => {
+                line_truncated = true;
+                let to_content = |offset: usize| {
+                    offset.checked_sub(leading).and_then(|o| truncated.map_offset(o))
+                };
+                let column = mat.map(|m| to_content(m.start).unwrap_or(0));
+                highlight_spans = highlight_spans.map(|spans| {
+                    spans
+                        .into_iter()
+                        .filter_map(|[s, e]| {
+                            Some([
+                                char_offset(&truncated.text, to_content(s)?),
+                                char_offset(&truncated.text, to_content(e)?),
+                            ])
+                        })
+                        .collect()
+                });
+                let char_column = column.map(|c| char_offset(&truncated.text, c));
+                let utf16_column = column.map(|c| utf16_offset(&truncated.text, c));
+                let window_end = truncated.prefix_len + (truncated.end - truncated.start);
+                let match_range = mat.map(|m| {
+                    let start = to_content(m.start).unwrap_or(truncated.prefix_len);
+                    let end = to_content(m.end).unwrap_or(window_end);
+                    (char_offset(&truncated.text, start), char_offset(&truncated.text, end))
+                });
+                (
+                    truncated.text,
+                    char_column,
+                    utf16_column,
+                    mat.map(|m| char_offset(line, m.start)),
+                    mat.map(|m| utf16_offset(line, m.start)),
+                    match_range,
+                )
+            }
+            None => {
+                highlight_spans = highlight_spans.map(|spans| {
+                    spans
+                        .into_iter()
+                        .map(|[s, e]| [char_offset(line, s), char_offset(line, e)])
+                        .collect()
+                });
+                let to_content = |offset: usize| offset.saturating_sub(leading).min(trimmed.len());
+                let match_range = mat.map(|m| {
+                    (char_offset(trimmed, to_content(m.start)), char_offset(trimmed, to_content(m.end)))
+                });
+                (
+                    trimmed.to_string(),
+                    mat.map(|m| char_offset(line, m.start)),
+                    mat.map(|m| utf16_offset(line, m.start)),
+                    None,
+                    None,
+                    match_range,
+                )
+            }
+        };
+    // 与组合正则在同一位置开始匹配的搜索词；由 query_expansion 中的同义词匹配到时为空
+    let matched_term = mat.and_then(|mat| {
+        ctx.terms
+            .iter()
+            .find(|(_, re)| re.find_at(line, mat.start).is_some_and(|m| m.start() == mat.start))
+            .map(|(term, _)| term.clone())
+    });
+    let (captures, named_captures, captures_truncated) = match mat
+        .filter(|_| ctx.capture_groups)
+        .and_then(|mat| extract_captures(&ctx.regex, line, mat.start))
+    {
+        Some((captures, named, truncated)) => (Some(captures), Some(named).filter(|named| !named.is_empty()), truncated),
+        None => (None, None, false),
+    };
+    let leading_whitespace_removed = match line[..leading].chars().count() {
+        0 => None,
+        n => Some(n),
+    };
+
+    SearchResult {
+        file_path: shown_path.to_string(),
+        line_number: i + 1,
+        line_content,
+        context_before,
+        context_after,
+        match_column,
+        match_column_utf16,
+        leading_whitespace_removed,
+        line_truncated: if line_truncated { Some(true) } else { None },
+        original_match_column,
+        original_match_column_utf16,
+        highlight_spans,
+        matched_term,
+        matched_text: mat.map(|m| line[m.start..m.end].to_string()),
+        match_start: match_range.map(|(start, _)| start),
+        match_end: match_range.map(|(_, end)| end),
+        near_lines: None,
+        file_match_count: 0,
+        file_mtime: None,
+        line_ending: None,
+        file_size: None,
+        modified_at: None,
+        language: None,
+        enclosing_line: None,
+        enclosing_text: None,
+        context_hex: None,
+        captures,
+        named_captures,
+        captures_truncated: captures_truncated.then_some(true),
+    }
+}
+
+/// 命名捕获组：组名 → 文本，未参与匹配的组为 None
+type NamedCaptures = BTreeMap<String, Option<String>>;
+
+/// 从 start 处开始的那个匹配的各捕获组 (第 1 组起) 和其中的命名组。文本按组的顺序累计，合计超过 MAX_CAPTURE_BYTES 时
+/// 从超出的那一组起按字符边界截断，之后的组为空字符串；第三项表示是否发生了截断
+fn extract_captures(re: &Regex, line: &str, start: usize) -> Option<(Vec<Option<String>>, NamedCaptures, bool)> {
+    let caps = re.captures_at(line, start)?;
+    let mut remaining = MAX_CAPTURE_BYTES;
+    let mut truncated = false;
+    let captures: Vec<Option<String>> = caps
+        .iter()
+        .skip(1)
+        .map(|group| {
+            group.map(|m| {
+                let text = m.as_str();
+                let mut end = text.len().min(remaining);
+                while !text.is_char_boundary(end) {
+                    end -= 1;
+                }
+                truncated |= end < text.len();
+                remaining -= end;
+                text[..end].to_string()
+            })
+        })
+        .collect();
+    let named = re
+        .capture_names()
+        .skip(1)
+        .zip(&captures)
+        .filter_map(|(name, text)| Some((name?.to_string(), text.clone())))
+        .collect();
+    Some((captures, named, truncated))
+}
+
+/// 插入上下文分隔符：同一文件内按行号排列的结果中，前一个结果的上下文结束行与后一个结果的上下文起始行不相连时，
+/// 在前一个结果的 context_after 末尾追加 separator
+fn insert_context_separators(file_results: &mut [SearchResult], separator: &str) {
+    for i in 1..file_results.len() {
+        let (before, after) = file_results.split_at_mut(i);
+        let previous = &mut before[i - 1];
+        let next = &after[0];
+        // 邻近搜索的上下文从两行中靠后的一行开始向后延伸
+        let previous_end = previous.near_lines.map_or(previous.line_number, |lines| lines[0].max(lines[1]))
+            + previous.context_after.len();
+        let next_start = next.line_number.saturating_sub(next.context_before.len());
+        if previous_end + 1 < next_start {
+            previous.context_after.push(separator.to_string());
+        }
+    }
+}
+
+/// format=text：grep 风格的输出。匹配行为 path:line:column: content (invert_match 时没有列，列号从 1 起)，
+/// 上下文行为 path-line-content；同一文件中重叠或相邻的结果合并成一块，块之间用 separator 分隔
+fn render_text(results: &[SearchResult], separator: Option<&str>) -> String {
+    let mut out = String::new();
+    let mut previous_block: Option<(&str, usize)> = None;  // 上一块的文件和最后一行
+    for group in results.chunk_by(|a, b| a.file_path == b.file_path) {
+        let path = group[0].file_path.as_str();
+        // 行号 -> (是否匹配行, 输出内容)。匹配行覆盖同一行上作为其他结果上下文的内容
+        let mut lines: BTreeMap<usize, (bool, String)> = BTreeMap::new();
+        for result in group {
+            let first = result.line_number - result.context_before.len();
+            let context = result.context_before.iter().chain(&result.context_after);
+            let numbers = (first..result.line_number).chain(result.line_number + 1..);
+            for (n, text) in numbers.zip(context) {
+                lines.entry(n).or_insert_with(|| (false, text.clone()));
+            }
+            let text = match result.match_column {
+                Some(column) => format!("{}: {}", column + 1, result.line_content),
+                None => format!(" {}", result.line_content),
+            };
+            lines.insert(result.line_number, (true, text));
+        }
+        for (n, (is_match, text)) in lines {
+            let adjacent = previous_block.is_some_and(|(file, last)| file == path && n == last + 1);
+            if let Some(separator) = separator.filter(|_| previous_block.is_some() && !adjacent) {
+                out.push_str(separator);
+                out.push('\n');
+            }
+            if is_match {
+                out.push_str(&format!("{}:{}:{}\n", path, n, text));
+            } else {
+                out.push_str(&format!("{}-{}-{}\n", path, n, text));
+            }
+            previous_block = Some((path, n));
+        }
+    }
+    out
+}
+
+/// 按文件分组，保持结果中文件首次出现的顺序
+fn group_by_file(results: &[SearchResult]) -> Vec<FileGroup> {
+    let mut groups: Vec<FileGroup> = Vec::new();
+    for result in results {
+        match groups.last_mut() {
+            Some(group) if group.file_path == result.file_path => group.matches.push(result.clone()),
+            _ => groups.push(FileGroup {
+                file_path: result.file_path.clone(),
+                matches: vec![result.clone()],
+            }),
+        }
+    }
+    groups
+}
+
+/// 按文件所在目录分组，根目录下的文件归入 "."
+fn group_by_directory(results: &[SearchResult]) -> Vec<DirectoryGroup> {
+    let mut groups: Vec<DirectoryGroup> = Vec::new();
+    for file in group_by_file(results) {
+        let directory = match Path::new(&file.file_path).parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_string_lossy().into_owned(),
+            _ => ".".to_string(),
+        };
+        let index = match groups.iter().position(|group| group.directory == directory) {
+            Some(index) => index,
+            None => {
+                groups.push(DirectoryGroup {
+                    directory,
+                    files: Vec::new(),
+                    total_matches: 0,
+                });
+                groups.len() - 1
+            }
+        };
+        groups[index].total_matches += file.matches.len();
+        groups[index].files.push(file);
+    }
+    groups
+}
+
+/// 用 scan_file 已经取得的 metadata 填充 file_size/modified_at/language，不再额外 stat
+fn add_file_metadata(results: &mut [SearchResult], file_path: &Path, metadata: &fs::Metadata) {
+    let modified_at = metadata
+        .modified()
+        .ok()
+        .map(|time| DateTime::<Utc>::from(time).to_rfc3339_opts(SecondsFormat::Secs, true));
+    let language = file_path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(|ext| FILE_TYPES.iter().find(|(_, exts)| exts.contains(&ext)))
+        .map(|(name, _)| name.to_string());
+    for result in results {
+        result.file_size = Some(metadata.len());
+        result.modified_at = modified_at.clone();
+        result.language = language.clone();
+    }
+}
+
+/// output_mode=dirs：按相对项目根目录的前 depth 级目录汇总每个文件的匹配数，
+/// 浅于 depth 的文件归入其所在目录，根目录下的文件归入 "."
+fn summarize_directories(counts: FileMatchCounts, project_base: &Path, depth: usize) -> Vec<DirectorySummary> {
+    let mut directories: BTreeMap<String, Vec<(String, usize)>> = BTreeMap::new();
+    for (path, match_count) in counts {
+        let relative = pathdiff::diff_paths(&path, project_base).unwrap_or(path);
+        let directory: PathBuf = relative.parent().into_iter().flat_map(Path::iter).take(depth).collect();
+        let directory = match directory.as_os_str().is_empty() {
+            true => ".".to_string(),
+            false => directory.to_string_lossy().into_owned(),
+        };
+        directories
+            .entry(directory)
+            .or_default()
+            .push((relative.to_string_lossy().into_owned(), match_count));
+    }
+
+    let mut summaries: Vec<DirectorySummary> = directories
+        .into_iter()
+        .map(|(directory, mut files)| {
+            files.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            DirectorySummary {
+                directory,
+                match_count: files.iter().map(|(_, count)| count).sum(),
+                file_count: files.len(),
+                example_files: files.into_iter().take(MAX_DIR_EXAMPLE_FILES).map(|(path, _)| path).collect(),
+            }
+        })
+        .collect();
+    // BTreeMap 已按目录名排好，稳定排序后匹配数相同的目录保持字母顺序
+    summaries.sort_by_key(|summary| std::cmp::Reverse(summary.match_count));
+    summaries
+}
+
+/// 字节偏移 → 字符偏移
+fn char_offset(s: &str, byte_offset: usize) -> usize {
+    s[..byte_offset].chars().count()
+}
+
+/// 字节偏移 → UTF-16 码元偏移
+fn utf16_offset(s: &str, byte_offset: usize) -> usize {
+    s[..byte_offset].encode_utf16().count()
+}
+
+/// 把同一文件中上下文窗口重叠或相邻的匹配合并为代码块。
+/// 同一行既是某个匹配的上下文又是另一个匹配行时，采用匹配行的内容 (截断以匹配位置为中心)
+fn merge_into_blocks(results: Vec<SearchResult>) -> Vec<ResultBlock> {
+    struct PendingBlock {
+        file_path: String,
+        lines: BTreeMap<usize, String>,
+        matches: Vec<BlockMatch>,
+    }
+
+    impl PendingBlock {
+        fn end_line(&self) -> usize {
+            self.lines.keys().next_back().copied().unwrap_or(0)
+        }
+
+        fn finish(self) -> ResultBlock {
+            ResultBlock {
+                start_line: self.lines.keys().next().copied().unwrap_or(0),
+                end_line: self.end_line(),
+                file_path: self.file_path,
+                lines: self.lines.into_values().collect(),
+                matches: self.matches,
+            }
+        }
+    }
+
+    let mut blocks = Vec::new();
+    let mut current: Option<PendingBlock> = None;
+    for result in results {
+        let start_line = result.line_number - result.context_before.len();
+        let joins_current = current.as_ref().is_some_and(|block| {
+            block.file_path == result.file_path && start_line <= block.end_line() + 1
+        });
+        if !joins_current {
+            blocks.extend(current.take().map(PendingBlock::finish));
+        }
+        let block = current.get_or_insert_with(|| PendingBlock {
+            file_path: result.file_path.clone(),
+            lines: BTreeMap::new(),
+            matches: Vec::new(),
+        });
+
+        for (offset, line) in result.context_before.into_iter().enumerate() {
+            block.lines.entry(start_line + offset).or_insert(line);
+        }
+        block.lines.insert(result.line_number, result.line_content);
+        for (offset, line) in result.context_after.into_iter().enumerate() {
+            block.lines.entry(result.line_number + 1 + offset).or_insert(line);
+        }
+        block.matches.push(BlockMatch {
+            line_number: result.line_number,
+            column: result.match_column,
+        });
+    }
+    blocks.extend(current.map(PendingBlock::finish));
+    blocks
+}
+
+/// 截断后的行
+struct TruncatedLine {
+    text: String,
+    start: usize,       // 保留窗口在原文中的起始字节
+    end: usize,         // 保留窗口在原文中的结束字节
+    prefix_len: usize,  // 开头省略号占用的字节数
+}
+
+impl TruncatedLine {
+    /// 把原文中的字节偏移映射到截断后的文本中，落在保留窗口之外时返回 None
+    fn map_offset(&self, offset: usize) -> Option<usize> {
+        (self.start..=self.end)
+            .contains(&offset)
+            .then(|| offset - self.start + self.prefix_len)
+    }
+}
+
+/// 截断超过 max_len 个字符的行：以 focus (字节偏移) 附近为中心保留 max_len 个字符，
+/// 被截掉的一端用省略号标记。不需要截断时返回 None
+fn truncate_line(line: &str, focus: usize, max_len: usize) -> Option<TruncatedLine> {
+    let char_count = line.chars().count();
+    if max_len == 0 || char_count <= max_len {
+        return None;
+    }
+
+    let focus = focus.min(line.len());
+    let focus_char = line[..focus].chars().count();
+    let start_char = focus_char.saturating_sub(max_len / 2).min(char_count - max_len);
+    let end_char = start_char + max_len;
+
+    let byte_at = |n: usize| line.char_indices().nth(n).map(|(b, _)| b).unwrap_or(line.len());
+    let start = byte_at(start_char);
+    let end = byte_at(end_char);
+
+    let mut text = String::with_capacity(end - start + 2 * TRUNCATION_MARKER.len());
+    if start > 0 {
+        text.push_str(TRUNCATION_MARKER);
+    }
+    let prefix_len = text.len();
+    text.push_str(&line[start..end]);
+    if end < line.len() {
+        text.push_str(TRUNCATION_MARKER);
+    }
+    Some(TruncatedLine {
+        text,
+        start,
+        end,
+        prefix_len,
+    })
+}
+
+fn print_stream_record(record: &StreamRecord) {
+    if let Ok(json) = serde_json::to_string(record) {
+        print_json_line(&json);
+    }
+}
+
+fn print_json_line(json: &str) {
+    let mut stdout = io::stdout().lock();
+    let _ = writeln!(stdout, "{}", json);
+    let _ = stdout.flush();
+}
+
+/// print_stats：在 stdout 的 JSON 写完之后向 stderr 输出一行统计
+fn print_stats_line(stats: &SearchStats, elapsed: Duration) {
+    eprintln!(
+        "[CodeSearcher] files_walked={} files_searched={} matches={} peak_pending={} elapsed={}ms",
+        stats.files_walked,
+        stats.files_scanned,
+        stats.total_matches,
+        stats.peak_pending_results,
+        elapsed.as_millis()
+    );
+}
+
+/// 请求中有效的 format。错误可能在参数校验的任何一步产生，print_error 据此决定输出方式
+static OUTPUT_FORMAT: OnceLock<&'static str> = OnceLock::new();
+
 fn print_error(message: String) {
+    match OUTPUT_FORMAT.get().copied() {
+        Some("text") => {
+            eprintln!("[CodeSearcher] error: {}", message);
+            println!("status=error");
+            return;
+        }
+        Some("sarif") => {
+            if let Ok(json) = serde_json::to_string(&SarifLog::error(message)) {
+                println!("{}", json);
+            }
+            return;
+        }
+        _ => {}
+    }
     let output = Output {
         status: "error".to_string(),
         result: None,
         error: Some(message),
-        truncated: None,
+        ..Default::default()
     };
     if let Ok(json) = serde_json::to_string(&output) {
         println!("{}", json);
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests;
\ No newline at end of file
@@ -0,0 +1,245 @@
+// format=sarif 的输出：最小的 SARIF 2.1.0 文档 (一个 run、一条由搜索词生成的规则、每个匹配一个 result)，
+// 供 GitHub code scanning 等工具导入。截断和警告放在 invocations[0].toolExecutionNotifications 中
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::SearchResult;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const SARIF_VERSION: &str = "2.1.0";
+const RULE_ID: &str = "codesearcher/match";
+const SRCROOT: &str = "%SRCROOT%";
+
+#[derive(Serialize)]
+pub struct SarifLog {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    version: &'static str,
+    runs: Vec<Run>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Run {
+    tool: Tool,
+    invocations: Vec<Invocation>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    original_uri_base_ids: BTreeMap<&'static str, ArtifactLocation>,
+    column_kind: &'static str,  // 列号按 UTF-16 码元计，与 match_column_utf16 一致
+    results: Vec<SarifResult>,
+}
+
+#[derive(Serialize)]
+struct Tool {
+    driver: Driver,
+}
+
+#[derive(Serialize)]
+struct Driver {
+    name: &'static str,
+    version: &'static str,
+    rules: Vec<Rule>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Rule {
+    id: &'static str,
+    short_description: Message,
+}
+
+#[derive(Serialize)]
+struct Message {
+    text: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Invocation {
+    execution_successful: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tool_execution_notifications: Vec<Notification>,
+}
+
+#[derive(Serialize)]
+struct Notification {
+    level: &'static str,  // "error"、"warning" 或 "note"
+    message: Message,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult {
+    rule_id: &'static str,
+    rule_index: usize,
+    level: &'static str,
+    message: Message,
+    locations: Vec<Location>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Location {
+    physical_location: PhysicalLocation,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PhysicalLocation {
+    artifact_location: ArtifactLocation,
+    region: Region,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ArtifactLocation {
+    uri: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    uri_base_id: Option<&'static str>,
+}
+
+/// 行列号都从 1 起，endColumn 是匹配之后的第一列；invert_match 的结果没有列
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Region {
+    start_line: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    start_column: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    end_column: Option<usize>,
+}
+
+impl SarifLog {
+    /// terms 用于规则描述；notifications 为 (level, 文本)，如截断原因和各类警告
+    pub fn new(
+        results: &[SearchResult],
+        terms: &[&str],
+        project_base: &Path,
+        notifications: Vec<(&'static str, String)>,
+    ) -> Self {
+        let terms: Vec<String> = terms.iter().map(|term| format!("{:?}", term)).collect();
+        let results = results
+            .iter()
+            .map(|result| SarifResult {
+                rule_id: RULE_ID,
+                rule_index: 0,
+                level: "note",
+                message: Message {
+                    text: result.line_content.trim().to_string(),
+                },
+                locations: vec![Location {
+                    physical_location: PhysicalLocation {
+                        artifact_location: ArtifactLocation {
+                            uri: relative_uri(Path::new(&result.file_path), project_base),
+                            uri_base_id: Some(SRCROOT),
+                        },
+                        region: Region {
+                            start_line: result.line_number,
+                            start_column: original_column(result).map(|column| column + 1),
+                            end_column: original_column(result).zip(result.matched_text.as_ref()).map(
+                                |(column, text)| column + 1 + text.encode_utf16().count(),
+                            ),
+                        },
+                    },
+                }],
+            })
+            .collect();
+        let mut run = Run::new(true, notifications);
+        run.tool.driver.rules.push(Rule {
+            id: RULE_ID,
+            short_description: Message {
+                text: format!("Matches of {}", terms.join(", ")),
+            },
+        });
+        run.original_uri_base_ids.insert(
+            SRCROOT,
+            ArtifactLocation {
+                uri: directory_uri(project_base),
+                uri_base_id: None,
+            },
+        );
+        run.results = results;
+        SarifLog::from_run(run)
+    }
+
+    /// 搜索没有执行成功时的文档：没有结果，错误信息作为 error 级别的通知
+    pub fn error(message: String) -> Self {
+        SarifLog::from_run(Run::new(false, vec![("error", message)]))
+    }
+
+    fn from_run(run: Run) -> Self {
+        SarifLog {
+            schema: SARIF_SCHEMA,
+            version: SARIF_VERSION,
+            runs: vec![run],
+        }
+    }
+}
+
+impl Run {
+    fn new(execution_successful: bool, notifications: Vec<(&'static str, String)>) -> Self {
+        Run {
+            tool: Tool {
+                driver: Driver {
+                    name: "CodeSearcher",
+                    version: env!("CARGO_PKG_VERSION"),
+                    rules: Vec::new(),
+                },
+            },
+            invocations: vec![Invocation {
+                execution_successful,
+                tool_execution_notifications: notifications
+                    .into_iter()
+                    .map(|(level, text)| Notification {
+                        level,
+                        message: Message { text },
+                    })
+                    .collect(),
+            }],
+            original_uri_base_ids: BTreeMap::new(),
+            column_kind: "utf16CodeUnits",
+            results: Vec::new(),
+        }
+    }
+}
+
+/// 匹配在原始行中的 UTF-16 列 (0 起)。行被截断时 match_column_utf16 相对截断后的内容，
+/// SARIF 的列指向源文件，所以优先用截断前的列
+fn original_column(result: &SearchResult) -> Option<usize> {
+    result.original_match_column_utf16.or(result.match_column_utf16)
+}
+
+/// 结果路径相对项目根目录的 URI (absolute_paths 时先转回相对路径)，分隔符统一为 /
+fn relative_uri(path: &Path, project_base: &Path) -> String {
+    let relative = if path.is_absolute() {
+        pathdiff::diff_paths(path, project_base).unwrap_or_else(|| path.to_path_buf())
+    } else {
+        path.to_path_buf()
+    };
+    percent_encode(&relative.to_string_lossy().replace('\\', "/"), false)
+}
+
+/// 项目根目录的 file URI，以 / 结尾以便作为 uriBaseId 拼接相对路径
+fn directory_uri(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let path = path.trim_end_matches('/');
+    // Windows 路径 (C:/...) 前面也需要一个 /
+    let slash = if path.starts_with('/') { "" } else { "/" };
+    format!("file://{}{}/", slash, percent_encode(path, true))
+}
+
+/// 对 URI 路径中 RFC 3986 unreserved 字符和 / 以外的字节做百分号编码。
+/// 相对 URI 中的 : 必须编码，否则第一段会被当成 scheme；绝对 file URI 保留 Windows 盘符的 :
+fn percent_encode(path: &str, keep_colon: bool) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => encoded.push(byte as char),
+            b':' if keep_colon => encoded.push(':'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
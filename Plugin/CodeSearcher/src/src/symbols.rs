@@ -0,0 +1,204 @@
+// enclosing_symbol 使用的定义行启发式：按扩展名用正则识别函数、类型等定义所在的行。
+// 只是近似，不解析语法：字符串或注释里出现的定义关键字同样会被当成定义行
+
+use regex::Regex;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// 从匹配行向上最多查找的行数，超出后视为没有外层定义
+pub const MAX_LOOKBACK: usize = 200;
+
+/// (扩展名, 定义行正则)。Java 方法必须带修饰符才能和普通调用语句区分开；
+/// JS/TS 只识别 function、class 和赋值给变量的函数，不识别类里的方法简写
+const DEFINITION_PATTERNS: [(&[&str], &str); 6] = [
+    (
+        &["rs"],
+        r#"^\s*(?:pub(?:\([^)]*\))?\s+)?(?:(?:async|const|unsafe|default|extern\s+"[^"]*")\s+)*(?:fn|impl|trait|struct|enum|union|mod|macro_rules!)[\s<{(]"#,
+    ),
+    (
+        &["js", "jsx", "mjs", "cjs"],
+        r"^\s*(?:export\s+(?:default\s+)?)?(?:(?:async\s+)?function\b|class\b|(?:const|let|var)\s+[\w$]+\s*=\s*(?:async\s+)?(?:function\b|\([^)]*\)\s*=>|[\w$]+\s*=>))",
+    ),
+    (
+        &["ts", "tsx", "mts", "cts"],
+        r"^\s*(?:export\s+(?:default\s+)?)?(?:declare\s+)?(?:(?:async\s+)?function\b|(?:abstract\s+)?class\b|interface\b|enum\b|namespace\b|(?:const|let|var)\s+[\w$]+\s*(?::[^=]+)?=\s*(?:async\s+)?(?:function\b|\([^)]*\)\s*(?::[^=]+)?=>|[\w$]+\s*=>))",
+    ),
+    (&["py", "pyi"], r"^\s*(?:async\s+)?(?:def|class)\s+\w+"),
+    (&["go"], r"^\s*(?:func\b|type\s+\w+\s+(?:struct|interface)\b)"),
+    (
+        &["java"],
+        r"^\s*(?:@\w+\s+)*(?:(?:(?:public|protected|private|static|final|abstract|sealed)\s+)*(?:class|interface|enum|record)\s+\w+|(?:(?:public|protected|private|static|final|abstract|synchronized|native|default)\s+)+[\w<>\[\],.?\s]*?\w+\s*\()",
+    ),
+];
+
+fn compiled_patterns() -> &'static [(&'static [&'static str], Regex)] {
+    static PATTERNS: OnceLock<Vec<(&[&str], Regex)>> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        DEFINITION_PATTERNS
+            .iter()
+            .filter_map(|(extensions, pattern)| Regex::new(pattern).ok().map(|re| (*extensions, re)))
+            .collect()
+    })
+}
+
+/// 文件扩展名对应的定义行正则，不支持的语言返回 None
+pub fn definition_pattern(file_path: &Path) -> Option<&'static Regex> {
+    let ext = file_path.extension()?.to_str()?;
+    compiled_patterns()
+        .iter()
+        .find(|(extensions, _)| extensions.contains(&ext))
+        .map(|(_, re)| re)
+}
+
+/// 从 line_index (0 起) 的上一行开始向上查找最近的定义行，返回其行下标和内容
+pub fn find_enclosing<'a>(lines: &[&'a str], line_index: usize, pattern: &Regex) -> Option<(usize, &'a str)> {
+    let start = line_index.saturating_sub(MAX_LOOKBACK);
+    (start..line_index.min(lines.len()))
+        .rev()
+        .find(|&i| pattern.is_match(lines[i]))
+        .map(|i| (i, lines[i]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 在 file_name 对应语言的 source 中，从含 marker 的行向上查找外层定义，返回定义行 (去掉首尾空白)
+    fn enclosing(file_name: &str, source: &str, marker: &str) -> Option<String> {
+        let pattern = definition_pattern(Path::new(file_name)).expect("supported extension");
+        let lines: Vec<&str> = source.lines().collect();
+        let index = lines.iter().position(|line| line.contains(marker)).expect("marker line");
+        find_enclosing(&lines, index, pattern).map(|(_, line)| line.trim().to_string())
+    }
+
+    #[test]
+    fn all_patterns_compile() {
+        assert_eq!(compiled_patterns().len(), DEFINITION_PATTERNS.len());
+    }
+
+    #[test]
+    fn rust() {
+        let source = r#"
+pub(crate) struct Config {
+    value: u32,
+}
+
+impl<T> Display for Wrapper<T> {
+    pub async unsafe fn load(&self) {
+        let x = call(1);
+        let y = x + MARKER;
+    }
+}
+
+extern "C" fn callback() {
+    MARKER2;
+}
+"#;
+        assert_eq!(enclosing("a.rs", source, "MARKER;").as_deref(), Some("pub async unsafe fn load(&self) {"));
+        assert_eq!(enclosing("a.rs", source, "MARKER2").as_deref(), Some(r#"extern "C" fn callback() {"#));
+        assert_eq!(enclosing("a.rs", source, "value").as_deref(), Some("pub(crate) struct Config {"));
+    }
+
+    #[test]
+    fn javascript() {
+        let source = "
+export default class Widget {
+  render() {
+    return MARKER;
+  }
+}
+const handler = async (event) => {
+  process(event);
+  MARKER2;
+};
+";
+        assert_eq!(enclosing("a.js", source, "MARKER;").as_deref(), Some("export default class Widget {"));
+        assert_eq!(enclosing("a.mjs", source, "MARKER2").as_deref(), Some("const handler = async (event) => {"));
+    }
+
+    #[test]
+    fn typescript() {
+        let source = "
+export interface Options {
+  MARKER: string;
+}
+export const parse = (input: string): Options => {
+  return helper(input, MARKER2);
+};
+declare namespace Lib {
+  MARKER3;
+}
+";
+        assert_eq!(enclosing("a.ts", source, "MARKER:").as_deref(), Some("export interface Options {"));
+        assert_eq!(
+            enclosing("a.tsx", source, "MARKER2").as_deref(),
+            Some("export const parse = (input: string): Options => {")
+        );
+        assert_eq!(enclosing("a.ts", source, "MARKER3").as_deref(), Some("declare namespace Lib {"));
+    }
+
+    #[test]
+    fn python() {
+        let source = "
+class Parser:
+    async def parse(self, text):
+        result = helper(text)
+        return MARKER
+";
+        assert_eq!(enclosing("a.py", source, "MARKER").as_deref(), Some("async def parse(self, text):"));
+        assert_eq!(enclosing("a.py", source, "async def").as_deref(), Some("class Parser:"));
+    }
+
+    #[test]
+    fn go() {
+        let source = "
+type Server struct {
+	MARKER int
+}
+
+func (s *Server) Run() error {
+	return MARKER2
+}
+";
+        assert_eq!(enclosing("a.go", source, "MARKER int").as_deref(), Some("type Server struct {"));
+        assert_eq!(enclosing("a.go", source, "MARKER2").as_deref(), Some("func (s *Server) Run() error {"));
+    }
+
+    #[test]
+    fn java() {
+        let source = "
+@Service public final class UserService {
+    private final Repository repo;
+
+    public List<User> findAll(int limit) {
+        helper(limit);
+        return MARKER;
+    }
+}
+";
+        // 中间的 helper(limit); 不带修饰符，不会被当成方法定义
+        assert_eq!(
+            enclosing("A.java", source, "MARKER").as_deref(),
+            Some("public List<User> findAll(int limit) {")
+        );
+        assert_eq!(
+            enclosing("A.java", source, "public List").as_deref(),
+            Some("@Service public final class UserService {")
+        );
+    }
+
+    #[test]
+    fn unsupported_extension() {
+        assert!(definition_pattern(Path::new("a.txt")).is_none());
+        assert!(definition_pattern(Path::new("Makefile")).is_none());
+    }
+
+    #[test]
+    fn lookback_is_limited() {
+        let pattern = definition_pattern(Path::new("a.rs")).unwrap();
+        let mut lines = vec!["fn outer() {"];
+        lines.extend(std::iter::repeat_n("    x;", MAX_LOOKBACK + 1));
+        assert_eq!(find_enclosing(&lines, MAX_LOOKBACK, pattern), Some((0, "fn outer() {")));
+        assert_eq!(find_enclosing(&lines, MAX_LOOKBACK + 1, pattern), None);
+    }
+}
@@ -0,0 +1,101 @@
+use super::*;
+use serde_json::{json, Value};
+
+fn parse_args(args: Value) -> Result<InputArgs, serde_json::Error> {
+    serde_json::from_value(args)
+}
+
+#[test]
+fn lenient_bool() {
+    for (value, expected) in [
+        (json!(true), true),
+        (json!(false), false),
+        (json!("true"), true),
+        (json!("TRUE"), true),
+        (json!("false"), false),
+        (json!(1), true),
+        (json!(0), false),
+        (json!("1"), true),
+        (json!("0"), false),
+    ] {
+        let args = parse_args(json!({ "invert_match": value })).unwrap_or_else(|e| panic!("{}: {}", value, e));
+        assert_eq!(args.invert_match, expected, "{}", value);
+    }
+    for value in [json!(3), json!(-1), json!("3"), json!("maybe"), json!(""), json!(null)] {
+        assert!(parse_args(json!({ "invert_match": value })).is_err(), "{} accepted", value);
+    }
+}
+
+#[test]
+fn lenient_optional_bool() {
+    assert_eq!(parse_args(json!({})).unwrap().ignore_test_files, None);
+    assert_eq!(parse_args(json!({ "ignore_test_files": "1" })).unwrap().ignore_test_files, Some(true));
+    assert!(parse_args(json!({ "ignore_test_files": "maybe" })).is_err());
+}
+
+#[test]
+fn lenient_usize() {
+    for (value, expected) in [(json!(3), 3), (json!("3"), 3), (json!(" 3 "), 3), (json!(0), 0)] {
+        let args = parse_args(json!({ "max_matches_per_file": value })).unwrap_or_else(|e| panic!("{}: {}", value, e));
+        assert_eq!(args.max_matches_per_file, Some(expected), "{}", value);
+    }
+    for value in [json!(-1), json!("-1"), json!("3.5"), json!(3.5), json!("maybe"), json!(true)] {
+        assert!(parse_args(json!({ "max_matches_per_file": value })).is_err(), "{} accepted", value);
+    }
+}
+
+#[test]
+fn lenient_schemas() {
+    let schema = serde_json::to_value(schema_for!(InputArgs)).unwrap();
+    let types = |property: &Value| -> Vec<String> {
+        property["oneOf"]
+            .as_array()
+            .unwrap_or_else(|| panic!("no oneOf: {}", property))
+            .iter()
+            .map(|variant| variant["type"].as_str().unwrap().to_string())
+            .collect()
+    };
+    let properties = &schema["properties"];
+    assert_eq!(types(&properties["invert_match"]), ["boolean", "string", "integer"]);
+    assert_eq!(types(&properties["ignore_test_files"]), ["boolean", "string", "integer"]);
+    assert_eq!(types(&properties["max_matches_per_file"]), ["integer", "string"]);
+    assert_eq!(types(&properties["max_line_length"]), ["integer", "string"]);
+    assert_eq!(types(&schema["definitions"]["NearArgs"]["properties"]["distance"]), ["integer", "string"]);
+}
+
+/// 单元测试用的临时目录，离开作用域时删除
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let path = env::temp_dir().join(format!("codesearcher-unit-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        TempDir(path.canonicalize().unwrap())
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn result_channel_stays_bounded() {
+    let dir = TempDir::new("channel");
+    let contents = "needle\n".repeat(1000);
+    for i in 0..8 {
+        fs::write(dir.0.join(format!("{}.rs", i)), &contents).unwrap();
+    }
+    let args = parse_args(json!({ "query": "needle", "context_lines": 0 })).unwrap();
+    let mut config = AppConfig::from_vars(&dir.0, |_| None).unwrap();
+    config.threads = 4;
+    let regex = build_regex(&args).unwrap();
+
+    let outcome = search_in_directory(&dir.0, &regex, &[], false, &config, &args, &dir.0).unwrap();
+    assert_eq!(outcome.stats.total_matches, 8000);
+    let peak = outcome.stats.peak_pending_results;
+    assert!(peak > 0);
+    assert!(peak <= RESULT_CHANNEL_CAPACITY + 1, "peak {} exceeds channel capacity", peak);
+}
@@ -0,0 +1,119 @@
+// cache_dir 下的目录遍历缓存：完整遍历一次后记下遍历到的目录 (mtime 及其中忽略文件的 mtime) 和文件 (路径、大小、mtime)，
+// 之后的请求在这些目录和忽略文件都没有变化时直接复用文件列表，省掉目录遍历和忽略规则匹配。
+// 目录中增删、重命名条目会改变目录自身的 mtime，修改文件内容不会，但文件内容本来就在搜索时重新读取
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+
+const WALK_CACHE_VERSION: &str = "1";
+/// 遍历器会读取的忽略文件名，修改它们会改变遍历结果但不改变所在目录的 mtime
+const IGNORE_FILE_NAMES: [&str; 4] = [".gitignore", ".ignore", ".searchignore", ".rgignore"];
+
+/// 遍历时记录的单个文件。复用时仍会重新 stat，size 和 mtime 只用于排查
+#[derive(Serialize, Deserialize)]
+pub struct CachedFile {
+    pub path: PathBuf,
+    pub size: u64,
+    pub mtime: u128,
+}
+
+#[derive(Serialize, Deserialize)]
+struct WalkCache {
+    dirs: Vec<(PathBuf, u128)>,
+    ignore_files: Vec<(PathBuf, Option<u128>)>,  // 不存在的忽略文件记为 None，之后被创建同样使缓存失效
+    files: Vec<CachedFile>,
+}
+
+/// 遍历时收集目录和文件，多个遍历线程共用
+#[derive(Default)]
+pub struct WalkRecorder {
+    dirs: Mutex<Vec<(PathBuf, u128)>>,
+    files: Mutex<Vec<CachedFile>>,
+}
+
+impl WalkRecorder {
+    /// 在读取目录内容之前调用：此时记下的 mtime 早于读取，读取期间目录再有变化也会使缓存失效
+    pub fn record_dir(&self, path: &Path) {
+        if let (Some(mtime), Ok(mut dirs)) = (stat_mtime(path), self.dirs.lock()) {
+            dirs.push((path.to_path_buf(), mtime));
+        }
+    }
+
+    pub fn record_file(&self, path: &Path) {
+        let Ok(metadata) = fs::metadata(path) else {
+            return;
+        };
+        if let Ok(mut files) = self.files.lock() {
+            files.push(CachedFile {
+                path: path.to_path_buf(),
+                size: metadata.len(),
+                mtime: mtime_nanos(&metadata),
+            });
+        }
+    }
+
+    /// 遍历完整结束后写入缓存。ancestor_dirs 是搜索根目录的上级目录，其中的忽略文件同样影响遍历；
+    /// extra_ignore_files 是 ignore_file 指定的文件。写入失败直接忽略
+    pub fn save(self, cache_path: &Path, ancestor_dirs: &[PathBuf], extra_ignore_files: &[PathBuf]) {
+        let dirs = self.dirs.into_inner().unwrap_or_default();
+        let files = self.files.into_inner().unwrap_or_default();
+        let ignore_files = dirs
+            .iter()
+            .map(|(dir, _)| dir.as_path())
+            .chain(ancestor_dirs.iter().map(PathBuf::as_path))
+            .flat_map(|dir| IGNORE_FILE_NAMES.iter().map(move |name| dir.join(name)))
+            .chain(extra_ignore_files.iter().cloned())
+            .map(|path| {
+                let mtime = stat_mtime(&path);
+                (path, mtime)
+            })
+            .collect();
+        let Ok(json) = serde_json::to_string(&WalkCache { dirs, ignore_files, files }) else {
+            return;
+        };
+        if let Some(dir) = cache_path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        let temp_path = cache_path.with_extension(format!("{}.tmp", std::process::id()));
+        if fs::write(&temp_path, json).is_ok() && fs::rename(&temp_path, cache_path).is_err() {
+            let _ = fs::remove_file(&temp_path);
+        }
+    }
+}
+
+/// 缓存文件路径：SHA-256(影响遍历结果的设置)，parts 由调用方按固定顺序给出
+pub fn cache_path(cache_dir: &Path, parts: &[String]) -> PathBuf {
+    let mut hasher = Sha256::new();
+    for part in std::iter::once(WALK_CACHE_VERSION).chain(parts.iter().map(String::as_str)) {
+        hasher.update(part.as_bytes());
+        hasher.update([0u8]);
+    }
+    let hash: String = hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect();
+    cache_dir.join(format!("walk-{}.json", hash))
+}
+
+/// 读取缓存的文件列表。缓存不存在、损坏，或任一目录、忽略文件的 mtime 变化时返回 None
+pub fn load(cache_path: &Path) -> Option<Vec<CachedFile>> {
+    let json = fs::read_to_string(cache_path).ok()?;
+    let cache: WalkCache = serde_json::from_str(&json).ok()?;
+    let unchanged = cache.dirs.iter().all(|(dir, mtime)| stat_mtime(dir) == Some(*mtime))
+        && cache.ignore_files.iter().all(|(path, mtime)| stat_mtime(path) == *mtime);
+    unchanged.then_some(cache.files)
+}
+
+fn stat_mtime(path: &Path) -> Option<u128> {
+    fs::metadata(path).ok().map(|metadata| mtime_nanos(&metadata))
+}
+
+fn mtime_nanos(metadata: &fs::Metadata) -> u128 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos())
+        .unwrap_or(0)
+}
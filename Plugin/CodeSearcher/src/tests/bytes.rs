@@ -0,0 +1,78 @@
+mod common;
+
+use common::Fixture;
+use serde_json::{json, Value};
+
+const PNG_HEADER: [u8; 8] = [0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a];
+
+fn matches(output: &Value) -> Vec<(String, u64)> {
+    assert_eq!(output["status"], "success", "{}", output);
+    output["byte_matches"]
+        .as_array()
+        .unwrap_or_else(|| panic!("no byte_matches: {}", output))
+        .iter()
+        .map(|m| (m["file_path"].as_str().unwrap().replace('\\', "/"), m["byte_offset"].as_u64().unwrap()))
+        .collect()
+}
+
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    let mut image = b"junk".to_vec();
+    image.extend_from_slice(&PNG_HEADER);
+    image.extend_from_slice(&[0u8; 40]);
+    fixture.write("assets/logo.png", &image);
+    fixture.write("assets/ok.png", PNG_HEADER);
+    fixture.write("notes.txt", "no header here\n");
+    fixture
+}
+
+#[test]
+fn finds_binary_files_outside_allowed_extensions() {
+    let fixture = fixture();
+    for query in ["89504E47", "89 50 4e 47"] {
+        let output = fixture.search(json!({ "query": query, "mode": "bytes" }));
+        assert_eq!(matches(&output), [("assets/logo.png".to_string(), 4), ("assets/ok.png".to_string(), 0)]);
+        assert!(output["result"].is_null(), "{}", output);
+    }
+}
+
+#[test]
+fn reports_hex_context() {
+    let fixture = fixture();
+    let output = fixture.search(json!({ "query": "0d0a1a0a", "mode": "bytes", "files": ["assets/logo.png"] }));
+    let found = &output["byte_matches"][0];
+    assert_eq!(found["byte_offset"], 8);
+    assert_eq!(found["matched_hex"], "0d 0a 1a 0a");
+    assert_eq!(found["context_start"], 0);
+    // 前 8 字节 + 匹配 4 字节 + 后 16 字节
+    let context = found["context_hex"].as_str().unwrap();
+    assert_eq!(context.split(' ').count(), 28);
+    assert!(context.starts_with("6a 75 6e 6b 89 50 4e 47 0d 0a"), "{}", context);
+}
+
+#[test]
+fn invalid_hex_is_an_error() {
+    let fixture = fixture();
+    for (query, error) in [
+        ("89504", "Invalid hex query: \"89504\" has an odd number of digits"),
+        ("PNG!", "Invalid hex query: \"PNG!\" contains a non-hex digit"),
+        ("   ", "Invalid hex query: empty"),
+    ] {
+        let output = fixture.search(json!({ "query": query, "mode": "bytes" }));
+        assert_eq!(output["status"], "error", "{}", query);
+        assert_eq!(output["error"], error);
+    }
+
+    let output = fixture.search(json!({ "mode": "bytes" }));
+    assert_eq!(output["error"], "mode \"bytes\" requires query");
+    let output = fixture.search(json!({ "query": "89", "mode": "bytes", "use_regex": true }));
+    assert_eq!(output["error"], "mode \"bytes\" cannot be used together with use_regex");
+}
+
+#[test]
+fn size_cap_still_applies() {
+    let fixture = fixture();
+    let output = fixture.search(json!({ "query": "89504E47", "mode": "bytes", "max_file_size": 20 }));
+    assert_eq!(matches(&output), [("assets/ok.png".to_string(), 0)]);
+    assert_eq!(output["files_skipped"], 1, "{}", output);
+}
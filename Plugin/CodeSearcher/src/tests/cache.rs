@@ -0,0 +1,89 @@
+mod common;
+
+use common::Fixture;
+use serde_json::json;
+
+#[test]
+fn cache_dir_outside_project_is_rejected() {
+    let fixture = Fixture::new();
+    fixture.write("a.rs", "fn needle() {}\n");
+    let outside = std::env::temp_dir().join(format!("codesearcher-outside-{}", std::process::id()));
+
+    let rejected = [
+        "../escaped-cache".to_string(),
+        "new/../../escaped-cache".to_string(),
+        outside.to_string_lossy().into_owned(),
+    ];
+    for cache_dir in rejected {
+        let output = fixture.search(json!({ "query": "needle", "cache_dir": cache_dir }));
+        assert_eq!(output["status"], "error", "cache_dir {:?}", cache_dir);
+        assert_eq!(output["error"], "cache_dir escapes project root");
+    }
+    assert!(!common::exists(&fixture.root.join("../escaped-cache")));
+    assert!(!common::exists(&outside));
+}
+
+#[test]
+fn cache_dir_not_yet_created_inside_project_is_accepted() {
+    let fixture = Fixture::new();
+    fixture.write("a.rs", "fn needle() {}\n");
+    let output = fixture.search(json!({ "query": "needle", "cache_dir": ".cache/codesearcher" }));
+    assert_eq!(output["status"], "success", "{}", output);
+    assert_eq!(common::locations(&output), vec![("a.rs".to_string(), 1)]);
+}
+
+#[cfg(unix)]
+#[test]
+fn cache_dir_symlink_to_outside_is_rejected() {
+    let fixture = Fixture::new();
+    let outside = Fixture::new();
+    fixture.write("a.rs", "fn needle() {}\n");
+    std::os::unix::fs::symlink(&outside.root, fixture.path("cache")).unwrap();
+    let output = fixture.search(json!({ "query": "needle", "cache_dir": "cache/results" }));
+    assert_eq!(output["error"], "cache_dir escapes project root");
+}
+
+#[test]
+fn eviction_only_removes_cache_files() {
+    let fixture = Fixture::new();
+    fixture.write("a.rs", "fn needle() {}\n");
+    let stale = format!("cache/codesearcher-cache/{}.json", "0".repeat(64));
+    let stale_walk = format!("cache/codesearcher-cache/walk-{}.json", "f".repeat(64));
+    let unrelated = [
+        "cache/settings.json",
+        "cache/codesearcher-cache/notes.json",
+        "cache/codesearcher-cache/ABCDEF.json",
+    ];
+    for rel in unrelated.iter().copied().chain([stale.as_str(), stale_walk.as_str()]) {
+        fixture.write(rel, "[]");
+    }
+
+    let output = fixture.search_with_env(
+        json!({ "query": "needle", "cache_dir": "cache" }),
+        &[("CODESEARCHER_CACHE_MAX_SIZE_MB", "0")],
+    );
+    assert_eq!(output["status"], "success", "{}", output);
+    assert_eq!(output["cache_stats"]["evicted_entries"], 2);
+    assert!(!common::exists(&fixture.path(&stale)));
+    assert!(!common::exists(&fixture.path(&stale_walk)));
+    for rel in unrelated {
+        assert!(common::exists(&fixture.path(rel)), "{} was evicted", rel);
+    }
+}
+
+#[test]
+fn cache_files_are_written_to_subdirectory() {
+    let fixture = Fixture::new();
+    fixture.write("a.rs", "fn needle() {}\n");
+    let first = fixture.search(json!({ "query": "needle", "cache_dir": "cache" }));
+    let second = fixture.search(json!({ "query": "needle", "cache_dir": "cache" }));
+    assert_eq!(common::locations(&first), common::locations(&second));
+    assert_eq!(second["walk_cache"], "hit");
+
+    let names: Vec<String> = std::fs::read_dir(fixture.path("cache"))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(names, vec!["codesearcher-cache".to_string()]);
+    assert!(std::fs::read_dir(fixture.path("cache/codesearcher-cache")).unwrap().count() >= 2);
+}
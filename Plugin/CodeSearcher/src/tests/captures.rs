@@ -0,0 +1,75 @@
+mod common;
+
+use common::Fixture;
+use serde_json::{json, Value};
+
+fn first(fixture: &Fixture, args: Value) -> Value {
+    let output = fixture.search(args);
+    assert_eq!(output["status"], "success", "{}", output);
+    output["result"][0].clone()
+}
+
+#[test]
+fn groups_and_named_groups() {
+    let fixture = Fixture::new();
+    fixture.write("Cargo.txt", "version = \"1.2.3\"\n");
+
+    let result = first(&fixture, json!({ "query": r#"(version)\s*=\s*"([^"]+)"(,)?"#, "use_regex": true }));
+    // 未参与匹配的组为 null
+    assert_eq!(result["captures"], json!(["version", "1.2.3", null]));
+    assert!(result.get("named_captures").is_none(), "{}", result);
+    assert!(result.get("captures_truncated").is_none(), "{}", result);
+
+    let result = first(&fixture, json!({ "query": r#"version\s*=\s*"(?P<major>\d+)\.(\d+)(?P<pre>-\w+)?"#, "use_regex": true }));
+    assert_eq!(result["captures"], json!(["1", "2", null]));
+    assert_eq!(result["named_captures"], json!({ "major": "1", "pre": null }));
+}
+
+#[test]
+fn captures_come_from_the_first_match_on_the_line() {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "a=1 b=2\n");
+    let result = first(&fixture, json!({ "query": r"(\w)=(\d)", "use_regex": true }));
+    assert_eq!(result["captures"], json!(["a", "1"]));
+}
+
+#[test]
+fn absent_without_regex_groups() {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "value (x)\n");
+    for args in [
+        json!({ "query": "(x)" }),
+        json!({ "query": r"value \(x\)", "use_regex": true }),
+        json!({ "query": r"(?:value)", "use_regex": true }),
+    ] {
+        let result = first(&fixture, args.clone());
+        assert!(result.get("captures").is_none(), "{}: {}", args, result);
+        assert!(result.get("named_captures").is_none(), "{}: {}", args, result);
+    }
+}
+
+#[test]
+fn total_captured_bytes_are_capped() {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", format!("{}:{}:{}\n", "a".repeat(1000), "b".repeat(100), "c".repeat(10)));
+
+    let result = first(&fixture, json!({ "query": r"(?P<first>a+):(b+):(c+)", "use_regex": true }));
+    assert_eq!(result["captures_truncated"], true);
+    let captures = result["captures"].as_array().unwrap();
+    assert_eq!(captures[0].as_str().unwrap().len(), 1000);
+    assert_eq!(captures[1].as_str().unwrap(), "b".repeat(24));
+    // 预算用完后的组仍然参与了匹配，只是文本为空
+    assert_eq!(captures[2], "");
+    assert_eq!(result["named_captures"]["first"].as_str().unwrap().len(), 1000);
+}
+
+#[test]
+fn truncation_respects_char_boundaries() {
+    let fixture = Fixture::new();
+    fixture.write("a.md", format!("{}\n", "字".repeat(400)));
+
+    let result = first(&fixture, json!({ "query": "(字+)", "use_regex": true }));
+    assert_eq!(result["captures_truncated"], true);
+    // 每个字 3 字节，1024 字节内最多 341 个
+    assert_eq!(result["captures"][0].as_str().unwrap().chars().count(), 341);
+}
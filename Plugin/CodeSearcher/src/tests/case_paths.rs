@@ -0,0 +1,55 @@
+// case_sensitive_paths：扩展名白名单和 ALWAYS_INCLUDE_FILENAMES 是否区分大小写
+
+mod common;
+
+use common::{locations, Fixture};
+use serde_json::{json, Value};
+
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .write("lower/a.ts", "needle\n")
+        .write("upper/a.TS", "needle\n")
+        .write("lower/Makefile", "needle\n")
+        .write("upper/MAKEFILE", "needle\n");
+    fixture
+}
+
+fn files(output: &Value) -> Vec<String> {
+    let mut files: Vec<String> = locations(output).into_iter().map(|(file, _)| file).collect();
+    files.sort();
+    files
+}
+
+const SENSITIVE: [&str; 2] = ["lower/Makefile", "lower/a.ts"];
+const INSENSITIVE: [&str; 4] = ["lower/Makefile", "lower/a.ts", "upper/MAKEFILE", "upper/a.TS"];
+
+#[test]
+fn explicit_setting() {
+    let fixture = fixture();
+    let output = fixture.search(json!({ "query": "needle", "case_sensitive_paths": true }));
+    assert_eq!(files(&output), SENSITIVE);
+
+    let output = fixture.search(json!({ "query": "needle", "case_sensitive_paths": false }));
+    assert_eq!(files(&output), INSENSITIVE);
+}
+
+#[test]
+fn default_follows_platform() {
+    let output = fixture().search(json!({ "query": "needle" }));
+    if cfg!(any(target_os = "macos", target_os = "windows")) {
+        assert_eq!(files(&output), INSENSITIVE);
+    } else {
+        assert_eq!(files(&output), SENSITIVE);
+    }
+}
+
+#[test]
+fn file_types_follow_setting() {
+    let fixture = fixture();
+    let output = fixture.search(json!({ "query": "needle", "file_types": ["web"], "case_sensitive_paths": true }));
+    assert_eq!(files(&output), ["lower/a.ts"]);
+
+    let output = fixture.search(json!({ "query": "needle", "file_types": ["web"], "case_sensitive_paths": false }));
+    assert_eq!(files(&output), ["lower/a.ts", "upper/a.TS"]);
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use common::{locations, Fixture};
+use serde_json::json;
+
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .write("a.rs", "// needle in a comment\n    /* needle block */\nlet needle = 1;\n")
+        .write("notes.txt", "# needle heading\n-- needle dash\nneedle text\n");
+    fixture
+}
+
+#[test]
+fn comment_style_is_inferred_from_the_extension() {
+    let fixture = fixture();
+    let output = fixture.search(json!({ "query": "needle", "ignore_comments": true, "context_lines": 0 }));
+    // txt 没有已知的注释符号，不跳过任何行
+    assert_eq!(
+        locations(&output),
+        [
+            ("a.rs".to_string(), 3),
+            ("notes.txt".to_string(), 1),
+            ("notes.txt".to_string(), 2),
+            ("notes.txt".to_string(), 3),
+        ]
+    );
+
+    let output = fixture.search(json!({ "query": "needle", "context_lines": 0 }));
+    assert_eq!(locations(&output).len(), 6);
+}
+
+#[test]
+fn explicit_comment_style_overrides_the_extension() {
+    let fixture = fixture();
+    let output = fixture.search(json!({ "query": "needle", "ignore_comments": "true", "comment_style": "#", "context_lines": 0 }));
+    assert_eq!(
+        locations(&output),
+        [
+            ("a.rs".to_string(), 1),
+            ("a.rs".to_string(), 2),
+            ("a.rs".to_string(), 3),
+            ("notes.txt".to_string(), 2),
+            ("notes.txt".to_string(), 3),
+        ]
+    );
+
+    let output = fixture.search(json!({ "query": "needle", "ignore_comments": "true", "comment_style": "--", "files": ["notes.txt"] }));
+    assert_eq!(locations(&output), [("notes.txt".to_string(), 1), ("notes.txt".to_string(), 3)]);
+}
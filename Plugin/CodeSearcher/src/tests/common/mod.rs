@@ -0,0 +1,152 @@
+// 集成测试共用的夹具：在临时目录中建立小型项目，通过 stdin 向编译好的 CodeSearcher 发送请求并解析输出
+
+#![allow(dead_code)]
+
+use serde_json::Value;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// 会影响搜索结果的环境变量，测试前全部清除，避免受运行环境干扰
+const CONFIG_VARS: &[&str] = &[
+    "MAX_RESULTS",
+    "IGNORED_FOLDERS",
+    "ALLOWED_EXTENSIONS",
+    "IGNORE_TEST_FILES",
+    "PROJECT_BASE_PATH",
+    "CODESEARCHER_CACHE_MAX_SIZE_MB",
+    "CODESEARCHER_SLOW_FILE_THRESHOLD_MS",
+    "FILE_READER_PLUGIN",
+    "PLUGIN_EXTENSIONS",
+    "PLUGIN_TIMEOUT_MS",
+    "MAX_TOTAL_BYTES_SEARCHED",
+    "CODESEARCHER_THREADS",
+    "ALWAYS_INCLUDE_FILENAMES",
+];
+
+/// 临时项目目录，离开作用域时删除
+pub struct Fixture {
+    pub root: PathBuf,
+}
+
+impl Fixture {
+    pub fn new() -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let root = std::env::temp_dir().join(format!(
+            "codesearcher-test-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).expect("create fixture dir");
+        Fixture { root }
+    }
+
+    /// 写入相对于项目根目录的文件，自动创建上级目录
+    pub fn write(&self, rel: &str, contents: impl AsRef<[u8]>) -> &Self {
+        let path = self.root.join(rel);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).expect("create fixture subdir");
+        }
+        fs::write(path, contents).expect("write fixture file");
+        self
+    }
+
+    pub fn path(&self, rel: &str) -> PathBuf {
+        self.root.join(rel)
+    }
+
+    /// 以 args 为请求运行搜索；插件从工作目录推断项目根目录，因此在夹具目录中启动
+    pub fn search(&self, args: Value) -> Value {
+        self.search_with_env(args, &[])
+    }
+
+    pub fn search_with_env(&self, args: Value, env: &[(&str, &str)]) -> Value {
+        let stdout = self.run(args, env);
+        serde_json::from_str(&stdout).unwrap_or_else(|e| panic!("invalid JSON output ({}): {}", e, stdout))
+    }
+
+    /// 运行并返回原始 stdout
+    pub fn run(&self, args: Value, env: &[(&str, &str)]) -> String {
+        run_in(&self.root, &args.to_string(), env)
+    }
+
+    /// 运行并返回原始 stdout 和 stderr
+    pub fn run_with_stderr(&self, args: Value, env: &[(&str, &str)]) -> (String, String) {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_CodeSearcher"));
+        command.current_dir(&self.root);
+        let output = output_of(command, &args.to_string(), env);
+        (
+            String::from_utf8(output.stdout).expect("stdout is UTF-8"),
+            String::from_utf8(output.stderr).expect("stderr is UTF-8"),
+        )
+    }
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+/// 直接把 input 写入 stdin 运行，不补 project_base
+pub fn run_raw(input: &str, env: &[(&str, &str)]) -> String {
+    run_command(Command::new(env!("CARGO_BIN_EXE_CodeSearcher")), input, env)
+}
+
+/// 与 run_raw 相同，但在 cwd 下运行，用于测试按工作目录探测项目根目录
+pub fn run_in(cwd: &Path, input: &str, env: &[(&str, &str)]) -> String {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_CodeSearcher"));
+    command.current_dir(cwd);
+    run_command(command, input, env)
+}
+
+fn run_command(command: Command, input: &str, env: &[(&str, &str)]) -> String {
+    String::from_utf8(output_of(command, input, env).stdout).expect("stdout is UTF-8")
+}
+
+fn output_of(mut command: Command, input: &str, env: &[(&str, &str)]) -> Output {
+    for var in CONFIG_VARS {
+        command.env_remove(var);
+    }
+    command
+        .env("ALLOWED_EXTENSIONS", ".rs,.txt,.md,.js,.ts,.json")
+        .envs(env.iter().copied())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let mut child = command.spawn().expect("spawn CodeSearcher");
+    child
+        .stdin
+        .take()
+        .expect("stdin")
+        .write_all(input.as_bytes())
+        .expect("write request");
+    child.wait_with_output().expect("wait CodeSearcher")
+}
+
+/// 结果列表中每项的 (file_path, line_number)，路径统一为 / 分隔
+pub fn locations(output: &Value) -> Vec<(String, u64)> {
+    output["result"]
+        .as_array()
+        .unwrap_or_else(|| panic!("no result array: {}", output))
+        .iter()
+        .map(|r| (r["file_path"].as_str().unwrap().replace('\\', "/"), r["line_number"].as_u64().unwrap()))
+        .collect()
+}
+
+/// 去掉每次运行都会变化的字段，便于比较两次输出
+pub fn strip_volatile(mut output: Value) -> Value {
+    if let Some(object) = output.as_object_mut() {
+        for key in ["elapsed_ms", "slow_files", "threads"] {
+            object.remove(key);
+        }
+    }
+    output
+}
+
+pub fn exists(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok()
+}
@@ -0,0 +1,111 @@
+// codesearcher.config.json 的优先级：内置默认值 < 配置文件 < 环境变量 < 请求参数
+
+mod common;
+
+use common::{locations, Fixture};
+use serde_json::json;
+
+const CONFIG: &str = "codesearcher.config.json";
+
+/// 五行匹配，每行前后都有足够的上下文
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    let lines: Vec<String> = (0..5).map(|i| format!("a{}\nb{}\nneedle {}\nc{}\nd{}", i, i, i, i, i)).collect();
+    fixture.write("src/a.rs", lines.join("\n") + "\n");
+    fixture
+}
+
+fn context_len(output: &serde_json::Value) -> usize {
+    output["result"][0]["context_before"].as_array().expect("context_before").len()
+}
+
+#[test]
+fn context_lines_precedence() {
+    let fixture = fixture();
+    let output = fixture.search(json!({ "query": "needle" }));
+    assert_eq!(context_len(&output), 2);
+
+    fixture.write(CONFIG, r#"{ "context_lines": 0 }"#);
+    let output = fixture.search(json!({ "query": "needle" }));
+    assert_eq!(context_len(&output), 0);
+
+    let output = fixture.search(json!({ "query": "needle", "context_lines": 1 }));
+    assert_eq!(context_len(&output), 1);
+}
+
+#[test]
+fn max_results_precedence() {
+    let fixture = fixture();
+    let output = fixture.search(json!({ "query": "needle" }));
+    assert_eq!(locations(&output).len(), 5);
+
+    fixture.write(CONFIG, r#"{ "max_results": 2 }"#);
+    let output = fixture.search(json!({ "query": "needle" }));
+    assert_eq!(locations(&output).len(), 2);
+    assert_eq!(output["truncated_reason"], "max_results");
+
+    let output = fixture.search_with_env(json!({ "query": "needle" }), &[("MAX_RESULTS", "4")]);
+    assert_eq!(locations(&output).len(), 4);
+}
+
+#[test]
+fn ignored_folders_precedence() {
+    let fixture = fixture();
+    fixture.write("vendor/b.rs", "needle\n").write("gen/c.rs", "needle\n");
+    let files = |output: &serde_json::Value| {
+        let mut files: Vec<String> = locations(output).into_iter().map(|(file, _)| file).collect();
+        files.sort();
+        files.dedup();
+        files
+    };
+
+    let output = fixture.search(json!({ "query": "needle" }));
+    assert_eq!(files(&output), vec!["gen/c.rs", "src/a.rs", "vendor/b.rs"]);
+
+    fixture.write(CONFIG, r#"{ "ignored_folders": ["vendor"] }"#);
+    let output = fixture.search(json!({ "query": "needle" }));
+    assert_eq!(files(&output), vec!["gen/c.rs", "src/a.rs"]);
+
+    let output = fixture.search_with_env(json!({ "query": "needle" }), &[("IGNORED_FOLDERS", "gen")]);
+    assert_eq!(files(&output), vec!["src/a.rs", "vendor/b.rs"]);
+}
+
+#[test]
+fn allowed_extensions_env_overrides_file() {
+    let fixture = fixture();
+    fixture.write("b.py", "needle\n").write(CONFIG, r#"{ "allowed_extensions": ["py"] }"#);
+
+    // 夹具默认设置了 ALLOWED_EXTENSIONS，配置文件中的列表不生效
+    let output = fixture.search(json!({ "query": "needle" }));
+    assert!(locations(&output).iter().all(|(file, _)| file == "src/a.rs"));
+    assert_eq!(locations(&output).len(), 5);
+
+    let output = fixture.search_with_env(json!({ "query": "needle" }), &[("ALLOWED_EXTENSIONS", "py")]);
+    assert_eq!(locations(&output), vec![("b.py".to_string(), 1)]);
+}
+
+#[test]
+fn max_file_size_precedence() {
+    let fixture = fixture();
+    fixture.write(CONFIG, r#"{ "max_file_size": 10 }"#);
+    let output = fixture.search(json!({ "query": "needle" }));
+    assert!(locations(&output).is_empty());
+
+    let output = fixture.search(json!({ "query": "needle", "max_file_size": 1000000 }));
+    assert_eq!(locations(&output).len(), 5);
+}
+
+#[test]
+fn malformed_config_is_an_error() {
+    let fixture = fixture();
+    fixture.write(CONFIG, "{ \"max_results\": 2,");
+    let output = fixture.search(json!({ "query": "needle" }));
+    assert_eq!(output["status"], "error");
+    assert!(output["error"].as_str().unwrap().starts_with("Invalid config file"), "{}", output);
+
+    // 拼错的字段名同样报错，而不是静默使用默认值
+    fixture.write(CONFIG, r#"{ "max_result": 2 }"#);
+    let output = fixture.search(json!({ "query": "needle" }));
+    assert_eq!(output["status"], "error");
+    assert!(output["error"].as_str().unwrap().contains("max_result"), "{}", output);
+}
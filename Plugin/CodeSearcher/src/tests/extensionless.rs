@@ -0,0 +1,63 @@
+// 没有扩展名的构建和部署文件：按 ALWAYS_INCLUDE_FILENAMES 中的完整文件名放行，不受扩展名白名单限制
+
+mod common;
+
+use common::{locations, Fixture};
+use serde_json::{json, Value};
+
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .write("Makefile", "needle:\n")
+        .write("docker/Dockerfile", "RUN needle\n")
+        .write("Justfile", "needle:\n")
+        .write("Procfile", "web: needle\n")
+        .write("Jenkinsfile", "needle()\n")
+        .write("LICENSE", "needle\n")
+        .write("Makefile.am", "needle\n")
+        .write("src/a.rs", "needle\n");
+    fixture
+}
+
+fn files(output: &Value) -> Vec<String> {
+    let mut files: Vec<String> = locations(output).into_iter().map(|(file, _)| file).collect();
+    files.sort();
+    files
+}
+
+#[test]
+fn default_filenames_are_included() {
+    let output = fixture().search(json!({ "query": "needle" }));
+    assert_eq!(files(&output), vec!["Justfile", "Makefile", "Procfile", "docker/Dockerfile", "src/a.rs"]);
+}
+
+#[test]
+fn env_replaces_default_filenames() {
+    let output = fixture().search_with_env(json!({ "query": "needle" }), &[("ALWAYS_INCLUDE_FILENAMES", "Jenkinsfile, LICENSE")]);
+    assert_eq!(files(&output), vec!["Jenkinsfile", "LICENSE", "src/a.rs"]);
+
+    let output = fixture().search_with_env(json!({ "query": "needle" }), &[("ALWAYS_INCLUDE_FILENAMES", "")]);
+    assert_eq!(files(&output), vec!["src/a.rs"]);
+}
+
+#[test]
+fn case_variants() {
+    let fixture = Fixture::new();
+    fixture
+        .write("a/makefile", "needle\n")
+        .write("b/MAKEFILE", "needle\n")
+        .write("c/DockerFile", "needle\n")
+        .write("d/Makefile", "needle\n");
+
+    let output = fixture.search(json!({ "query": "needle", "case_sensitive_paths": true }));
+    assert_eq!(files(&output), vec!["d/Makefile"]);
+
+    let output = fixture.search(json!({ "query": "needle", "case_sensitive_paths": false }));
+    assert_eq!(files(&output), vec!["a/makefile", "b/MAKEFILE", "c/DockerFile", "d/Makefile"]);
+}
+
+#[test]
+fn file_types_ignore_always_included_names() {
+    let output = fixture().search(json!({ "query": "needle", "file_types": ["rust"] }));
+    assert_eq!(files(&output), vec!["src/a.rs"]);
+}
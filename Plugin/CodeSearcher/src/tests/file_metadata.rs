@@ -0,0 +1,76 @@
+mod common;
+
+use common::Fixture;
+use serde_json::{json, Value};
+use std::fs::File;
+use std::time::{Duration, SystemTime};
+
+/// a.rs 两处匹配，修改时间固定为 2020-01-02T03:04:05Z
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    fixture.write("a.rs", "fn needle() {}\nlet needle = 1;\n");
+    fixture.write("b.md", "needle\n");
+    fixture.write("c.txt", "needle\n");
+    let mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(1_577_934_245);
+    File::options().write(true).open(fixture.path("a.rs")).unwrap().set_modified(mtime).unwrap();
+    fixture
+}
+
+fn result_for<'a>(output: &'a Value, file: &str) -> Vec<&'a Value> {
+    assert_eq!(output["status"], "success", "{}", output);
+    output["result"].as_array().unwrap().iter().filter(|r| r["file_path"] == file).collect()
+}
+
+#[test]
+fn metadata_is_absent_by_default() {
+    let fixture = fixture();
+    let output = fixture.search(json!({ "query": "needle" }));
+    for result in output["result"].as_array().unwrap() {
+        for key in ["file_size", "modified_at", "language"] {
+            assert!(result.get(key).is_none(), "{}: {}", key, result);
+        }
+    }
+}
+
+#[test]
+fn every_result_carries_its_file_metadata() {
+    let fixture = fixture();
+    for args in [
+        json!({ "query": "needle", "file_metadata": true }),
+        json!({ "query": "needle", "file_metadata": "true", "threads": 4 }),
+    ] {
+        let output = fixture.search(args);
+        let results = result_for(&output, "a.rs");
+        assert_eq!(results.len(), 2);
+        for result in results {
+            assert_eq!(result["file_size"], 31);
+            assert_eq!(result["modified_at"], "2020-01-02T03:04:05Z");
+            assert_eq!(result["language"], "rust");
+        }
+        assert_eq!(result_for(&output, "b.md")[0]["language"], "markdown");
+        assert_eq!(result_for(&output, "c.txt")[0]["language"], "text");
+    }
+}
+
+#[test]
+fn metadata_is_added_to_cached_results() {
+    let fixture = fixture();
+    let args = json!({ "query": "needle", "file_metadata": true, "cache_dir": "cache" });
+    let first = fixture.search(args.clone());
+    let second = fixture.search(args);
+    assert_eq!(first["result"], second["result"]);
+    assert_eq!(result_for(&second, "a.rs")[0]["modified_at"], "2020-01-02T03:04:05Z");
+
+    // 缓存里保存的是不带元数据的结果，不请求时也不会带出来
+    let output = fixture.search(json!({ "query": "needle", "cache_dir": "cache" }));
+    assert!(result_for(&output, "a.rs")[0].get("file_size").is_none(), "{}", output);
+}
+
+#[test]
+fn byte_matches_carry_size_and_mtime() {
+    let fixture = fixture();
+    let output = fixture.search(json!({ "query": "6e6565646c65", "mode": "bytes", "files": ["a.rs"], "file_metadata": true }));
+    assert_eq!(output["status"], "success", "{}", output);
+    assert_eq!(output["byte_matches"][0]["file_size"], 31, "{}", output);
+    assert_eq!(output["byte_matches"][0]["modified_at"], "2020-01-02T03:04:05Z");
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use common::{locations, Fixture};
+use serde_json::{json, Value};
+
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .write("a.rs", "needle\n")
+        .write("b.ts", "needle\n")
+        .write("c.md", "needle\n")
+        .write("d.py", "needle\n");
+    fixture
+}
+
+fn error_message(output: &Value) -> &str {
+    assert_eq!(output["status"], "error", "{}", output);
+    output["error"].as_str().unwrap()
+}
+
+#[test]
+fn file_types_intersect_with_allowed_extensions() {
+    let fixture = fixture();
+    let output = fixture.search(json!({ "query": "needle", "file_types": ["rust", "web"] }));
+    let files: Vec<String> = locations(&output).into_iter().map(|(path, _)| path).collect();
+    assert_eq!(files, ["a.rs", "b.ts"]);
+
+    let output = fixture.search(json!({
+        "query": "needle", "file_types": ["docs"], "type_definitions": { "docs": [".md", "rst"] },
+    }));
+    let files: Vec<String> = locations(&output).into_iter().map(|(path, _)| path).collect();
+    assert_eq!(files, ["c.md"]);
+}
+
+#[test]
+fn unknown_file_type_lists_the_available_types() {
+    let output = fixture().search(json!({ "query": "needle", "file_types": ["rust", "cobol"] }));
+    let error = error_message(&output);
+    assert!(error.starts_with("Unknown file type: cobol"), "{}", error);
+    for name in ["rust", "web", "config", "python", "markdown", "text"] {
+        assert!(error.contains(name), "{} missing from {}", name, error);
+    }
+}
+
+#[test]
+fn file_types_outside_allowed_extensions_are_an_error() {
+    // py 不在 ALLOWED_EXTENSIONS 中，交集为空时不能退化为搜索全部文件
+    let output = fixture().search(json!({ "query": "needle", "file_types": ["python"] }));
+    let error = error_message(&output);
+    assert!(error.contains("python") && error.contains("ALLOWED_EXTENSIONS"), "{}", error);
+
+    let output = fixture().search(json!({
+        "query": "needle", "file_types": ["cobol"], "type_definitions": { "cobol": ["cbl"] },
+    }));
+    assert!(error_message(&output).contains("ALLOWED_EXTENSIONS"), "{}", output);
+}
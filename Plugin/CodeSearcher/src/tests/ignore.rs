@@ -0,0 +1,154 @@
+mod common;
+
+use common::{locations, Fixture};
+use serde_json::json;
+
+fn matched_files(output: &serde_json::Value) -> Vec<String> {
+    let mut files: Vec<String> = locations(output).into_iter().map(|(file, _)| file).collect();
+    files.sort();
+    files.dedup();
+    files
+}
+
+#[test]
+fn ignored_folders_apply_without_gitignore() {
+    let fixture = Fixture::new();
+    fixture
+        .write("src/a.js", "const needle = 1;\n")
+        .write("node_modules/pkg/index.js", "const needle = 2;\n")
+        .write("lib/node_modules/pkg/index.js", "const needle = 3;\n")
+        .write("vendor/b.js", "const needle = 4;\n");
+
+    let output = fixture.search_with_env(json!({ "query": "needle" }), &[("IGNORED_FOLDERS", "node_modules")]);
+    assert_eq!(matched_files(&output), vec!["src/a.js", "vendor/b.js"]);
+
+    let output = fixture.search_with_env(json!({ "query": "needle" }), &[("IGNORED_FOLDERS", "node_modules,vendor")]);
+    assert_eq!(matched_files(&output), vec!["src/a.js"]);
+}
+
+#[test]
+fn dot_ignore_and_rgignore_are_respected() {
+    let fixture = Fixture::new();
+    fixture
+        .write("keep.rs", "needle\n")
+        .write("generated.rs", "needle\n")
+        .write("sub/skip.rs", "needle\n")
+        .write(".ignore", "generated.rs\n")
+        .write("sub/.rgignore", "skip.rs\n");
+
+    let output = fixture.search(json!({ "query": "needle" }));
+    assert_eq!(matched_files(&output), vec!["keep.rs"]);
+}
+
+#[test]
+fn custom_ignore_file_is_applied() {
+    let fixture = Fixture::new();
+    fixture
+        .write("keep.rs", "needle\n")
+        .write("drop.rs", "needle\n")
+        .write("config/search.ignore", "drop.rs\n");
+
+    let output = fixture.search(json!({ "query": "needle", "ignore_file": "config/search.ignore" }));
+    assert_eq!(matched_files(&output), vec!["keep.rs"]);
+}
+
+#[test]
+fn ignore_file_outside_project_is_rejected() {
+    let fixture = Fixture::new();
+    let outside = Fixture::new();
+    fixture.write("a.rs", "needle\n");
+    outside.write("search.ignore", "a.rs\n");
+
+    let absolute = outside.path("search.ignore").to_string_lossy().into_owned();
+    let relative = format!("../{}/search.ignore", outside.root.file_name().unwrap().to_string_lossy());
+    for ignore_file in [absolute, relative] {
+        let output = fixture.search(json!({ "query": "needle", "ignore_file": ignore_file }));
+        assert_eq!(output["status"], "error");
+        assert_eq!(output["error"], "ignore_file escapes project root");
+    }
+
+    let output = fixture.search(json!({ "query": "needle", "ignore_file": "missing.ignore" }));
+    assert_eq!(output["status"], "error");
+    assert!(output["error"].as_str().unwrap().starts_with("Invalid ignore_file missing.ignore"));
+}
+
+/// 带 .gitignore 的 git 仓库夹具 (.gitignore 只在 git 仓库中生效)
+fn gitignore_fixture() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .write(".git/HEAD", "ref: refs/heads/main\n")
+        .write(".gitignore", "generated.rs\nout/\n")
+        .write("keep.rs", "needle\n")
+        .write("generated.rs", "needle\n")
+        .write("out/bundle.js", "needle\n")
+        .write(".hidden.rs", "needle\n")
+        .write(".config/settings.json", "{\"needle\": 1}\n");
+    fixture
+}
+
+#[test]
+fn respect_gitignore_toggle() {
+    let fixture = gitignore_fixture();
+    let output = fixture.search(json!({ "query": "needle" }));
+    assert_eq!(matched_files(&output), vec![".config/settings.json", ".hidden.rs", "keep.rs"]);
+
+    let output = fixture.search(json!({ "query": "needle", "respect_gitignore": "false" }));
+    assert_eq!(
+        matched_files(&output),
+        vec![".config/settings.json", ".hidden.rs", "generated.rs", "keep.rs", "out/bundle.js"]
+    );
+}
+
+#[test]
+fn include_hidden_toggle() {
+    let fixture = gitignore_fixture();
+    let output = fixture.search(json!({ "query": "needle", "include_hidden": "false" }));
+    assert_eq!(matched_files(&output), vec!["keep.rs"]);
+
+    let output = fixture.search(json!({ "query": "needle", "include_hidden": "false", "respect_gitignore": "false" }));
+    assert_eq!(matched_files(&output), vec!["generated.rs", "keep.rs", "out/bundle.js"]);
+}
+
+#[test]
+fn exclude_file_patterns_skip_matching_files() {
+    let fixture = Fixture::new();
+    fixture
+        .write("src/api.ts", "const needle = 1;\n")
+        .write("src/api.gen.ts", "const needle = 2;\n")
+        .write("src/deep/types.gen.ts", "const needle = 3;\n");
+
+    let output = fixture.search(json!({ "query": "needle", "exclude_file_patterns": ["**/*.gen.ts"] }));
+    assert_eq!(matched_files(&output), vec!["src/api.ts"]);
+
+    // 只作用于目录遍历，files 中显式列出的文件照常搜索
+    let output = fixture.search(json!({
+        "query": "needle", "exclude_file_patterns": ["**/*.gen.ts"], "files": ["src/api.gen.ts"],
+    }));
+    assert_eq!(matched_files(&output), vec!["src/api.gen.ts"]);
+}
+
+#[test]
+fn invalid_exclude_file_pattern_is_an_error() {
+    let fixture = Fixture::new();
+    fixture.write("a.rs", "needle\n");
+    let output = fixture.search(json!({ "query": "needle", "exclude_file_patterns": ["a["] }));
+    assert_eq!(output["status"], "error", "{}", output);
+    assert!(output["error"].as_str().unwrap().contains("a["), "{}", output);
+}
+
+#[test]
+fn searchignore_applies_from_parent_directories_without_git() {
+    let fixture = Fixture::new();
+    fixture
+        .write("src/keep.rs", "needle\n")
+        .write("src/generated/out.rs", "needle\n")
+        .write("src/snapshot.rs", "needle\n")
+        .write("src/snapshot_keep.rs", "needle\n")
+        .write(".searchignore", "generated/\nsnapshot*.rs\n!snapshot_keep.rs\n");
+
+    let output = fixture.search(json!({ "query": "needle" }));
+    assert_eq!(matched_files(&output), vec!["src/keep.rs", "src/snapshot_keep.rs"]);
+
+    let output = fixture.search(json!({ "query": "needle", "search_path": "src" }));
+    assert_eq!(matched_files(&output), vec!["src/keep.rs", "src/snapshot_keep.rs"]);
+}
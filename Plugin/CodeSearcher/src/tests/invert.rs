@@ -0,0 +1,82 @@
+mod common;
+
+use common::{locations, Fixture};
+use serde_json::{json, Value};
+
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    fixture.write("conf/a.txt", "timeout = 5\nretries = 3\nTIMEOUT = 10\ntimeouts = 2\nname = x\n");
+    fixture
+}
+
+fn lines(output: &Value) -> Vec<u64> {
+    assert_eq!(output["status"], "success", "{}", output);
+    locations(output).into_iter().map(|(_, line)| line).collect()
+}
+
+#[test]
+fn requires_search_path_or_files() {
+    let fixture = fixture();
+    let output = fixture.search(json!({ "query": "timeout", "invert_match": "true" }));
+    assert_eq!(output["status"], "error");
+    assert_eq!(output["error"], "invert_match requires search_path or files");
+
+    let output = fixture.search(json!({ "query": "timeout", "invert_match": "true", "files": ["conf/a.txt"], "context_lines": "0" }));
+    assert_eq!(lines(&output), vec![2, 5]);
+}
+
+#[test]
+fn reports_lines_without_the_pattern() {
+    let fixture = fixture();
+    let output = fixture.search(json!({ "query": "timeout", "invert_match": "true", "search_path": "conf", "context_lines": "0" }));
+    assert_eq!(lines(&output), vec![2, 5]);
+    for result in output["result"].as_array().unwrap() {
+        assert!(result.get("match_column").is_none(), "{}", result);
+    }
+}
+
+#[test]
+fn combines_with_case_sensitive() {
+    let fixture = fixture();
+    let output = fixture.search(json!({
+        "query": "timeout", "invert_match": "true", "case_sensitive": "true", "search_path": "conf", "context_lines": "0",
+    }));
+    assert_eq!(lines(&output), vec![2, 3, 5]);
+}
+
+#[test]
+fn combines_with_whole_word() {
+    let fixture = fixture();
+    // timeouts 不是完整的 timeout 单词
+    let output = fixture.search(json!({
+        "query": "timeout", "invert_match": "true", "whole_word": "true", "search_path": "conf", "context_lines": "0",
+    }));
+    assert_eq!(lines(&output), vec![2, 4, 5]);
+
+    let output = fixture.search(json!({
+        "query": "timeout", "invert_match": "true", "whole_word": "true", "case_sensitive": "true",
+        "search_path": "conf", "context_lines": "0",
+    }));
+    assert_eq!(lines(&output), vec![2, 3, 4, 5]);
+}
+
+#[test]
+fn context_and_caps_still_apply() {
+    let fixture = fixture();
+    let output = fixture.search(json!({ "query": "timeout", "invert_match": "true", "search_path": "conf", "context_lines": "1" }));
+    let second = &output["result"][0];
+    assert_eq!(second["context_before"], json!(["timeout = 5"]));
+    assert_eq!(second["context_after"], json!(["TIMEOUT = 10"]));
+
+    let output = fixture.search(json!({
+        "query": "timeout", "invert_match": "true", "search_path": "conf", "max_matches_per_file": "1", "context_lines": "0",
+    }));
+    assert_eq!(lines(&output), vec![2]);
+
+    let output = fixture.search_with_env(
+        json!({ "query": "zzz", "invert_match": "true", "search_path": "conf" }),
+        &[("MAX_RESULTS", "2")],
+    );
+    assert_eq!(lines(&output), vec![1, 2]);
+    assert_eq!(output["truncated"], true, "{}", output);
+}
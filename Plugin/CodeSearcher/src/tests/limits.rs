@@ -0,0 +1,179 @@
+mod common;
+
+use common::{locations, Fixture};
+use serde_json::{json, Value};
+
+fn files(output: &Value) -> Vec<String> {
+    assert_eq!(output["status"], "success", "{}", output);
+    let mut files: Vec<String> = locations(output).into_iter().map(|(path, _)| path).collect();
+    files.dedup();
+    files
+}
+
+/// small.rs 几十字节，big.rs 2000 字节，huge.rs 超过默认的 1MB
+fn size_fixture() -> Fixture {
+    let fixture = Fixture::new();
+    fixture.write("small.rs", "fn needle() {}\n");
+    fixture.write("big.rs", format!("fn needle() {{}}\n{}", "// padding\n".repeat(180)));
+    fixture.write("huge.rs", format!("fn needle() {{}}\n{}", "// padding\n".repeat(150_000)));
+    fixture
+}
+
+#[test]
+fn default_max_file_size_is_one_megabyte() {
+    let fixture = size_fixture();
+    let output = fixture.search(json!({ "query": "needle" }));
+    assert_eq!(files(&output), ["big.rs", "small.rs"]);
+    assert_eq!(output["files_skipped"], 1, "{}", output);
+}
+
+#[test]
+fn max_file_size_skips_larger_files_and_counts_them() {
+    let fixture = size_fixture();
+    let output = fixture.search(json!({ "query": "needle", "max_file_size": "1000" }));
+    assert_eq!(files(&output), ["small.rs"]);
+    assert_eq!(output["files_skipped"], 2, "{}", output);
+    assert_eq!(output["files_scanned"], 1, "{}", output);
+}
+
+#[test]
+fn max_file_size_zero_means_no_limit() {
+    let fixture = size_fixture();
+    let output = fixture.search(json!({ "query": "needle", "max_file_size": "0" }));
+    assert_eq!(files(&output), ["big.rs", "huge.rs", "small.rs"]);
+    assert_eq!(output["files_skipped"], 0, "{}", output);
+}
+
+fn depth_fixture() -> Fixture {
+    let fixture = Fixture::new();
+    fixture.write("root.rs", "fn needle() {}\n");
+    fixture.write("a/one.rs", "fn needle() {}\n");
+    fixture.write("a/b/two.rs", "fn needle() {}\n");
+    fixture
+}
+
+#[test]
+fn max_depth_zero_searches_only_the_root_directory() {
+    let fixture = depth_fixture();
+    let output = fixture.search(json!({ "query": "needle", "max_depth": "0" }));
+    assert_eq!(files(&output), ["root.rs"]);
+}
+
+#[test]
+fn max_depth_limits_subdirectories() {
+    let fixture = depth_fixture();
+    let output = fixture.search(json!({ "query": "needle", "max_depth": "1" }));
+    assert_eq!(files(&output), ["a/one.rs", "root.rs"]);
+
+    let output = fixture.search(json!({ "query": "needle" }));
+    assert_eq!(files(&output), ["a/b/two.rs", "a/one.rs", "root.rs"]);
+}
+
+#[test]
+fn max_depth_is_relative_to_search_path() {
+    let fixture = depth_fixture();
+    let output = fixture.search(json!({ "query": "needle", "search_path": "a", "max_depth": "0" }));
+    assert_eq!(files(&output), ["a/one.rs"]);
+}
+
+#[test]
+fn timeout_returns_partial_results_with_the_reason() {
+    let fixture = Fixture::new();
+    let contents = "fn needle() {}\n".repeat(200);
+    for i in 0..2000 {
+        fixture.write(&format!("d{}/f{:04}.rs", i % 20, i), &contents);
+    }
+
+    let output = fixture.search(json!({ "query": "needle", "timeout_ms": "1" }));
+    assert_eq!(output["status"], "success", "{}", output);
+    assert_eq!(output["timed_out"], true, "{}", output);
+    assert_eq!(output["truncated"], true, "{}", output);
+    assert_eq!(output["truncated_reason"], "timeout", "{}", output);
+    assert!(output["files_scanned"].as_u64().unwrap() < 2000, "{}", output);
+
+    let output = fixture.search(json!({ "query": "needle", "timeout_ms": "0", "summary_only": true }));
+    assert!(output.get("timed_out").is_none(), "{}", output);
+    assert_eq!(output["files_scanned"], 2000, "{}", output);
+}
+
+/// 5 个文件各 50 行匹配，序列化后远超过 2000 字节
+fn output_fixture() -> Fixture {
+    let fixture = Fixture::new();
+    let contents: String = (1..=50).map(|n| format!("needle {}\n", n)).collect();
+    for i in 0..5 {
+        fixture.write(&format!("f{}.rs", i), &contents);
+    }
+    fixture
+}
+
+#[test]
+fn max_output_bytes_drops_whole_results() {
+    let fixture = output_fixture();
+    let env = [("MAX_RESULTS", "1000")];
+    let output = fixture.search_with_env(json!({ "query": "needle", "context_lines": 0, "max_output_bytes": "2000" }), &env);
+    assert_eq!(output["status"], "success", "{}", output);
+    assert_eq!(output["truncated"], true, "{}", output);
+    assert_eq!(output["truncated_reason"], "max_output_bytes", "{}", output);
+    assert_eq!(output["total_matches"], 250, "{}", output);
+    let kept = output["result"].as_array().unwrap();
+    assert!(!kept.is_empty() && kept.len() < 250, "{}", output);
+    assert!(output["result"].to_string().len() <= 2000, "{}", output);
+
+    let output = fixture.search_with_env(json!({ "query": "needle", "context_lines": 0, "max_output_bytes": "0" }), &env);
+    assert_eq!(locations(&output).len(), 250);
+    assert!(output.get("truncated_reason").is_none(), "{}", output);
+}
+
+#[test]
+fn max_output_bytes_applies_to_streamed_lines() {
+    let fixture = output_fixture();
+    let stdout = fixture.run(json!({ "query": "needle", "context_lines": 0, "max_output_bytes": 2000, "stream": true }), &[]);
+    let (matches, summary) = stdout.trim_end().rsplit_once('\n').expect("match lines and a summary");
+    let summary: Value = serde_json::from_str(summary).unwrap();
+    assert_eq!(summary["truncated"], true, "{}", summary);
+    assert_eq!(summary["truncated_reason"], "max_output_bytes", "{}", summary);
+    assert!(matches.len() < 2000, "{} bytes of matches", matches.len());
+    assert!(matches.lines().count() < 250);
+}
+
+#[test]
+fn max_total_bytes_searched_stops_starting_new_files() {
+    let fixture = output_fixture();
+    let output = fixture.search(json!({ "query": "needle", "threads": "1", "max_total_bytes_searched": "100" }));
+    assert_eq!(output["status"], "success", "{}", output);
+    assert_eq!(output["truncated"], true, "{}", output);
+    assert_eq!(output["truncated_reason"], "max_bytes", "{}", output);
+    // 正在搜索的文件会完整搜索完
+    assert_eq!(output["files_scanned"], 1, "{}", output);
+    assert_eq!(output["total_matches"], 50, "{}", output);
+
+    let output = fixture.search_with_env(json!({ "query": "needle", "threads": "1" }), &[("MAX_TOTAL_BYTES_SEARCHED", "100")]);
+    assert_eq!(output["truncated_reason"], "max_bytes", "{}", output);
+}
+
+#[test]
+fn max_scan_line_length_skips_and_counts_long_lines() {
+    let fixture = Fixture::new();
+    fixture.write("a.rs", format!("fn needle() {{}}\n{}needle\n", "x".repeat(300)));
+
+    let output = fixture.search(json!({ "query": "needle", "max_scan_line_length": "100" }));
+    assert_eq!(files(&output), ["a.rs"]);
+    assert_eq!(output["total_matches"], 1, "{}", output);
+    assert_eq!(output["long_lines_skipped"], 1, "{}", output);
+
+    let output = fixture.search(json!({ "query": "needle", "max_scan_line_length": "0" }));
+    assert_eq!(output["total_matches"], 2, "{}", output);
+    assert!(output.get("long_lines_skipped").is_none(), "{}", output);
+}
+
+#[test]
+fn patterns_longer_than_the_limit_are_rejected() {
+    let fixture = Fixture::new();
+    fixture.write("a.rs", "needle\n");
+    let output = fixture.search(json!({ "query": "a".repeat(2001) }));
+    assert_eq!(output["status"], "error", "{}", output);
+    assert!(output["error"].as_str().unwrap().contains("2001"), "{}", output);
+
+    let output = fixture.search(json!({ "query": "a".repeat(2000) }));
+    assert_eq!(output["status"], "success", "{}", output);
+}
@@ -0,0 +1,98 @@
+// 带 BOM 或 CRLF 的文件：行内容和上下文都不含 \u{feff} 和 \r，每个结果标明所在文件的换行符
+
+mod common;
+
+use common::Fixture;
+use serde_json::{json, Value};
+
+fn results(output: &Value) -> &Vec<Value> {
+    output["result"].as_array().unwrap_or_else(|| panic!("no result array: {}", output))
+}
+
+#[test]
+fn crlf_context_has_no_carriage_returns() {
+    let fixture = Fixture::new();
+    // 最后一行只有孤立的 \r，没有 \n
+    fixture.write("a.rs", "fn a() {\r\n    one();  \r\n    needle();\r\n    two();\r\n}\r\nneedle\r");
+
+    for preserve_whitespace in ["true", "false"] {
+        for merge_context in ["false", "true"] {
+            let args = json!({
+                "query": "needle", "preserve_whitespace": preserve_whitespace, "merge_context": merge_context,
+            });
+            let stdout = fixture.run(args.clone(), &[]);
+            assert!(!stdout.contains("\\r"), "{}: {}", args, stdout);
+            let output: Value = serde_json::from_str(&stdout).unwrap();
+            assert_eq!(output["status"], "success", "{}", output);
+        }
+    }
+
+    let output = fixture.search(json!({ "query": "needle", "context_lines": "3" }));
+    let results = results(&output);
+    assert_eq!(results[0]["context_before"], json!(["fn a() {", "    one();  "]));
+    assert_eq!(results[0]["context_after"], json!(["    two();", "}", "needle"]));
+    assert_eq!(results[1]["line_content"], "needle");
+}
+
+#[test]
+fn bom_and_crlf() {
+    let fixture = Fixture::new();
+    fixture.write("a.rs", "\u{feff}needle first\r\n  before\r\nneedle second  \r\nafter\r\n");
+
+    let output = fixture.search(json!({ "query": "needle", "preserve_whitespace": true }));
+    let results = results(&output);
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0]["line_number"], 1);
+    assert_eq!(results[0]["line_content"], "needle first");
+    assert_eq!(results[0]["match_column"], 0);
+    assert_eq!(results[0]["line_ending"], "crlf");
+    assert_eq!(results[1]["line_content"], "needle second  ");
+    assert_eq!(results[1]["context_before"], json!(["needle first", "  before"]));
+    assert_eq!(results[1]["context_after"], json!(["after"]));
+    assert_eq!(results[1]["line_ending"], "crlf");
+}
+
+#[test]
+fn bom_is_counted_in_byte_offsets() {
+    let fixture = Fixture::new();
+    fixture.write("a.rs", "\u{feff}needle\r\n");
+
+    // mode=bytes 按原始字节报告偏移，BOM 占 3 个字节
+    let output = fixture.search(json!({ "query": "6e 65 65 64 6c 65", "mode": "bytes" }));
+    let matches = output["byte_matches"].as_array().expect("byte_matches");
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0]["byte_offset"], 3);
+}
+
+#[test]
+fn crlf_only() {
+    let fixture = Fixture::new();
+    fixture.write("a.rs", "one\r\nneedle\r\nthree\r\n");
+
+    let output = fixture.search(json!({ "query": "needle", "preserve_whitespace": true }));
+    let results = results(&output);
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["line_content"], "needle");
+    assert_eq!(results[0]["context_before"], json!(["one"]));
+    assert_eq!(results[0]["context_after"], json!(["three"]));
+    assert_eq!(results[0]["line_ending"], "crlf");
+}
+
+#[test]
+fn mixed_endings() {
+    let fixture = Fixture::new();
+    fixture
+        .write("mixed.rs", "one\r\nneedle\nthree\r\nfour\n")
+        .write("lf.rs", "needle\nafter\n");
+
+    let output = fixture.search(json!({ "query": "needle", "preserve_whitespace": true, "sort": "path" }));
+    let results = results(&output);
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0]["file_path"], "lf.rs");
+    assert_eq!(results[0]["line_ending"], "lf");
+    assert_eq!(results[1]["file_path"], "mixed.rs");
+    assert_eq!(results[1]["line_content"], "needle");
+    assert_eq!(results[1]["context_before"], json!(["one"]));
+    assert_eq!(results[1]["context_after"], json!(["three", "four"]));
+    assert_eq!(results[1]["line_ending"], "mixed");
+}
@@ -0,0 +1,112 @@
+mod common;
+
+use common::{locations, Fixture};
+use serde_json::{json, Value};
+
+/// needle 在第 2、5、8 行，共 10 行
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    let lines: Vec<String> = (1..=10)
+        .map(|i| if [2, 5, 8].contains(&i) { format!("needle {}", i) } else { format!("line {}", i) })
+        .collect();
+    fixture.write("a.txt", lines.join("\n") + "\n");
+    fixture.write("b.txt", "needle\n");
+    fixture
+}
+
+fn search(fixture: &Fixture, line_start: Value, line_end: Value) -> Value {
+    let mut args = json!({ "query": "needle", "files": ["a.txt"], "context_lines": 0 });
+    if !line_start.is_null() {
+        args["line_start"] = line_start;
+    }
+    if !line_end.is_null() {
+        args["line_end"] = line_end;
+    }
+    let output = fixture.search(args);
+    assert_eq!(output["status"], "success", "{}", output);
+    output
+}
+
+fn lines(output: &Value) -> Vec<u64> {
+    locations(output).into_iter().map(|(_, line)| line).collect()
+}
+
+#[test]
+fn restricts_matches_to_the_window() {
+    let fixture = fixture();
+    let output = search(&fixture, json!(4), json!("6"));
+    assert_eq!(lines(&output), vec![5]);
+    assert_eq!(output["line_window"], json!({ "line_start": 4, "line_end": 6 }));
+    assert_eq!(output["total_matches"], 1, "{}", output);
+
+    let output = search(&fixture, json!(5), json!(8));
+    assert_eq!(lines(&output), vec![5, 8]);
+}
+
+#[test]
+fn open_ended_windows() {
+    let fixture = fixture();
+    let output = search(&fixture, json!(6), Value::Null);
+    assert_eq!(lines(&output), vec![8]);
+    assert_eq!(output["line_window"], json!({ "line_start": 6, "line_end": 10 }));
+
+    let output = search(&fixture, Value::Null, json!(4));
+    assert_eq!(lines(&output), vec![2]);
+    assert_eq!(output["line_window"], json!({ "line_start": 1, "line_end": 4 }));
+}
+
+#[test]
+fn out_of_range_values_are_clamped() {
+    let fixture = fixture();
+    let output = search(&fixture, json!(0), json!(999));
+    assert_eq!(lines(&output), vec![2, 5, 8]);
+    assert_eq!(output["line_window"], json!({ "line_start": 1, "line_end": 10 }));
+
+    let output = search(&fixture, json!(50), json!(60));
+    assert!(lines(&output).is_empty());
+    assert_eq!(output["line_window"], json!({ "line_start": 10, "line_end": 10 }));
+}
+
+#[test]
+fn context_may_come_from_outside_the_window() {
+    let fixture = fixture();
+    let output = fixture.search(json!({
+        "query": "needle", "files": ["a.txt"], "line_start": 5, "line_end": 5, "context_lines": 2,
+    }));
+    let result = &output["result"][0];
+    assert_eq!(result["line_number"], 5);
+    assert_eq!(result["context_before"], json!(["line 3", "line 4"]));
+    assert_eq!(result["context_after"], json!(["line 6", "line 7"]));
+}
+
+#[test]
+fn invalid_combinations_are_errors() {
+    let fixture = fixture();
+    for (args, error) in [
+        (json!({ "query": "needle", "line_start": 1 }), "line_start and line_end require files with exactly one entry"),
+        (
+            json!({ "query": "needle", "files": ["a.txt", "b.txt"], "line_end": 3 }),
+            "line_start and line_end require files with exactly one entry",
+        ),
+        (
+            json!({ "query": "needle", "files": ["a.txt"], "line_start": 6, "line_end": 4 }),
+            "line_start must not be greater than line_end",
+        ),
+        (
+            json!({ "query": "needle", "files": ["a.txt"], "line_start": 1, "replace": "pin" }),
+            "line_start and line_end cannot be used together with replace",
+        ),
+    ] {
+        let output = fixture.search(args.clone());
+        assert_eq!(output["status"], "error", "{}", args);
+        assert_eq!(output["error"], error, "{}", args);
+    }
+}
+
+#[test]
+fn missing_file_reports_no_window() {
+    let fixture = fixture();
+    let output = fixture.search(json!({ "query": "needle", "files": ["gone.txt"], "line_start": 1 }));
+    assert_eq!(output["status"], "success", "{}", output);
+    assert!(output["line_window"].is_null(), "{}", output);
+}
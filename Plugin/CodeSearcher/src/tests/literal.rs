@@ -0,0 +1,110 @@
+// 字面量快速路径 (LiteralMatcher) 与正则路径的差分测试：同一请求分别以字面量和转义后的正则发送，输出应完全相同
+
+mod common;
+
+use common::{strip_volatile, Fixture};
+use serde_json::{json, Value};
+
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .write("src/main.rs", "fn main() {\n    let a.b = Foo::new();\n    foo(a.b);\n    FOO + foo\n}\n")
+        .write("src/util.rs", "// helper\npub fn bar() -> Bar {\n    Bar(1)\n}\nfn foo_bar() {}\n")
+        .write("docs/notes.md", "a.b and (foo)\nnothing here\nBAR then foo\n")
+        .write("long.txt", format!("{}a.b{}\n", "-".repeat(300), "=".repeat(300)));
+    fixture
+}
+
+/// 把请求改成等价的正则请求：query/queries 逐项转义并打开 use_regex
+fn as_regex(args: &Value) -> Value {
+    let mut regex_args = args.clone();
+    let escape = |term: &Value| Value::String(regex::escape(term.as_str().unwrap()));
+    if let Some(query) = args.get("query") {
+        regex_args["query"] = escape(query);
+    }
+    if let Some(queries) = args.get("queries").and_then(Value::as_array) {
+        regex_args["queries"] = Value::Array(queries.iter().map(escape).collect());
+    }
+    regex_args["use_regex"] = Value::Bool(true);
+    regex_args
+}
+
+/// 正则路径中 matched_term 是转义后的搜索词，还原后再比较
+fn unescape_terms(mut output: Value, args: &Value) -> Value {
+    let terms: Vec<String> = args
+        .get("queries")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .chain(args.get("query"))
+        .map(|term| term.as_str().unwrap().to_string())
+        .collect();
+    for result in output["result"].as_array_mut().into_iter().flatten() {
+        if let Some(matched) = result.get("matched_term").and_then(Value::as_str) {
+            if let Some(term) = terms.iter().find(|term| regex::escape(term) == matched) {
+                result["matched_term"] = Value::String(term.clone());
+            }
+        }
+    }
+    output
+}
+
+fn assert_same(args: Value) -> Value {
+    assert_same_with_env(args, &[])
+}
+
+fn assert_same_with_env(args: Value, env: &[(&str, &str)]) -> Value {
+    let fixture = fixture();
+    let literal = strip_volatile(fixture.search_with_env(args.clone(), env));
+    let regex = unescape_terms(strip_volatile(fixture.search_with_env(as_regex(&args), env)), &args);
+    assert_eq!(literal["status"], "success", "{}", literal);
+    assert!(
+        literal["result"].as_array().is_some_and(|result| !result.is_empty()),
+        "request {} matched nothing",
+        args
+    );
+    assert_eq!(literal, regex, "literal and regex paths differ for {}", args);
+    literal
+}
+
+#[test]
+fn case_insensitive() {
+    assert_same(json!({ "query": "foo" }));
+    assert_same(json!({ "query": "a.b" }));
+}
+
+#[test]
+fn case_sensitive() {
+    assert_same(json!({ "query": "Foo", "case_sensitive": true }));
+    assert_same(json!({ "query": "(foo)", "case_sensitive": true }));
+}
+
+#[test]
+fn smart_case() {
+    assert_same(json!({ "query": "foo", "smart_case": true }));
+    assert_same(json!({ "query": "Bar", "smart_case": true }));
+}
+
+#[test]
+fn multiple_terms() {
+    assert_same(json!({ "queries": ["foo", "a.b"] }));
+    assert_same(json!({ "query": "bar", "queries": ["FOO"], "case_sensitive": true }));
+}
+
+#[test]
+fn operator_and() {
+    assert_same(json!({ "queries": ["foo", "bar"], "operator": "and" }));
+}
+
+#[test]
+fn invert_match() {
+    assert_same(json!({ "query": "foo", "invert_match": true, "search_path": "src" }));
+}
+
+#[test]
+fn truncation() {
+    let output = assert_same(json!({ "query": "a.b", "max_line_length": 40 }));
+    assert!(output["result"].as_array().unwrap().iter().any(|r| r["line_truncated"] == true));
+    let output = assert_same_with_env(json!({ "query": "foo" }), &[("MAX_RESULTS", "2")]);
+    assert_eq!(output["truncated_reason"], "max_results");
+}
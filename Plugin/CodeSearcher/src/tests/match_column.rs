@@ -0,0 +1,75 @@
+mod common;
+
+use common::Fixture;
+use serde_json::{json, Value};
+
+fn first(fixture: &Fixture, args: Value) -> Value {
+    let output = fixture.search(args);
+    assert_eq!(output["status"], "success", "{}", output);
+    output["result"][0].clone()
+}
+
+fn columns(result: &Value) -> (Value, Value) {
+    (result["match_column"].clone(), result["match_column_utf16"].clone())
+}
+
+#[test]
+fn ascii_columns_equal_byte_offsets() {
+    let fixture = Fixture::new();
+    fixture.write("a.rs", "let needle = 1;\n");
+    let result = first(&fixture, json!({ "query": "needle" }));
+    assert_eq!(columns(&result), (json!(4), json!(4)));
+}
+
+#[test]
+fn cjk_before_match_counts_characters() {
+    let fixture = Fixture::new();
+    // "今天写了" 是 4 个字符、12 个字节，每个字符占 1 个 UTF-16 码元
+    fixture.write("diary.md", "今天写了 needle\n");
+    let result = first(&fixture, json!({ "query": "needle" }));
+    assert_eq!(columns(&result), (json!(5), json!(5)));
+}
+
+#[test]
+fn astral_characters_take_two_utf16_units() {
+    let fixture = Fixture::new();
+    // 😀 在 UTF-8 中 4 个字节，在 UTF-16 中是一对代理项
+    fixture.write("a.md", "😀日 needle\n");
+    let result = first(&fixture, json!({ "query": "needle" }));
+    assert_eq!(columns(&result), (json!(3), json!(4)));
+}
+
+#[test]
+fn trimmed_lines_report_removed_whitespace() {
+    let fixture = Fixture::new();
+    fixture.write("a.md", "\t    中文 needle\n");
+
+    let result = first(&fixture, json!({ "query": "needle", "preserve_whitespace": "false" }));
+    assert_eq!(result["line_content"], "中文 needle");
+    assert_eq!(columns(&result), (json!(8), json!(8)));
+    assert_eq!(result["leading_whitespace_removed"], 5);
+    // 减去去掉的空白即为在 line_content 中的列
+    let column = result["match_column"].as_u64().unwrap() - result["leading_whitespace_removed"].as_u64().unwrap();
+    let rest: String = result["line_content"].as_str().unwrap().chars().skip(column as usize).collect();
+    assert_eq!(rest, "needle");
+
+    // 默认不 trim，列直接对应 line_content
+    let result = first(&fixture, json!({ "query": "needle" }));
+    assert_eq!(result["line_content"], "\t    中文 needle");
+    assert_eq!(columns(&result), (json!(8), json!(8)));
+    assert!(result.get("leading_whitespace_removed").is_none(), "{}", result);
+}
+
+#[test]
+fn truncated_lines_keep_original_columns() {
+    let fixture = Fixture::new();
+    fixture.write("a.md", format!("{}😀needle{}\n", "字".repeat(100), "尾".repeat(100)));
+
+    let result = first(&fixture, json!({ "query": "needle", "max_line_length": "30" }));
+    assert_eq!(result["line_truncated"], true);
+    assert_eq!(result["original_match_column"], 101);
+    // match_column 相对截断后的 line_content
+    let column = result["match_column"].as_u64().unwrap() as usize;
+    let rest: String = result["line_content"].as_str().unwrap().chars().skip(column).collect();
+    assert!(rest.starts_with("needle"), "{}", result);
+}
@@ -0,0 +1,96 @@
+// matched_text 与 match_start/match_end：本行第一个匹配在 line_content 中的字符区间
+
+mod common;
+
+use common::Fixture;
+use serde_json::{json, Value};
+
+fn first(fixture: &Fixture, args: Value) -> Value {
+    let output = fixture.search(args);
+    output["result"][0].clone()
+}
+
+/// 按 match_start/match_end 从 line_content 中截出的文本
+fn slice(result: &Value) -> String {
+    let start = result["match_start"].as_u64().unwrap() as usize;
+    let end = result["match_end"].as_u64().unwrap() as usize;
+    result["line_content"].as_str().unwrap().chars().skip(start).take(end - start).collect()
+}
+
+#[test]
+fn regex_with_capture_groups() {
+    let fixture = Fixture::new();
+    fixture.write("a.rs", "    let x = foobar + fooo;\n");
+
+    let result = first(&fixture, json!({ "query": "(fo+)(bar)?", "use_regex": "true", "preserve_whitespace": "false" }));
+    assert_eq!(result["line_content"], "let x = foobar + fooo;");
+    assert_eq!(result["matched_text"], "foobar");
+    assert_eq!((result["match_start"].clone(), result["match_end"].clone()), (json!(8), json!(14)));
+    assert_eq!(slice(&result), "foobar");
+    // match_column 相对未 trim 的原始行
+    assert_eq!(result["match_column"], 12);
+
+    // 可选组未参与匹配
+    let result = first(&fixture, json!({ "query": r"(fo{3})(bar)?", "use_regex": "true" }));
+    assert_eq!(result["matched_text"], "fooo");
+    assert_eq!(slice(&result), "fooo");
+}
+
+#[test]
+fn overlapping_candidates() {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "xabcdef\n");
+
+    // 最左的匹配优先；同一位置按搜索词的先后顺序
+    let result = first(&fixture, json!({ "queries": ["bcd", "abc"] }));
+    assert_eq!(result["matched_text"], "abc");
+    assert_eq!((result["match_start"].clone(), result["match_end"].clone()), (json!(1), json!(4)));
+    assert_eq!(result["matched_term"], "abc");
+
+    let result = first(&fixture, json!({ "queries": ["abc", "abcdef"] }));
+    assert_eq!(result["matched_text"], "abc");
+    let result = first(&fixture, json!({ "queries": ["abcdef", "abc"] }));
+    assert_eq!(result["matched_text"], "abcdef");
+    assert_eq!(slice(&result), "abcdef");
+
+    // 正则分支同样按 leftmost-first
+    let result = first(&fixture, json!({ "query": "(b|bcde)(c|f)?", "use_regex": "true" }));
+    assert_eq!(result["matched_text"], "bc");
+    assert_eq!((result["match_start"].clone(), result["match_end"].clone()), (json!(2), json!(4)));
+}
+
+#[test]
+fn character_offsets() {
+    let fixture = Fixture::new();
+    fixture.write("a.md", "数据：needle 和 needle\n");
+
+    let result = first(&fixture, json!({ "query": "needle" }));
+    assert_eq!((result["match_start"].clone(), result["match_end"].clone()), (json!(3), json!(9)));
+    assert_eq!(slice(&result), "needle");
+}
+
+#[test]
+fn truncated_line() {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", format!("{}needle{}\n", "a".repeat(200), "b".repeat(200)));
+
+    let result = first(&fixture, json!({ "query": "needle", "max_line_length": "30" }));
+    assert_eq!(result["line_truncated"], true);
+    assert_eq!(result["matched_text"], "needle");
+    assert_eq!(slice(&result), "needle");
+    assert_eq!(result["original_match_column"], 200);
+}
+
+#[test]
+fn highlight_spans_cover_every_match_in_match_column_coordinates() {
+    let fixture = Fixture::new();
+    fixture.write("a.rs", "    foo + Foo + bar;\n");
+
+    let result = first(&fixture, json!({ "query": "foo", "include_spans": "true", "preserve_whitespace": "false" }));
+    assert_eq!(result["match_column"], 4);
+    assert_eq!(result["highlight_spans"], json!([[4, 7], [10, 13]]));
+    assert_eq!(result["match_start"], 0);
+
+    let result = first(&fixture, json!({ "query": "foo" }));
+    assert!(result.get("highlight_spans").is_none(), "{}", result);
+}
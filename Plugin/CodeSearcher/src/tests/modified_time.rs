@@ -0,0 +1,38 @@
+mod common;
+
+use common::Fixture;
+use serde_json::json;
+use std::fs;
+use std::time::{Duration, SystemTime};
+
+#[test]
+fn modified_after_skips_older_files() {
+    let fixture = Fixture::new();
+    fixture.write("old.rs", "needle\n");
+    fixture.write("new.rs", "needle\n");
+    let three_days_ago = SystemTime::now() - Duration::from_secs(3 * 24 * 60 * 60);
+    fs::File::options().write(true).open(fixture.path("old.rs")).unwrap().set_modified(three_days_ago).unwrap();
+
+    let output = fixture.search(json!({ "query": "needle", "modified_after": "48h" }));
+    assert_eq!(output["status"], "success", "{}", output);
+    assert_eq!(common::locations(&output), vec![("new.rs".to_string(), 1)]);
+    assert_eq!(output["files_skipped"], 1);
+
+    let output = fixture.search(json!({ "query": "needle", "modified_before": "2d" }));
+    assert_eq!(common::locations(&output), vec![("old.rs".to_string(), 1)]);
+
+    // 纯日期按当天 0 点 UTC 解析
+    let output = fixture.search(json!({ "query": "needle", "modified_after": "1970-01-02" }));
+    assert_eq!(common::locations(&output).len(), 2, "{}", output);
+}
+
+#[test]
+fn invalid_time_filter_names_the_value() {
+    let fixture = Fixture::new();
+    fixture.write("a.rs", "needle\n");
+
+    let output = fixture.search(json!({ "query": "needle", "modified_before": "yesterday" }));
+    assert_eq!(output["status"], "error");
+    let error = output["error"].as_str().unwrap();
+    assert!(error.contains("modified_before") && error.contains("yesterday"), "{}", error);
+}
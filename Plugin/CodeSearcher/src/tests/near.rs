@@ -0,0 +1,41 @@
+mod common;
+
+use common::{locations, Fixture};
+use serde_json::json;
+
+/// alpha 在第 2、6 行，beta 在第 5、6 行
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .write("a.rs", "fn a() {}\nlet alpha = 1;\n\n\nlet beta = 2;\nalpha beta\n")
+        .write("b.rs", "alpha\n\nbeta\n");
+    fixture
+}
+
+#[test]
+fn distance_zero_requires_the_same_line() {
+    let output = fixture().search(json!({ "near": { "a": "alpha", "b": "beta", "distance": 0 }, "context_lines": 0 }));
+    assert_eq!(output["status"], "success", "{}", output);
+    assert_eq!(locations(&output), [("a.rs".to_string(), 6)]);
+    assert_eq!(output["result"][0]["near_lines"], json!([6, 6]));
+}
+
+#[test]
+fn distance_widens_the_window() {
+    let fixture = fixture();
+    let output = fixture.search(json!({ "near": { "a": "alpha", "b": "beta", "distance": "2" }, "context_lines": 0 }));
+    assert_eq!(locations(&output), [("a.rs".to_string(), 5), ("a.rs".to_string(), 6), ("b.rs".to_string(), 1)]);
+    assert_eq!(output["result"][2]["near_lines"], json!([1, 3]));
+
+    let output = fixture.search(json!({ "near": { "a": "alpha", "b": "beta", "distance": "3" }, "context_lines": 0 }));
+    assert_eq!(
+        locations(&output),
+        [
+            ("a.rs".to_string(), 2),
+            ("a.rs".to_string(), 5),
+            ("a.rs".to_string(), 6),
+            ("b.rs".to_string(), 1),
+        ]
+    );
+    assert_eq!(output["result"][0]["near_lines"], json!([2, 5]));
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use common::{locations, Fixture};
+use serde_json::{json, Value};
+
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    for dir in 0..3 {
+        for file in 0..4 {
+            let lines: Vec<String> = (0..=file).map(|n| format!("needle {}", n)).collect();
+            fixture.write(&format!("d{}/f{}.rs", dir, file), lines.join("\n") + "\n");
+        }
+    }
+    fixture
+}
+
+fn has_sort_warning(output: &Value) -> bool {
+    output["query_warnings"]
+        .as_array()
+        .is_some_and(|warnings| warnings.iter().any(|w| w.as_str().unwrap().contains("sort \"none\"")))
+}
+
+#[test]
+fn pages_cover_all_results_once() {
+    let fixture = fixture();
+    let env = [("MAX_RESULTS", "1000"), ("CODESEARCHER_THREADS", "4")];
+    let full = locations(&fixture.search_with_env(json!({ "query": "needle" }), &env));
+    assert_eq!(full.len(), 30);
+
+    let mut paged = Vec::new();
+    for offset in (0..full.len() + 7).step_by(7) {
+        let output = fixture.search_with_env(json!({ "query": "needle", "offset": offset, "limit": 7 }), &env);
+        assert_eq!(output["total_count"], 30);
+        paged.extend(locations(&output));
+    }
+    assert_eq!(paged, full);
+}
+
+#[test]
+fn limit_and_offset_edges() {
+    let fixture = fixture();
+    let output = fixture.search(json!({ "query": "needle", "limit": 4 }));
+    assert_eq!(locations(&output).len(), 4);
+    assert_eq!(output["truncated_reason"], "max_results");
+    assert_eq!(output["total_count"], 30);
+
+    let output = fixture.search(json!({ "query": "needle", "offset": 28, "limit": 4 }));
+    assert_eq!(locations(&output).len(), 2);
+    assert!(output["truncated_reason"].is_null(), "{}", output);
+
+    let output = fixture.search(json!({ "query": "needle", "offset": 100 }));
+    assert!(locations(&output).is_empty());
+    assert_eq!(output["total_count"], 30);
+}
+
+#[test]
+fn unsorted_offset_warning_follows_effective_threads() {
+    let fixture = fixture();
+    let args = json!({ "query": "needle", "sort": "none", "offset": 2, "limit": 3 });
+
+    // 夹具默认 CODESEARCHER_THREADS=1
+    assert!(!has_sort_warning(&fixture.search(args.clone())));
+
+    let output = fixture.search_with_env(args.clone(), &[("CODESEARCHER_THREADS", "4")]);
+    assert!(has_sort_warning(&output), "{}", output);
+
+    let mut single = args.clone();
+    single["threads"] = json!(1);
+    assert!(!has_sort_warning(&fixture.search_with_env(single, &[("CODESEARCHER_THREADS", "4")])));
+
+    let mut multi = args.clone();
+    multi["threads"] = json!(2);
+    assert!(has_sort_warning(&fixture.search(multi)));
+
+    // 没有 offset 或使用稳定排序时不提示
+    let output = fixture.search_with_env(json!({ "query": "needle", "sort": "none", "limit": 3 }), &[("CODESEARCHER_THREADS", "4")]);
+    assert!(!has_sort_warning(&output));
+    let output = fixture.search_with_env(json!({ "query": "needle", "offset": 2 }), &[("CODESEARCHER_THREADS", "4")]);
+    assert!(!has_sort_warning(&output));
+}
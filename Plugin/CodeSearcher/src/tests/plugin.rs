@@ -0,0 +1,74 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::{locations, Fixture};
+use serde_json::{json, Value};
+use std::fs::{self, Permissions};
+use std::os::unix::fs::PermissionsExt;
+
+/// 按文件名决定行为的读取插件：ok 输出文本，fail 以非零状态退出，slow 一直不结束
+const PLUGIN: &str = r#"#!/bin/sh
+case "$1" in
+    *ok.docx) printf 'first line\nneedle from plugin\n' ;;
+    *fail.docx) echo "cannot parse" >&2; exit 3 ;;
+    *slow.docx) exec sleep 5 ;;
+esac
+"#;
+
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .write("reader.sh", PLUGIN)
+        .write("a.rs", "fn needle() {}\n")
+        .write("ok.docx", "binary placeholder")
+        .write("fail.docx", "binary placeholder")
+        .write("slow.docx", "binary placeholder");
+    fs::set_permissions(fixture.path("reader.sh"), Permissions::from_mode(0o755)).expect("chmod plugin");
+    fixture
+}
+
+fn search(fixture: &Fixture, files: &[&str]) -> Value {
+    let plugin = fixture.path("reader.sh");
+    fixture.search_with_env(
+        json!({ "query": "needle", "files": files, "context_lines": 0 }),
+        &[("FILE_READER_PLUGIN", plugin.to_str().unwrap()), ("PLUGIN_EXTENSIONS", "docx"), ("PLUGIN_TIMEOUT_MS", "300")],
+    )
+}
+
+fn warning_kinds(output: &Value) -> Vec<(String, String)> {
+    output["warnings"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|w| (w["path"].as_str().unwrap().to_string(), w["kind"].as_str().unwrap().to_string()))
+        .collect()
+}
+
+#[test]
+fn plugin_output_is_searched_like_file_contents() {
+    let fixture = fixture();
+    let output = search(&fixture, &["ok.docx", "a.rs"]);
+    assert_eq!(output["status"], "success", "{}", output);
+    assert_eq!(locations(&output), [("a.rs".to_string(), 1), ("ok.docx".to_string(), 2)]);
+    assert_eq!(output["result"][1]["line_content"], "needle from plugin");
+    assert!(output.get("warnings").is_none(), "{}", output);
+}
+
+#[test]
+fn plugin_failure_is_a_warning() {
+    let fixture = fixture();
+    let output = search(&fixture, &["fail.docx", "a.rs"]);
+    assert_eq!(output["status"], "success", "{}", output);
+    assert_eq!(locations(&output), [("a.rs".to_string(), 1)]);
+    assert_eq!(warning_kinds(&output), [("fail.docx".to_string(), "read_error".to_string())]);
+}
+
+#[test]
+fn plugin_timeout_is_a_warning() {
+    let fixture = fixture();
+    let output = search(&fixture, &["slow.docx", "a.rs"]);
+    assert_eq!(output["status"], "success", "{}", output);
+    assert_eq!(locations(&output), [("a.rs".to_string(), 1)]);
+    assert_eq!(warning_kinds(&output), [("slow.docx".to_string(), "timeout".to_string())]);
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use common::{locations, run_in, Fixture};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+
+/// 不带 project_base 在 cwd 下搜索
+fn search_in(cwd: &Path, args: Value, env: &[(&str, &str)]) -> Value {
+    let stdout = run_in(cwd, &args.to_string(), env);
+    serde_json::from_str(&stdout).unwrap_or_else(|e| panic!("invalid JSON output ({}): {}", e, stdout))
+}
+
+fn files(output: &Value) -> Vec<String> {
+    assert_eq!(output["status"], "success", "{}", output);
+    locations(output).into_iter().map(|(path, _)| path).collect()
+}
+
+/// 项目根目录带 marker 文件，工作目录在两层子目录里
+fn marker_fixture(marker: &str) -> Fixture {
+    let fixture = Fixture::new();
+    fixture.write("src/a.rs", "fn needle() {}\n");
+    fs::create_dir_all(fixture.path("src/deep/er")).unwrap();
+    if marker == ".git" {
+        fs::create_dir_all(fixture.path(".git")).unwrap();
+    } else {
+        fixture.write(marker, "{}\n");
+    }
+    fixture
+}
+
+#[test]
+fn marker_discovery_walks_up_from_cwd() {
+    for marker in ["Cargo.toml", "package.json", ".git"] {
+        let fixture = marker_fixture(marker);
+        let output = search_in(&fixture.path("src/deep/er"), json!({ "query": "needle" }), &[]);
+        assert_eq!(files(&output), ["src/a.rs"], "{}", marker);
+    }
+}
+
+#[test]
+fn without_markers_cwd_is_the_root() {
+    let fixture = Fixture::new();
+    fixture.write("sub/a.rs", "fn needle() {}\n");
+    fixture.write("b.rs", "fn needle() {}\n");
+    let output = search_in(&fixture.path("sub"), json!({ "query": "needle" }), &[]);
+    assert_eq!(files(&output), ["a.rs"]);
+}
+
+#[test]
+fn project_base_path_env_wins_over_markers() {
+    let fixture = marker_fixture("Cargo.toml");
+    let other = Fixture::new();
+    other.write("other.rs", "fn needle() {}\n");
+    let env = other.root.to_string_lossy().into_owned();
+
+    let output = search_in(&fixture.path("src"), json!({ "query": "needle" }), &[("PROJECT_BASE_PATH", &env)]);
+    assert_eq!(files(&output), ["other.rs"]);
+}
+
+#[test]
+fn missing_project_base_path_falls_back_to_markers() {
+    let fixture = marker_fixture("Cargo.toml");
+    let missing = fixture.path("no/such/dir").to_string_lossy().into_owned();
+    let output = search_in(&fixture.path("src"), json!({ "query": "needle" }), &[("PROJECT_BASE_PATH", &missing)]);
+    assert_eq!(files(&output), ["src/a.rs"]);
+}
+
+#[test]
+fn project_base_arg_wins_over_env_and_markers() {
+    let fixture = marker_fixture("Cargo.toml");
+    let other = Fixture::new();
+    other.write("other.rs", "fn needle() {}\n");
+    let chosen = Fixture::new();
+    chosen.write("chosen.rs", "fn needle() {}\n");
+    let env = other.root.to_string_lossy().into_owned();
+
+    let output = search_in(
+        &fixture.path("src"),
+        json!({ "query": "needle", "project_base": chosen.root.to_string_lossy() }),
+        &[("PROJECT_BASE_PATH", &env)],
+    );
+    assert_eq!(files(&output), ["chosen.rs"]);
+
+    let output = search_in(&fixture.root, json!({ "query": "needle", "project_base": fixture.path("nope").to_string_lossy() }), &[]);
+    assert_eq!(output["status"], "error", "{}", output);
+    assert!(output["error"].as_str().unwrap().starts_with("Invalid project_base"), "{}", output);
+}
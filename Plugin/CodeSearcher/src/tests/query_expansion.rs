@@ -0,0 +1,55 @@
+// query_expansion 与手写分支正则的差分测试：两种写法的输出应完全相同
+
+mod common;
+
+use common::{locations, strip_volatile, Fixture};
+use serde_json::{json, Value};
+
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .write("src/a.rs", "let a.b = 1;\nlet axb = 2;\nfn Remove() {}\n")
+        .write("src/b.rs", "delete x\nremoved\nnothing\n");
+    fixture
+}
+
+fn assert_same(expanded: Value, alternation: Value) -> Value {
+    let fixture = fixture();
+    let expanded = strip_volatile(fixture.search(expanded));
+    let alternation = strip_volatile(fixture.search(alternation));
+    assert_eq!(expanded["status"], "success", "{}", expanded);
+    assert_eq!(expanded, alternation);
+    expanded
+}
+
+#[test]
+fn literal_terms_match_the_escaped_alternation() {
+    let output = assert_same(
+        json!({ "query": "a.b", "query_expansion": ["remove", "delete"], "context_lines": 0 }),
+        json!({ "query": "(?:a\\.b|remove|delete)", "use_regex": true, "context_lines": 0 }),
+    );
+    assert_eq!(
+        locations(&output),
+        [
+            ("src/a.rs".to_string(), 1),
+            ("src/a.rs".to_string(), 3),
+            ("src/b.rs".to_string(), 1),
+            ("src/b.rs".to_string(), 2),
+        ]
+    );
+}
+
+#[test]
+fn whole_word_and_case_apply_to_the_whole_group() {
+    let output = assert_same(
+        json!({
+            "query": "a.b", "query_expansion": ["remove", "delete"],
+            "whole_word": true, "case_sensitive": true, "context_lines": 0,
+        }),
+        json!({
+            "query": "(?:a\\.b|remove|delete)", "use_regex": true,
+            "whole_word": true, "case_sensitive": true, "context_lines": 0,
+        }),
+    );
+    assert_eq!(locations(&output), [("src/a.rs".to_string(), 1), ("src/b.rs".to_string(), 1)]);
+}
@@ -0,0 +1,147 @@
+mod common;
+
+use common::Fixture;
+use serde_json::json;
+use std::fs;
+
+#[test]
+fn dry_run_returns_a_unified_diff_without_touching_files() {
+    let fixture = Fixture::new();
+    fixture.write("src/a.rs", "let old = 1;\nlet other = 2;\n");
+
+    let output = fixture.search(json!({ "query": "old", "replace": "new" }));
+    assert_eq!(output["status"], "success", "{}", output);
+    let replacement = &output["replacements"][0];
+    assert_eq!(replacement["file_path"], "src/a.rs");
+    assert_eq!(replacement["replacements"], 1);
+    assert_eq!(replacement["modified"], false);
+    assert!(output.get("files_modified").is_none(), "{}", output);
+
+    let diff = replacement["diff"].as_str().unwrap();
+    assert!(diff.starts_with("--- a/src/a.rs\n+++ b/src/a.rs\n"), "{}", diff);
+    assert!(diff.contains("-let old = 1;\n+let new = 1;\n"), "{}", diff);
+    assert_eq!(fs::read_to_string(fixture.path("src/a.rs")).unwrap(), "let old = 1;\nlet other = 2;\n");
+}
+
+#[test]
+fn rewrite_keeps_bom_line_endings_and_permissions() {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "\u{feff}old one\r\nkeep\r\nold two");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(fixture.path("a.txt"), fs::Permissions::from_mode(0o640)).unwrap();
+    }
+
+    let output = fixture.search(json!({ "query": "old", "replace": "new", "dry_run": "false" }));
+    assert_eq!(output["status"], "success", "{}", output);
+    assert_eq!(output["files_modified"], 1);
+    assert_eq!(output["replacements"][0]["modified"], true);
+    assert!(output["replacements"][0].get("diff").is_none(), "{}", output);
+
+    assert_eq!(fs::read_to_string(fixture.path("a.txt")).unwrap(), "\u{feff}new one\r\nkeep\r\nnew two");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(fixture.path("a.txt")).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o640);
+    }
+    // 临时文件 a.codesearcher.<pid>.tmp 已重命名为原文件，不应残留
+    let leftovers: Vec<_> = fs::read_dir(&fixture.root)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .filter(|name| name.ends_with(".tmp"))
+        .collect();
+    assert!(leftovers.is_empty(), "{:?}", leftovers);
+}
+
+#[test]
+fn captures_expand_only_with_use_regex() {
+    let fixture = Fixture::new();
+    fixture.write("a.rs", "call(foo)\n");
+
+    let output = fixture.search(json!({
+        "query": r"call\((\w+)\)", "use_regex": "true", "replace": "invoke($1)", "dry_run": "false",
+    }));
+    assert_eq!(output["status"], "success", "{}", output);
+    assert_eq!(fs::read_to_string(fixture.path("a.rs")).unwrap(), "invoke(foo)\n");
+
+    // 不用正则时 $1 按字面量写入
+    let output = fixture.search(json!({ "query": "invoke(foo)", "replace": "$1", "dry_run": "false" }));
+    assert_eq!(output["status"], "success", "{}", output);
+    assert_eq!(fs::read_to_string(fixture.path("a.rs")).unwrap(), "$1\n");
+}
+
+#[test]
+fn max_replacements_skips_files_that_would_exceed_it() {
+    let fixture = Fixture::new();
+    fixture.write("a.rs", "needle\n");
+    fixture.write("b.rs", "needle needle\n");
+
+    let output = fixture.search(json!({
+        "query": "needle", "replace": "pin", "dry_run": "false", "max_replacements": "2",
+    }));
+    assert_eq!(output["status"], "success", "{}", output);
+    let replacements = output["replacements"].as_array().unwrap();
+    let modified: Vec<_> = replacements.iter().filter(|r| r["modified"] == true).collect();
+    let skipped: Vec<_> = replacements.iter().filter(|r| r["skipped"] == "max_replacements exceeded").collect();
+    // 两个文件合计 3 次替换，超出上限 2，只有一个文件能被改写
+    assert_eq!((modified.len(), skipped.len()), (1, 1), "{}", output);
+    assert_eq!(output["files_modified"], 1);
+
+    let skipped_path = skipped[0]["file_path"].as_str().unwrap();
+    let original = if skipped_path == "a.rs" { "needle\n" } else { "needle needle\n" };
+    assert_eq!(fs::read_to_string(fixture.path(skipped_path)).unwrap(), original);
+}
+
+#[test]
+fn replace_rejects_invert_match() {
+    let fixture = Fixture::new();
+    fixture.write("a.rs", "needle\nother\n");
+
+    let output = fixture.search(json!({
+        "query": "needle", "replace": "pin", "dry_run": "false", "invert_match": "true", "search_path": ".",
+    }));
+    assert_eq!(output["status"], "error");
+    assert_eq!(output["error"], "replace cannot be used together with invert_match");
+    assert_eq!(fs::read_to_string(fixture.path("a.rs")).unwrap(), "needle\nother\n");
+}
+
+#[test]
+fn replace_leaves_files_over_max_file_size_alone() {
+    let fixture = Fixture::new();
+    fixture.write("small.rs", "needle\n");
+    fixture.write("large.rs", format!("needle\n{}\n", "x".repeat(100)));
+
+    let output = fixture.search(json!({
+        "query": "needle", "replace": "pin", "dry_run": "false", "max_file_size": "50",
+    }));
+    assert_eq!(output["status"], "success", "{}", output);
+    assert_eq!(output["files_modified"], 1);
+    assert_eq!(output["files_skipped"], 1);
+    assert_eq!(fs::read_to_string(fixture.path("small.rs")).unwrap(), "pin\n");
+    assert!(fs::read_to_string(fixture.path("large.rs")).unwrap().starts_with("needle\n"));
+
+    // 不限制文件大小时拒绝替换
+    let output = fixture.search(json!({ "query": "needle", "replace": "pin", "max_file_size": "0" }));
+    assert_eq!(output["status"], "error");
+    assert_eq!(output["error"], "replace requires a max_file_size limit");
+}
+
+#[test]
+fn replace_rejects_max_matches_per_file() {
+    let fixture = Fixture::new();
+    fixture.write("a.rs", "needle needle\nneedle\n");
+
+    let output = fixture.search(json!({
+        "query": "needle", "replace": "pin", "dry_run": "false", "max_matches_per_file": "1",
+    }));
+    assert_eq!(output["status"], "error");
+    assert_eq!(output["error"], "replace cannot be used together with max_matches_per_file");
+    assert_eq!(fs::read_to_string(fixture.path("a.rs")).unwrap(), "needle needle\nneedle\n");
+
+    // 0 表示不限制，可以同时使用
+    let output = fixture.search(json!({ "query": "needle", "replace": "pin", "max_matches_per_file": "0" }));
+    assert_eq!(output["status"], "success", "{}", output);
+    assert_eq!(output["replacements"][0]["replacements"], 3);
+}
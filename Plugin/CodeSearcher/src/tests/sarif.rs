@@ -0,0 +1,44 @@
+mod common;
+
+use common::Fixture;
+use serde_json::json;
+
+#[test]
+fn sarif_columns_point_into_original_line() {
+    let fixture = Fixture::new();
+    // 200 个 BMP 汉字加一个占两个 UTF-16 码元的字符，匹配从第 202 个码元开始
+    let line = format!("{}𝄞needle{}", "数据".repeat(100), "x".repeat(300));
+    fixture.write("src/a.rs", format!("fn main() {{}}\n{}\n", line));
+
+    let stdout = fixture.run(json!({ "query": "needle", "format": "sarif", "max_line_length": 40 }), &[]);
+    let log: serde_json::Value = serde_json::from_str(&stdout).expect("SARIF is JSON");
+
+    assert_eq!(log["version"], "2.1.0");
+    let run = &log["runs"][0];
+    assert_eq!(run["columnKind"], "utf16CodeUnits");
+    let rule_id = run["tool"]["driver"]["rules"][0]["id"].as_str().expect("rule id");
+
+    let results = run["results"].as_array().expect("results");
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["ruleId"], rule_id);
+    let location = &results[0]["locations"][0]["physicalLocation"];
+    assert_eq!(location["artifactLocation"]["uri"], "src/a.rs");
+    assert_eq!(location["artifactLocation"]["uriBaseId"], "%SRCROOT%");
+    let region = &location["region"];
+    assert_eq!(region["startLine"], 2);
+    assert_eq!(region["startColumn"], 203);
+    assert_eq!(region["endColumn"], 209);
+}
+
+#[test]
+fn sarif_columns_without_truncation() {
+    let fixture = Fixture::new();
+    fixture.write("a.rs", "let 𝄞 = needle;\n");
+
+    let stdout = fixture.run(json!({ "query": "needle", "format": "sarif" }), &[]);
+    let log: serde_json::Value = serde_json::from_str(&stdout).expect("SARIF is JSON");
+    let region = &log["runs"][0]["results"][0]["locations"][0]["physicalLocation"]["region"];
+    assert_eq!(region["startLine"], 1);
+    assert_eq!(region["startColumn"], 10);
+    assert_eq!(region["endColumn"], 16);
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use common::{locations, Fixture};
+use serde_json::json;
+
+fn assert_escapes(fixture: &Fixture, search_path: &str) {
+    let output = fixture.search(json!({ "query": "needle", "search_path": search_path }));
+    assert_eq!(output["status"], "error", "{}: {}", search_path, output);
+    assert_eq!(output["error"], "search_path escapes project root", "{}", search_path);
+}
+
+fn fixtures() -> (Fixture, Fixture) {
+    let outside = Fixture::new();
+    outside.write("secret.rs", "fn needle() {}\n");
+    let fixture = Fixture::new();
+    fixture.write("src/a.rs", "fn needle() {}\n");
+    (fixture, outside)
+}
+
+#[test]
+fn parent_components_are_rejected() {
+    let (fixture, outside) = fixtures();
+    assert_escapes(&fixture, "..");
+    assert_escapes(&fixture, "src/../..");
+    let relative = format!("../{}", outside.root.file_name().unwrap().to_string_lossy());
+    assert_escapes(&fixture, &relative);
+}
+
+#[test]
+fn parent_components_inside_root_are_allowed() {
+    let (fixture, _outside) = fixtures();
+    let output = fixture.search(json!({ "query": "needle", "search_path": "src/.." }));
+    assert_eq!(output["status"], "success", "{}", output);
+    let found = locations(&output);
+    assert_eq!(found.len(), 1, "{}", output);
+    assert!(found[0].0.ends_with("src/a.rs"), "{:?}", found);
+}
+
+#[test]
+fn absolute_paths_are_checked() {
+    let (fixture, outside) = fixtures();
+    assert_escapes(&fixture, &outside.root.to_string_lossy());
+
+    // 位于项目内的绝对路径仍然可用
+    let output = fixture.search(json!({ "query": "needle", "search_path": fixture.path("src").to_string_lossy() }));
+    assert_eq!(output["status"], "success", "{}", output);
+    assert_eq!(locations(&output), vec![("src/a.rs".to_string(), 1)]);
+}
+
+#[test]
+fn missing_search_path_is_an_error() {
+    let (fixture, _outside) = fixtures();
+    let output = fixture.search(json!({ "query": "needle", "search_path": "nope" }));
+    assert_eq!(output["status"], "error", "{}", output);
+    assert!(output["error"].as_str().unwrap().starts_with("Invalid search_path"), "{}", output);
+}
+
+#[cfg(unix)]
+#[test]
+fn symlink_to_outside_directory_is_rejected() {
+    let (fixture, outside) = fixtures();
+    std::os::unix::fs::symlink(&outside.root, fixture.path("external")).unwrap();
+    assert_escapes(&fixture, "external");
+    assert_escapes(&fixture, "src/../external");
+}
+
+#[test]
+fn search_root_is_the_resolved_absolute_directory() {
+    let (fixture, _outside) = fixtures();
+    let root = fixture.root.canonicalize().unwrap();
+
+    let output = fixture.search(json!({ "query": "needle" }));
+    assert_eq!(output["search_root"], root.to_string_lossy().as_ref(), "{}", output);
+    assert_eq!(output["project_base"], root.to_string_lossy().as_ref(), "{}", output);
+
+    let output = fixture.search(json!({ "query": "needle", "search_path": "src/../src" }));
+    assert_eq!(output["search_root"], root.join("src").to_string_lossy().as_ref(), "{}", output);
+    assert_eq!(output["project_base"], root.to_string_lossy().as_ref(), "{}", output);
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use common::{locations, Fixture};
+use serde_json::{json, Value};
+
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    fixture.write("a.rs", "let Value = 1;\nlet value = 2;\nlet VALUE = 3;\nlet values = 4;\n");
+    fixture
+}
+
+fn lines(output: &Value) -> Vec<u64> {
+    assert_eq!(output["status"], "success", "{}", output);
+    locations(output).into_iter().map(|(_, line)| line).collect()
+}
+
+#[test]
+fn lowercase_query_is_case_insensitive() {
+    let fixture = fixture();
+    let output = fixture.search(json!({ "query": "value", "smart_case": "true", "context_lines": "0" }));
+    assert_eq!(lines(&output), vec![1, 2, 3, 4]);
+}
+
+#[test]
+fn uppercase_query_is_case_sensitive() {
+    let fixture = fixture();
+    let output = fixture.search(json!({ "query": "Value", "smart_case": "true", "context_lines": "0" }));
+    assert_eq!(lines(&output), vec![1]);
+}
+
+#[test]
+fn regex_escapes_are_not_uppercase_literals() {
+    let fixture = fixture();
+    // \S、\W、\p{Lu} 和 [:upper:] 中的大写字母都是元字符，仍按不区分大小写处理
+    for query in [r"valu\S", r"value\W", r"\p{Lu}alue", r"[[:upper:]]alue", r"(?P<Name>value)"] {
+        let output = fixture.search(json!({ "query": query, "use_regex": "true", "smart_case": "true", "context_lines": "0" }));
+        assert!(lines(&output).contains(&3), "{} should match VALUE case-insensitively: {}", query, output);
+    }
+
+    // 字面的大写字母仍会切换为区分大小写
+    let output = fixture.search(json!({ "query": r"V\w+", "use_regex": "true", "smart_case": "true", "context_lines": "0" }));
+    assert_eq!(lines(&output), vec![1, 3]);
+}
+
+#[test]
+fn composes_with_whole_word() {
+    let fixture = fixture();
+    let output = fixture.search(json!({ "query": "value", "smart_case": "true", "whole_word": "true", "context_lines": "0" }));
+    assert_eq!(lines(&output), vec![1, 2, 3]);
+
+    let output = fixture.search(json!({ "query": "VALUE", "smart_case": "true", "whole_word": "true", "context_lines": "0" }));
+    assert_eq!(lines(&output), vec![3]);
+}
+
+#[test]
+fn case_sensitive_takes_precedence_over_smart_case() {
+    let fixture = fixture();
+    // case_sensitive=true 时小写搜索词也区分大小写
+    let output = fixture.search(json!({ "query": "value", "case_sensitive": "true", "smart_case": "true", "context_lines": "0" }));
+    assert_eq!(lines(&output), vec![2, 4]);
+
+    // case_sensitive=false 不会阻止 smart_case 因大写字母切换为区分大小写
+    let output = fixture.search(json!({ "query": "Value", "case_sensitive": "false", "smart_case": "true", "context_lines": "0" }));
+    assert_eq!(lines(&output), vec![1]);
+}
+
+#[test]
+fn without_smart_case_uppercase_query_is_case_insensitive() {
+    let fixture = fixture();
+    let output = fixture.search(json!({ "query": "Value", "context_lines": "0" }));
+    assert_eq!(lines(&output), vec![1, 2, 3, 4]);
+}
@@ -0,0 +1,101 @@
+// 排序模式下汇总线程边收边裁剪，只保留前 MAX_RESULTS 条：结果应与不裁剪时完整结果的开头相同
+
+mod common;
+
+use common::{locations, Fixture};
+use serde_json::{json, Value};
+use std::fs::File;
+use std::time::{Duration, SystemTime};
+
+/// 各文件匹配数不同 (有并列)，修改时间各不相同且与路径顺序无关
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    let files = [("a.rs", 1), ("b.rs", 5), ("c.txt", 2), ("d.rs", 5), ("e.txt", 3), ("f.md", 4), ("g.md", 1), ("h.rs", 3)];
+    for (i, (name, count)) in files.iter().enumerate() {
+        let lines: Vec<String> = (0..*count).map(|n| format!("needle {}\nhay", n)).collect();
+        fixture.write(name, lines.join("\n") + "\n");
+        let mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000 + (i as u64 * 7919) % 13 * 3600);
+        File::options().write(true).open(fixture.path(name)).unwrap().set_modified(mtime).unwrap();
+    }
+    fixture
+}
+
+fn search(fixture: &Fixture, args: Value, max_results: usize) -> Value {
+    let output = fixture.search_with_env(args, &[("MAX_RESULTS", &max_results.to_string())]);
+    assert_eq!(output["status"], "success", "{}", output);
+    output
+}
+
+#[test]
+fn pruned_results_match_full_sort() {
+    let fixture = fixture();
+    for sort in ["path", "matches", "mtime"] {
+        let args = json!({ "query": "needle", "sort": sort });
+        let full = locations(&search(&fixture, args.clone(), 1000));
+        assert_eq!(full.len(), 24, "{:?}", full);
+        // 1、2、5 条时缓冲都会多次超过 2 * MAX_RESULTS 而被裁剪
+        for max_results in [1, 2, 5, 23] {
+            let output = search(&fixture, args.clone(), max_results);
+            assert_eq!(locations(&output), full[..max_results], "sort={} MAX_RESULTS={}", sort, max_results);
+            assert_eq!(output["total_matches"], 24);
+            assert_eq!(output["truncated"], true);
+        }
+    }
+}
+
+/// 按页取结果：每一页都应是完整结果中对应的一段，total_count 是限额后的总数
+fn assert_pages_match(fixture: &Fixture, args: Value, threads: &[&str]) {
+    let run = |args: Value, threads: &str| {
+        let output = fixture.search_with_env(args, &[("MAX_RESULTS", "1000"), ("CODESEARCHER_THREADS", threads)]);
+        assert_eq!(output["status"], "success", "{}", output);
+        output
+    };
+    let full = locations(&run(args.clone(), "1"));
+    assert!(full.len() > 4, "{:?}", full);
+    for (offset, limit) in [(0, 1), (1, 2), (3, 3), (full.len() - 1, 3)] {
+        let mut page_args = args.clone();
+        page_args["offset"] = json!(offset);
+        page_args["limit"] = json!(limit);
+        for threads in threads {
+            let page = run(page_args.clone(), threads);
+            let end = (offset + limit).min(full.len());
+            assert_eq!(locations(&page), full[offset..end], "{} threads={}", page_args, threads);
+            assert_eq!(page["total_count"], full.len(), "{}", page_args);
+        }
+    }
+}
+
+#[test]
+fn pruned_pages_respect_extension_cap() {
+    let fixture = fixture();
+    for sort in ["path", "matches", "mtime"] {
+        assert_pages_match(&fixture, json!({ "query": "needle", "sort": sort, "max_per_extension": 3 }), &["1", "4"]);
+    }
+}
+
+#[test]
+fn unsorted_extension_cap_is_pruned_consistently() {
+    // 单线程时 sort=none 的到达顺序是确定的目录顺序
+    let fixture = fixture();
+    assert_pages_match(&fixture, json!({ "query": "needle", "sort": "none", "max_per_extension": 2 }), &["1"]);
+}
+
+#[test]
+fn matches_and_mtime_order() {
+    let fixture = fixture();
+    let files = |output: &Value| {
+        let mut files: Vec<String> = locations(output).into_iter().map(|(file, _)| file).collect();
+        files.dedup();
+        files
+    };
+    let output = search(&fixture, json!({ "query": "needle", "sort": "matches" }), 1000);
+    assert_eq!(files(&output), ["b.rs", "d.rs", "f.md", "e.txt", "h.rs", "c.txt", "a.rs", "g.md"]);
+
+    let output = search(&fixture, json!({ "query": "needle", "sort": "mtime" }), 1000);
+    let mut expected: Vec<(SystemTime, String)> = files(&output)
+        .into_iter()
+        .map(|file| (std::fs::metadata(fixture.path(&file)).unwrap().modified().unwrap(), file))
+        .collect();
+    expected.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    assert_eq!(files(&output), expected.into_iter().map(|(_, file)| file).collect::<Vec<_>>());
+}
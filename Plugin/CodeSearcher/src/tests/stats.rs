@@ -0,0 +1,110 @@
+mod common;
+
+use common::Fixture;
+use serde_json::{json, Value};
+
+/// a.rs 2 处匹配，b.rs 和 big.rs 各 1 处，c.rs 没有匹配；d.py 扩展名不在白名单中，bin.rs 不是 UTF-8
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    fixture.write("a.rs", "needle\nx needle\n");
+    fixture.write("src/b.rs", "needle\n");
+    fixture.write("src/c.rs", "nothing\n");
+    fixture.write("big.rs", format!("needle {}\n", "0".repeat(2000)));
+    fixture.write("d.py", "needle\n");
+    fixture.write("bin.rs", b"needle\xff\n");
+    fixture
+}
+
+fn counts(output: &Value) -> [u64; 4] {
+    assert_eq!(output["status"], "success", "{}", output);
+    assert!(output["elapsed_ms"].is_u64(), "{}", output);
+    ["total_matches", "files_scanned", "files_matched", "files_skipped"]
+        .map(|key| output[key].as_u64().unwrap_or_else(|| panic!("missing {}: {}", key, output)))
+}
+
+#[test]
+fn counts_match_the_fixture() {
+    let fixture = fixture();
+    for threads in ["1", "4"] {
+        let output = fixture.search(json!({ "query": "needle", "threads": threads }));
+        assert_eq!(counts(&output), [4, 4, 3, 2], "threads={}", threads);
+        assert!(output["truncated"].is_null(), "{}", output);
+    }
+}
+
+#[test]
+fn counting_continues_past_truncation() {
+    let fixture = fixture();
+    for threads in ["1", "4"] {
+        let output = fixture.search(json!({ "query": "needle", "limit": 1, "threads": threads }));
+        assert_eq!(output["result"].as_array().unwrap().len(), 1);
+        assert_eq!(output["truncated"], true);
+        assert_eq!(counts(&output), [4, 4, 3, 2], "threads={}", threads);
+
+        let output = fixture.search_with_env(json!({ "query": "needle", "threads": threads }), &[("MAX_RESULTS", "2")]);
+        assert_eq!(output["result"].as_array().unwrap().len(), 2);
+        assert_eq!(counts(&output), [4, 4, 3, 2], "threads={}", threads);
+    }
+}
+
+#[test]
+fn skipped_files_are_broken_down_by_reason() {
+    let fixture = fixture();
+    let output = fixture.search(json!({ "query": "needle", "explain": true }));
+    assert_eq!(output["exclusions"]["extension"]["examples"], json!(["d.py"]));
+    assert_eq!(output["exclusions"]["binary"]["examples"], json!(["bin.rs"]));
+
+    let output = fixture.search(json!({ "query": "needle", "max_file_size": 100, "explain": true }));
+    assert_eq!(counts(&output), [3, 3, 2, 3]);
+    assert_eq!(output["exclusions"]["size_limit"]["examples"], json!(["big.rs"]));
+}
+
+#[test]
+fn search_path_limits_the_counted_files() {
+    let fixture = fixture();
+    let output = fixture.search(json!({ "query": "needle", "search_path": "src" }));
+    assert_eq!(counts(&output), [1, 2, 1, 0]);
+}
+
+#[test]
+fn no_matches_still_reports_counts() {
+    let fixture = fixture();
+    let output = fixture.search(json!({ "query": "absent" }));
+    assert_eq!(counts(&output), [0, 4, 0, 2]);
+}
+
+#[test]
+fn slow_files_follow_the_threshold() {
+    let fixture = fixture();
+    let args = json!({ "query": "needle", "search_path": "src" });
+    let output = fixture.search_with_env(args.clone(), &[("CODESEARCHER_SLOW_FILE_THRESHOLD_MS", "0")]);
+    let mut slow: Vec<(String, u64)> = output["slow_files"]
+        .as_array()
+        .unwrap_or_else(|| panic!("no slow_files: {}", output))
+        .iter()
+        .map(|file| {
+            assert!(file["duration_ms"].is_u64(), "{}", file);
+            (file["path"].as_str().unwrap().replace('\\', "/"), file["match_count"].as_u64().unwrap())
+        })
+        .collect();
+    slow.sort();
+    assert_eq!(slow, [("src/b.rs".to_string(), 1), ("src/c.rs".to_string(), 0)]);
+
+    let output = fixture.search_with_env(args, &[("CODESEARCHER_SLOW_FILE_THRESHOLD_MS", "60000")]);
+    assert!(output.get("slow_files").is_none(), "{}", output);
+}
+
+#[test]
+fn print_stats_writes_one_line_to_stderr() {
+    let fixture = fixture();
+    let (stdout, stderr) = fixture.run_with_stderr(json!({ "query": "needle", "threads": "1", "print_stats": "true" }), &[]);
+    let output: Value = serde_json::from_str(&stdout).unwrap_or_else(|e| panic!("invalid JSON output ({}): {}", e, stdout));
+    assert_eq!(counts(&output), [4, 4, 3, 2]);
+    let lines: Vec<&str> = stderr.lines().collect();
+    assert_eq!(lines.len(), 1, "{}", stderr);
+    assert!(lines[0].starts_with("[CodeSearcher] files_walked="), "{}", stderr);
+    assert!(lines[0].contains(" files_searched=4 matches=4 "), "{}", stderr);
+
+    let (_, stderr) = fixture.run_with_stderr(json!({ "query": "needle" }), &[]);
+    assert_eq!(stderr, "");
+}
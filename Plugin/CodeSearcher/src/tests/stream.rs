@@ -0,0 +1,47 @@
+mod common;
+
+use common::{locations, Fixture};
+use serde_json::{json, Value};
+
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .write("a.rs", "fn needle() {}\nfn other() {}\nneedle();\n")
+        .write("b.rs", "// needle\n")
+        .write("c.rs", "nothing here\n");
+    fixture
+}
+
+/// 按行解析 NDJSON 输出
+fn lines(stdout: &str) -> Vec<Value> {
+    stdout
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap_or_else(|e| panic!("invalid NDJSON line ({}): {}", e, line)))
+        .collect()
+}
+
+#[test]
+fn stream_writes_match_lines_then_one_summary() {
+    let fixture = fixture();
+    let lines = lines(&fixture.run(json!({ "query": "needle", "stream": true, "context_lines": 0 }), &[]));
+    let (summary, matches) = lines.split_last().expect("at least the summary line");
+
+    assert!(matches.iter().all(|line| line["type"] == "match"), "{:?}", matches);
+    let mut streamed = locations(&json!({ "result": matches }));
+    streamed.sort();
+    assert_eq!(streamed, [("a.rs".to_string(), 1), ("a.rs".to_string(), 3), ("b.rs".to_string(), 1)]);
+
+    assert_eq!(summary["type"], "summary", "{}", summary);
+    assert_eq!(summary["total_matches"], 3, "{}", summary);
+    assert_eq!(summary["files_matched"], 2, "{}", summary);
+    assert_eq!(summary["truncated"], false, "{}", summary);
+}
+
+#[test]
+fn stream_without_matches_writes_only_the_summary() {
+    let fixture = fixture();
+    let lines = lines(&fixture.run(json!({ "query": "absent", "stream": "true" }), &[]));
+    assert_eq!(lines.len(), 1, "{:?}", lines);
+    assert_eq!(lines[0]["type"], "summary");
+    assert_eq!(lines[0]["total_matches"], 0);
+}
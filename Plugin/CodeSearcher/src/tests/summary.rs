@@ -0,0 +1,35 @@
+mod common;
+
+use common::Fixture;
+use serde_json::json;
+
+#[test]
+fn summary_only_lists_matching_files() {
+    let fixture = Fixture::new();
+    fixture
+        .write("src/b.rs", "needle\nneedle\n")
+        .write("src/a.rs", "needle\n")
+        .write("docs/c.md", "hay\n")
+        .write("z.txt", "needle needle\n");
+
+    for threads in ["1", "4"] {
+        let output = fixture.search_with_env(json!({ "query": "needle", "summary_only": true }), &[("CODESEARCHER_THREADS", threads)]);
+        assert_eq!(output["status"], "success");
+        assert!(output["result"].is_null(), "{}", output);
+        assert_eq!(
+            output["summary"],
+            json!({ "total_matches": 4, "matching_files": ["src/a.rs", "src/b.rs", "z.txt"], "truncated": false })
+        );
+    }
+}
+
+#[test]
+fn summary_only_ignores_max_results() {
+    let fixture = Fixture::new();
+    for i in 0..5 {
+        fixture.write(&format!("f{}.rs", i), "needle\n");
+    }
+    let output = fixture.search_with_env(json!({ "query": "needle", "summary_only": "true" }), &[("MAX_RESULTS", "2")]);
+    assert_eq!(output["summary"]["total_matches"], 5);
+    assert_eq!(output["summary"]["matching_files"].as_array().unwrap().len(), 5);
+}
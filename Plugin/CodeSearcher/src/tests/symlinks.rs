@@ -0,0 +1,102 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::{locations, Fixture};
+use serde_json::{json, Value};
+use std::os::unix::fs::symlink;
+
+fn warning_kinds(output: &Value) -> Vec<String> {
+    output["warnings"]
+        .as_array()
+        .map(|warnings| warnings.iter().map(|w| w["kind"].as_str().unwrap().to_string()).collect())
+        .unwrap_or_default()
+}
+
+/// pkg/self 指回 pkg 形成目录环，linked 是 pkg 的另一个入口，external 指向项目之外
+fn symlink_fixture(outside: &Fixture) -> Fixture {
+    let fixture = Fixture::new();
+    fixture.write("pkg/lib.rs", "fn needle() {}\n");
+    outside.write("secret.rs", "fn needle() {}\n");
+    symlink(fixture.path("pkg"), fixture.path("pkg/self")).unwrap();
+    symlink(fixture.path("pkg"), fixture.path("linked")).unwrap();
+    symlink(&outside.root, fixture.path("external")).unwrap();
+    fixture
+}
+
+#[test]
+fn symlinks_are_not_followed_by_default() {
+    let outside = Fixture::new();
+    let fixture = symlink_fixture(&outside);
+    let output = fixture.search(json!({ "query": "needle" }));
+    assert_eq!(output["status"], "success", "{}", output);
+    assert_eq!(locations(&output), vec![("pkg/lib.rs".to_string(), 1)]);
+}
+
+#[test]
+fn following_symlinks_survives_cycles_and_skips_duplicates() {
+    let outside = Fixture::new();
+    let fixture = symlink_fixture(&outside);
+    let output = fixture.search(json!({ "query": "needle", "follow_symlinks": "true" }));
+    assert_eq!(output["status"], "success", "{}", output);
+    let results = locations(&output);
+    assert_eq!(results.len(), 1, "{:?}", results);
+    assert!(results[0].0.ends_with("lib.rs"));
+    assert!(output["duplicates_skipped"].as_u64().unwrap() >= 1, "{}", output);
+    assert!(warning_kinds(&output).contains(&"outside_project".to_string()), "{}", output);
+}
+
+#[test]
+fn file_symlink_to_outside_is_skipped() {
+    let outside = Fixture::new();
+    let fixture = Fixture::new();
+    fixture.write("a.rs", "fn needle() {}\n");
+    outside.write("secret.rs", "fn needle() {}\n");
+    symlink(outside.path("secret.rs"), fixture.path("b.rs")).unwrap();
+
+    let output = fixture.search(json!({ "query": "needle", "follow_symlinks": "true" }));
+    assert_eq!(locations(&output), vec![("a.rs".to_string(), 1)]);
+    let warnings = output["warnings"].as_array().unwrap();
+    assert!(warnings.iter().any(|w| w["kind"] == "outside_project" && w["path"] == "b.rs"), "{}", output);
+}
+
+#[test]
+fn files_reached_twice_fill_pages_and_counts_once() {
+    let fixture = Fixture::new();
+    for name in ["a", "b", "c", "d"] {
+        fixture.write(&format!("src/{}.rs", name), "fn needle() {}\n");
+    }
+    symlink(fixture.path("src"), fixture.path("alias")).unwrap();
+
+    for args in [
+        json!({ "query": "needle", "follow_symlinks": true, "limit": 3 }),
+        json!({ "query": "needle", "follow_symlinks": true, "limit": 3, "sort": "none" }),
+        json!({ "query": "needle", "follow_symlinks": true, "limit": 3, "threads": 4 }),
+    ] {
+        let output = fixture.search(args.clone());
+        assert_eq!(locations(&output).len(), 3, "{}: {}", args, output);
+        assert_eq!(output["total_matches"], 4, "{}", output);
+        assert_eq!(output["files_matched"], 4, "{}", output);
+        assert!(output.get("duplicate_results").is_none(), "{}", output);
+    }
+
+    let output = fixture.search(json!({ "query": "needle", "follow_symlinks": true, "summary_only": true }));
+    assert_eq!(output["total_matches"], 4, "{}", output);
+}
+
+#[test]
+fn absolute_paths_resolve_symlinked_project_base() {
+    let fixture = Fixture::new();
+    let links = Fixture::new();
+    fixture.write("src/a.rs", "fn needle() {}\n");
+    let linked_base = links.path("project");
+    symlink(&fixture.root, &linked_base).unwrap();
+
+    let output = fixture.search(json!({
+        "query": "needle",
+        "absolute_paths": true,
+        "project_base": linked_base.to_string_lossy(),
+    }));
+    let expected = fixture.root.canonicalize().unwrap().join("src/a.rs");
+    assert_eq!(locations(&output), vec![(expected.to_string_lossy().into_owned(), 1)]);
+}
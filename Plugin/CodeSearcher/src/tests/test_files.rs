@@ -0,0 +1,71 @@
+mod common;
+
+use common::{locations, Fixture};
+use serde_json::{json, Value};
+
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    for file in [
+        "src/lib.rs",
+        "src/lib_test.rs",
+        "src/latest.rs",
+        "web/app.ts",
+        "web/app.test.ts",
+        "web/app.spec.ts",
+        "web/util.js",
+        "web/util.test.js",
+        "web/util.spec.js",
+        "web/contest.js",
+    ] {
+        fixture.write(file, "needle\n");
+    }
+    fixture
+}
+
+fn files(output: &Value) -> Vec<String> {
+    let mut files: Vec<String> = locations(output).into_iter().map(|(file, _)| file).collect();
+    files.sort();
+    files
+}
+
+const PRODUCTION: [&str; 5] = ["src/latest.rs", "src/lib.rs", "web/app.ts", "web/contest.js", "web/util.js"];
+
+#[test]
+fn env_enables_test_file_filter() {
+    let fixture = fixture();
+    let output = fixture.search(json!({ "query": "needle" }));
+    assert_eq!(files(&output).len(), 10);
+
+    for value in ["true", "1", "TRUE"] {
+        let output = fixture.search_with_env(json!({ "query": "needle" }), &[("IGNORE_TEST_FILES", value)]);
+        assert_eq!(files(&output), PRODUCTION, "IGNORE_TEST_FILES={}", value);
+    }
+}
+
+#[test]
+fn request_overrides_env() {
+    let fixture = fixture();
+    let output = fixture.search(json!({ "query": "needle", "ignore_test_files": "true" }));
+    assert_eq!(files(&output), PRODUCTION);
+
+    let output = fixture.search_with_env(json!({ "query": "needle", "ignore_test_files": "false" }), &[("IGNORE_TEST_FILES", "true")]);
+    assert_eq!(files(&output).len(), 10);
+}
+
+#[test]
+fn explicit_files_are_not_filtered() {
+    let fixture = fixture();
+    let output = fixture.search(json!({
+        "query": "needle",
+        "ignore_test_files": "true",
+        "files": ["src/lib_test.rs", "web/app.spec.ts", "src/lib.rs"],
+    }));
+    assert_eq!(files(&output), ["src/lib.rs", "src/lib_test.rs", "web/app.spec.ts"]);
+}
+
+#[test]
+fn explain_reports_test_files() {
+    let fixture = fixture();
+    let output = fixture.search(json!({ "query": "needle", "ignore_test_files": true, "explain": true }));
+    assert_eq!(output["exclusions"]["test_file"]["count"], 5, "{}", output);
+}
@@ -0,0 +1,89 @@
+mod common;
+
+use common::Fixture;
+use serde_json::json;
+
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        // 2 和 4 的上下文重叠，合并成一块；9 的上下文与前一块不相连
+        .write("a.rs", "one\nneedle a\nthree\nneedle b\nfive\nsix\nseven\neight\nneedle c\nten\n")
+        .write("b.rs", "    needle d\nlast\n")
+        // 2 的上下文到第 3 行，5 的上下文从第 4 行开始，两块相邻，不加分隔符
+        .write("c.rs", "x\nneedle e\ny\nz\nneedle f\nw\n");
+    fixture
+}
+
+/// 去掉汇总行中每次运行都不同的 elapsed
+fn render(fixture: &Fixture, args: serde_json::Value) -> String {
+    let stdout = fixture.run(args, &[]);
+    let (body, summary) = stdout.trim_end().rsplit_once('\n').expect("summary line");
+    let summary: Vec<&str> = summary.split(' ').filter(|field| !field.starts_with("elapsed=")).collect();
+    format!("{}\n{}\n", body, summary.join(" "))
+}
+
+#[test]
+fn context_merging_and_separators() {
+    let output = render(
+        &fixture(),
+        json!({ "query": "needle", "format": "text", "context_lines": 1, "sort": "path" }),
+    );
+    assert_eq!(
+        output,
+        "\
+a.rs-1-one
+a.rs:2:1: needle a
+a.rs-3-three
+a.rs:4:1: needle b
+a.rs-5-five
+--
+a.rs-8-eight
+a.rs:9:1: needle c
+a.rs-10-ten
+--
+b.rs:1:5:     needle d
+b.rs-2-last
+--
+c.rs-1-x
+c.rs:2:1: needle e
+c.rs-3-y
+c.rs-4-z
+c.rs:5:1: needle f
+c.rs-6-w
+status=success matches=6 files=3 scanned=3
+"
+    );
+}
+
+#[test]
+fn custom_and_empty_separator() {
+    let fixture = fixture();
+    let args = json!({ "query": "needle", "format": "text", "context_lines": 1, "search_path": "a.rs" });
+    let body = "\
+a.rs-1-one
+a.rs:2:1: needle a
+a.rs-3-three
+a.rs:4:1: needle b
+a.rs-5-five
+{separator}a.rs-8-eight
+a.rs:9:1: needle c
+a.rs-10-ten
+status=success matches=3 files=1 scanned=1
+";
+    let mut custom = args.clone();
+    custom["context_separator"] = json!("==");
+    assert_eq!(render(&fixture, custom), body.replace("{separator}", "==\n"));
+
+    let mut empty = args;
+    empty["context_separator"] = json!("");
+    assert_eq!(render(&fixture, empty), body.replace("{separator}", ""));
+}
+
+#[test]
+fn no_separator_without_context() {
+    let args = json!({ "query": "needle", "format": "text", "context_lines": 0, "search_path": "c.rs" });
+    assert_eq!(
+        render(&fixture(), args),
+        "c.rs:2:1: needle e\nc.rs:5:1: needle f\nstatus=success matches=2 files=1 scanned=1\n"
+    );
+}
@@ -0,0 +1,55 @@
+mod common;
+
+use common::{strip_volatile, Fixture};
+use serde_json::{json, Value};
+
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    for dir in 0..6 {
+        for file in 0..8 {
+            let lines: Vec<String> = (0..20)
+                .map(|line| if (line + file) % 4 == 0 { format!("needle {} {}", dir, line) } else { format!("hay {}", line) })
+                .collect();
+            fixture.write(&format!("d{}/sub{}/f{}.rs", dir, file % 2, file), lines.join("\n") + "\n");
+        }
+    }
+    fixture
+}
+
+/// 以单线程和多线程各运行一次，输出去掉耗时等字段后应完全一致
+fn assert_thread_independent(fixture: &Fixture, args: Value) {
+    let run = |threads: usize| {
+        let mut args = args.clone();
+        args["threads"] = json!(threads);
+        strip_volatile(fixture.search(args))
+    };
+    let single = run(1);
+    assert_eq!(single["status"], "success", "{}", single);
+    for threads in [2, 4, 8] {
+        assert_eq!(single, run(threads), "threads={} differs for {}", threads, args);
+    }
+}
+
+#[test]
+fn sorted_modes() {
+    let fixture = fixture();
+    for sort in ["path", "matches"] {
+        assert_thread_independent(&fixture, json!({ "query": "needle", "sort": sort }));
+    }
+}
+
+#[test]
+fn truncated_and_paginated() {
+    let fixture = fixture();
+    assert_thread_independent(&fixture, json!({ "query": "needle", "context_lines": 1 }));
+    assert_thread_independent(&fixture, json!({ "query": "needle", "offset": 7, "limit": 5 }));
+    assert_thread_independent(&fixture, json!({ "query": "needle", "max_per_extension": 3 }));
+}
+
+#[test]
+fn grouped_and_summary() {
+    let fixture = fixture();
+    assert_thread_independent(&fixture, json!({ "query": "needle", "group_by": "directory" }));
+    assert_thread_independent(&fixture, json!({ "query": "needle", "summary_only": true }));
+    assert_thread_independent(&fixture, json!({ "query": "needle", "output_mode": "dirs" }));
+}
@@ -0,0 +1,49 @@
+mod common;
+
+use common::{locations, Fixture};
+use serde_json::{json, Value};
+use std::process::Command;
+
+fn matched_files(output: &Value) -> Vec<String> {
+    assert_eq!(output["status"], "success", "{}", output);
+    let mut files: Vec<String> = locations(output).into_iter().map(|(file, _)| file).collect();
+    files.dedup();
+    files
+}
+
+fn git(fixture: &Fixture, args: &[&str]) {
+    let status = Command::new("git").args(args).current_dir(&fixture.root).status().expect("run git");
+    assert!(status.success(), "git {:?} failed", args);
+}
+
+/// sub/a.rs 和 top.rs 已加入索引，sub/b.rs 未跟踪
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    fixture.write("sub/a.rs", "needle\n").write("sub/b.rs", "needle\n").write("top.rs", "needle\n");
+    fixture
+}
+
+#[test]
+fn tracked_only_in_a_subdirectory_reports_paths_from_the_root() {
+    let fixture = fixture();
+    git(&fixture, &["init", "-q"]);
+    git(&fixture, &["add", "sub/a.rs", "top.rs"]);
+
+    let output = fixture.search(json!({ "query": "needle", "tracked_only": true }));
+    assert_eq!(matched_files(&output), ["sub/a.rs", "top.rs"]);
+    assert!(output.get("warnings").is_none(), "{}", output);
+
+    let output = fixture.search(json!({ "query": "needle", "tracked_only": true, "search_path": "sub" }));
+    assert_eq!(matched_files(&output), ["sub/a.rs"]);
+}
+
+#[test]
+fn tracked_only_outside_a_repository_warns_and_walks() {
+    let fixture = fixture();
+    let output = fixture.search(json!({ "query": "needle", "tracked_only": true }));
+    assert_eq!(matched_files(&output), ["sub/a.rs", "sub/b.rs", "top.rs"]);
+    let warnings = output["warnings"].as_array().unwrap_or_else(|| panic!("no warnings: {}", output));
+    assert_eq!(warnings.len(), 1, "{}", output);
+    assert_eq!(warnings[0]["kind"], "git_unavailable");
+    assert!(warnings[0]["message"].as_str().unwrap().contains("falling back"), "{}", output);
+}
@@ -0,0 +1,157 @@
+mod common;
+
+use common::{locations, Fixture};
+use serde_json::{json, Value};
+
+fn warnings(output: &Value) -> Vec<(String, String)> {
+    output["warnings"]
+        .as_array()
+        .map(|warnings| {
+            warnings
+                .iter()
+                .map(|w| {
+                    assert!(w["message"].as_str().is_some_and(|m| !m.is_empty()), "{}", w);
+                    (w["path"].as_str().unwrap().replace('\\', "/"), w["kind"].as_str().unwrap().to_string())
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[test]
+fn clean_search_has_no_warnings() {
+    let fixture = Fixture::new();
+    fixture.write("a.rs", "fn needle() {}\n");
+    let output = fixture.search(json!({ "query": "needle" }));
+    assert_eq!(output["status"], "success", "{}", output);
+    assert!(output.get("warnings").is_none(), "{}", output);
+    assert!(output.get("warnings_dropped").is_none(), "{}", output);
+}
+
+#[test]
+fn decode_errors_are_reported_without_failing() {
+    let fixture = Fixture::new();
+    fixture.write("a.rs", "fn needle() {}\n");
+    fixture.write("latin1.rs", b"// caf\xe9 needle\n");
+
+    let output = fixture.search(json!({ "query": "needle" }));
+    assert_eq!(output["status"], "success", "{}", output);
+    assert_eq!(locations(&output), vec![("a.rs".to_string(), 1)]);
+    assert_eq!(warnings(&output), vec![("latin1.rs".to_string(), "decode_error".to_string())]);
+    assert_eq!(output["files_skipped"], 1, "{}", output);
+}
+
+#[test]
+fn missing_explicit_files_are_reported() {
+    let fixture = Fixture::new();
+    fixture.write("a.rs", "fn needle() {}\n");
+    fixture.write("dir/b.rs", "fn needle() {}\n");
+
+    let output = fixture.search(json!({ "query": "needle", "files": ["a.rs", "gone.rs", "dir"] }));
+    assert_eq!(output["status"], "success", "{}", output);
+    assert_eq!(locations(&output), vec![("a.rs".to_string(), 1)]);
+    assert_eq!(
+        warnings(&output),
+        vec![("gone.rs".to_string(), "not_found".to_string()), ("dir".to_string(), "not_a_file".to_string())]
+    );
+}
+
+#[test]
+fn warnings_are_capped_with_an_overflow_count() {
+    let fixture = Fixture::new();
+    for i in 0..130 {
+        fixture.write(&format!("bad{:03}.rs", i), b"\xff\xfe needle\n");
+    }
+
+    for threads in ["1", "4"] {
+        let output = fixture.search(json!({ "query": "needle", "threads": threads }));
+        assert_eq!(output["status"], "success", "{}", output);
+        assert_eq!(warnings(&output).len(), 100);
+        assert_eq!(output["warnings_dropped"], 30, "{}", output);
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn unreadable_files_and_directories_are_reported() {
+    use std::fs::{self, Permissions};
+    use std::os::unix::fs::PermissionsExt;
+
+    let fixture = Fixture::new();
+    fixture.write("a.rs", "fn needle() {}\n");
+    fixture.write("locked.rs", "fn needle() {}\n");
+    fixture.write("sealed/c.rs", "fn needle() {}\n");
+    fs::set_permissions(fixture.path("locked.rs"), Permissions::from_mode(0o000)).unwrap();
+    fs::set_permissions(fixture.path("sealed"), Permissions::from_mode(0o000)).unwrap();
+
+    // root 不受文件权限限制，此时无法构造读取失败
+    if fs::read(fixture.path("locked.rs")).is_err() {
+        let output = fixture.search(json!({ "query": "needle" }));
+        assert_eq!(output["status"], "success", "{}", output);
+        assert_eq!(locations(&output), vec![("a.rs".to_string(), 1)]);
+        let warnings = warnings(&output);
+        assert!(warnings.contains(&("locked.rs".to_string(), "permission_denied".to_string())), "{:?}", warnings);
+        assert!(warnings.contains(&("sealed".to_string(), "permission_denied".to_string())), "{:?}", warnings);
+    }
+
+    // 恢复权限，让 Fixture 能删除目录
+    fs::set_permissions(fixture.path("sealed"), Permissions::from_mode(0o755)).unwrap();
+}
+
+fn query_warnings(output: &Value) -> Vec<String> {
+    assert_eq!(output["status"], "success", "{}", output);
+    output["query_warnings"]
+        .as_array()
+        .map(|warnings| warnings.iter().map(|w| w.as_str().unwrap().to_string()).collect())
+        .unwrap_or_default()
+}
+
+fn broad_fixture() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .write("a.rs", "fn needle() {}\nneedle();\n")
+        .write("b.rs", "needle\n")
+        .write("c.rs", "needle\n")
+        .write("d.rs", "other\n");
+    fixture
+}
+
+#[test]
+fn single_character_query_is_warned_about() {
+    let output = broad_fixture().search(json!({ "query": "n" }));
+    let warnings = query_warnings(&output);
+    assert_eq!(warnings.len(), 1, "{}", output);
+    assert!(warnings[0].contains("\"n\" is shorter than 2 characters"), "{}", output);
+
+    let output = broad_fixture().search(json!({ "query": "needle" }));
+    assert!(output.get("query_warnings").is_none(), "{}", output);
+}
+
+#[test]
+fn wildcard_regex_is_warned_about() {
+    let output = broad_fixture().search(json!({ "query": ".*", "use_regex": true }));
+    let warnings = query_warnings(&output);
+    assert_eq!(warnings.len(), 1, "{}", output);
+    assert!(warnings[0].contains("\".*\" matches every line"), "{}", output);
+
+    // 不是正则时 .* 只是字面量
+    let output = broad_fixture().search(json!({ "query": ".*" }));
+    assert!(output.get("query_warnings").is_none(), "{}", output);
+}
+
+#[test]
+fn truncated_search_matching_most_files_is_warned_about() {
+    let fixture = broad_fixture();
+    let output = fixture.search_with_env(json!({ "query": "needle" }), &[("MAX_RESULTS", "2")]);
+    assert_eq!(output["truncated_reason"], "max_results", "{}", output);
+    let warnings = query_warnings(&output);
+    assert_eq!(warnings.len(), 1, "{}", output);
+    assert!(warnings[0].starts_with("3 of 4 searched files matched"), "{}", output);
+
+    // 截断但只有少数文件匹配，或匹配多数文件但没有截断，都不警告
+    let output = fixture.search_with_env(json!({ "query": "()" }), &[("MAX_RESULTS", "1")]);
+    assert_eq!(output["truncated_reason"], "max_results", "{}", output);
+    assert!(output.get("query_warnings").is_none(), "{}", output);
+    let output = fixture.search(json!({ "query": "needle" }));
+    assert!(output.get("query_warnings").is_none(), "{}", output);
+}
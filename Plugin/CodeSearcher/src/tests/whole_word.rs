@@ -0,0 +1,70 @@
+mod common;
+
+use common::{locations, Fixture};
+use serde_json::{json, Value};
+
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    fixture.write(
+        "a.txt",
+        "let foo_bar = 1;\ncall(foo_barx);\nx = foo-bar;\ny = foo-barz;\n数据库 数据\ncafé latte\nxcafé\ncafébar\n",
+    );
+    fixture
+}
+
+fn lines(output: &Value) -> Vec<u64> {
+    locations(output).into_iter().map(|(_, line)| line).collect()
+}
+
+fn identifier(fixture: &Fixture, query: &str) -> Value {
+    fixture.search(json!({ "query": query, "whole_word": true, "word_mode": "identifier" }))
+}
+
+#[test]
+fn identifier_underscore() {
+    let fixture = fixture();
+    let output = identifier(&fixture, "foo_bar");
+    assert_eq!(lines(&output), vec![1]);
+    assert_eq!(output["result"][0]["match_column"], 4);
+
+    // - 是分隔符，_ 不是
+    assert_eq!(lines(&identifier(&fixture, "foo")), vec![3, 4]);
+}
+
+#[test]
+fn identifier_hyphen() {
+    let fixture = fixture();
+    let output = identifier(&fixture, "foo-bar");
+    assert_eq!(lines(&output), vec![3]);
+    assert_eq!(output["result"][0]["match_column"], 4);
+}
+
+#[test]
+fn identifier_non_ascii_edges() {
+    let fixture = fixture();
+    // 首尾都不是 ASCII 词字符，两侧都不加边界
+    let output = identifier(&fixture, "数据");
+    assert_eq!(lines(&output), vec![5]);
+    assert_eq!(output["result"][0]["match_column"], 0);
+
+    // 只有开头要求边界：xcafé 不匹配，é 之后是什么都可以
+    assert_eq!(lines(&identifier(&fixture, "café")), vec![6, 8]);
+}
+
+#[test]
+fn unicode_mode() {
+    let fixture = fixture();
+    let search = |query: &str| fixture.search(json!({ "query": query, "whole_word": true }));
+    assert_eq!(lines(&search("foo_bar")), vec![1]);
+    assert_eq!(lines(&search("foo-bar")), vec![3]);
+    assert_eq!(lines(&search("数据")), vec![5]);
+    // é 是 Unicode 词字符，cafébar 中的 café 不是整词
+    assert_eq!(lines(&search("café")), vec![6]);
+}
+
+#[test]
+fn none_mode() {
+    let fixture = fixture();
+    let output = fixture.search(json!({ "query": "foo_bar", "whole_word": true, "word_mode": "none" }));
+    assert_eq!(lines(&output), vec![1, 2]);
+}
@@ -18,27 +18,264 @@ export interface VexusStats {
   capacity: number
   memoryUsage: number
 }
+/** 索引的运行时配置 (构造参数 + 当前状态)，用于确认加载后的度量、量化方式等是否符合预期 */
+export interface IndexInfo {
+  dimensions: number
+  metric: string
+  quantization: string
+  connectivity: number
+  expansionAdd: number
+  expansionSearch: number
+  totalVectors: number
+  capacity: number
+  memoryUsage: number
+  hardwareAcceleration: string
+}
+/**
+ * 索引的构造参数，可以序列化为 JSON 保存到磁盘、写进日志或在进程之间传递。
+ * JSON 字段名与 JS 对象一致 (camelCase)；metric 缺省为 "l2sq"，quantization 缺省为 "f32"
+ */
+export interface VexusIndexConfig {
+  dimensions: number
+  capacity: number
+  metric: string
+  quantization: string
+  seed?: number
+}
+/** 重建统计 (切换度量等全量重建操作的返回值) */
+export interface RebuildStats {
+  vectorCount: number
+  elapsedMs: number
+}
+/** validate_and_add 的结果：added 为 false 时 reason 说明拒绝原因 */
+export interface ValidationResult {
+  added: boolean
+  reason?: string
+}
+/** 可取消恢复的结果：cancelled 为 true 时 recovered 只是取消前已写入索引的部分 */
+export interface RecoverStats {
+  recovered: number
+  skippedDimMismatch: number
+  cancelled: boolean
+}
+/** SQLite 恢复的连接选项 */
+export interface RecoverOptions {
+  /**
+   * 数据库被其他连接锁住时最多等待的毫秒数，超时后以 code 为 DB_BUSY 的错误失败。
+   * 缺省时使用 rusqlite 的默认值 (5 秒)
+   */
+  connectionTimeoutMs?: number
+  /**
+   * 数据库已经是 WAL 模式时，查询前执行 PRAGMA wal_checkpoint(PASSIVE)，
+   * 让其他进程已提交但还在 WAL 文件里的事务可见。不会改变数据库文件的日志模式，其他模式的数据库忽略此选项
+   */
+  wal?: boolean
+}
+/**
+ * 取消令牌：传给 recover_from_sqlite_async_with_cancellation，在 JS 侧调用 cancel() 请求中止。
+ * 标志位放在 Arc 里，后台任务持有同一份引用，JS 对象先被回收也不影响任务读取
+ */
+export declare class CancelToken {
+  constructor()
+  /** 请求取消，后台任务会在下一次检查时停止 (每 1000 行检查一次) */
+  cancel(): void
+  get isCancelled(): boolean
+}
+/** ID 重映射项 (reassign_ids 的参数) */
+export interface IdMapping {
+  oldId: number
+  newId: number
+}
 /** 核心索引结构 (无状态，只存向量) */
 export declare class VexusIndex {
-  /** 创建新的空索引 */
-  constructor(dim: number, capacity: number)
+  /**
+   * 创建新的空索引
+   * quantization 为 "f32" (默认) 或 "i8"。i8 每个分量只占 1 字节，内存约为 f32 的 1/4，
+   * 写入时逐向量缩放 (见 quantize_i8)，会损失精度和向量之间的量级差异。
+   * 量化类型和 i8 的缩放系数随 save 写入状态文件、随 toBuffer 写入 Buffer，
+   * load / loadAndVerify / loadFromBuffer 时恢复。
+   * seed 目前被忽略：usearch 的层级随机数生成器在每个线程上下文中以固定的默认种子创建，没有提供设置种子的接口。
+   * 因此逐条 add 按相同顺序写入时图结构本来就可复现，parallel_add_batch 中向量落到哪个线程上下文不确定，无法复现。
+   * 设置环境变量 VEXUS_WARN_NONDETERMINISTIC=1 时，传入 seed 会打印一条警告
+   */
+  constructor(dim: number, capacity: number, quantization?: string | undefined | null, seed?: number | undefined | null)
+  /** 按 JSON 形式的构造参数 (见 VexusIndexConfig) 创建新的空索引，JSON 无效时报错 */
+  static fromConfigJson(json: string): VexusIndex
+  /**
+   * 当前索引的构造参数。capacity 是当前已预留的容量 (扩容后可能大于创建时的值)，
+   * load 得到的索引 seed 为空
+   */
+  config(): VexusIndexConfig
   /**
    * 从磁盘加载索引
    * 注意：移除了 map_path，因为映射关系现在由 SQLite 管理
+   * 存活 ID 集合从 save 写出的 {index_path}.vexus 读取；没有该文件 (旧版本保存的索引) 或与索引对不上时，
+   * 用一次暴力搜索重建，大索引会明显变慢
    */
   static load(indexPath: string, unusedMapPath: string | undefined | null, dim: number, capacity: number): VexusIndex
-  /** 保存索引到磁盘 */
-  save(indexPath: string): void
-  /** 单个添加 (JS 循环调用) */
+  /**
+   * 从内存中的索引数据加载 (例如存放在数据库或对象存储里的 to_buffer / save 输出)，不经过临时文件。
+   * 数据会被复制进索引，调用返回后 buf 可以释放。to_buffer 的输出带有状态，与 load 一样恢复
+   * 存活 ID 集合、量化类型和缩放系数；只有 save 写出的索引文件 (或旧版本的 to_buffer 输出) 时，
+   * 存活 ID 集合用一次暴力搜索重建，量化类型取索引头中的存储类型，i8 向量的缩放系数按 1 处理
+   */
+  static loadFromBuffer(buf: Buffer, dim: number): VexusIndex
+  /**
+   * 只加载白名单中的向量 (例如单个用户的文档)
+   * 以内存映射方式打开完整索引文件，只读出白名单 ID 对应的向量写入新的空索引，
+   * 不在索引中的 ID 直接忽略；实际找到的数量即返回索引的 size()。
+   * 新索引沿用源文件索引头中的度量 (读不出时为 L2sq)，以及状态文件中的量化类型和白名单 ID 的缩放系数，
+   * 搜索分数与加载完整索引一致
+   */
+  static loadPartial(indexPath: string, idWhitelist: Array<number>, dim: number): VexusIndex
+  /**
+   * 从磁盘加载索引并校验完整性 (推荐的生产环境加载方式)
+   * 读取 meta 中保存的 SHA-256，与索引文件内容重新计算的结果比对，不一致则拒绝加载，
+   * 用于发现磁盘损坏、写入中断或文件被意外覆盖。校验和加载使用同一份字节，避免读两次文件
+   */
+  static loadAndVerify(indexPath: string, metaPath: string, dim: number): VexusIndex
+  /**
+   * 保存索引到磁盘
+   * 同时在旁边写出状态文件 {index_path}.vexus (存活 ID 集合、量化类型和 i8 缩放系数)，
+   * load 时据此跳过重建并还原 i8 向量的量级。
+   * 传入 meta_path 时额外写出元数据文件 (包含索引文件的 SHA-256)，供 load_and_verify 校验
+   */
+  save(indexPath: string, metaPath?: string | undefined | null): void
+  /**
+   * 把索引序列化到内存 Buffer，可交给 load_from_buffer 还原。内容为 save 写出的索引文件，
+   * 后面追加状态文件的内容 (存活 ID 集合、量化类型和 i8 缩放系数)。
+   * 序列化期间持有读锁，不会读到写了一半的状态；不触发 on_save
+   */
+  toBuffer(): Buffer
+  /** 单个添加 (JS 循环调用)，ID 为负数时报错 */
   add(id: number, vector: Buffer): void
-  /** 批量添加 (更高效，建议未来 JS 改用此接口) */
+  /**
+   * 添加一个带有效期的向量 (例如只在一次会话内有用的消息向量)，ttl_seconds 秒后过期。
+   * 过期的向量不会自动消失，由 prune_expired 或 set_prune_interval 启动的后台清理删除；
+   * 用普通 add 写入或被 remove 删除的 ID 没有过期时间
+   */
+  addWithExpiry(id: number, vector: Buffer, ttlSeconds: number): void
+  /** 删除所有已过期的向量 (会触发 on_remove)，返回删除的数量 */
+  pruneExpired(): number
+  /**
+   * 每隔 seconds 秒在后台线程中自动执行一次 prune_expired，0 表示停止。
+   * 重复调用会替换之前的间隔；索引被回收时后台线程随之退出
+   */
+  setPruneInterval(seconds: number): void
+  /** 批量添加 (更高效，建议未来 JS 改用此接口)，有任一 ID 为负数时整批不写入 */
   addBatch(ids: Array<number>, vectors: Buffer): void
+  /**
+   * 校验后添加：NaN、Inf、零向量、维度不符或 ID 为负数时不写入索引，而是在结果中说明原因
+   * (usearch 对 NaN 输入的行为未定义，可能污染整个图)
+   */
+  validateAndAdd(id: number, vector: Buffer): ValidationResult
+  /** validate_and_add 的批量版本：只加一次写锁，逐个校验，返回与 ids 一一对应的结果 */
+  validateAndAddBatch(ids: Array<number>, vectors: Buffer): Array<ValidationResult>
+  /**
+   * 多线程批量添加：usearch 的 add 在预留了足够线程槽位后可以并发调用，
+   * 这里用限定为 threads 个线程的 rayon 线程池并行插入 (threads 为 0 时使用 CPU 核数)。
+   * 单个向量插入失败只打印警告，返回成功插入的数量；有任一 ID 为负数时整批不写入
+   */
+  parallelAddBatch(ids: Array<number>, vectors: Buffer, threads: number): number
   /** 搜索 */
   search(query: Buffer, k: number): Array<SearchResult>
+  /**
+   * 带超时的搜索
+   * 在独立线程中执行 usearch 搜索并用 recv_timeout 等待，超时返回 code 为 SEARCH_TIMEOUT 的错误。
+   * 注意：usearch 的搜索线程无法被安全地中止，超时后它仍会在后台跑完 (结果被丢弃)
+   */
+  searchWithTimeout(query: Buffer, k: number, timeoutMs: number): Array<SearchResult>
+  /**
+   * 按 ID 取出向量 (f32 小端字节)，ID 不存在时返回 null
+   * i8 量化的索引按写入时记录的缩放系数还原成 f32，精度损失见 quantize_i8
+   */
+  getVector(id: number): Buffer | null
+  /**
+   * 计算全部向量的均值 (质心，f32 小端字节)，用于判断查询是否落在某个主题簇内或筛掉离题内容。
+   * 逐个读出存活 ID 的向量并用 f64 累加，O(n) 且要读出每个向量，开销较大，不宜在每次查询时调用；
+   * i8 量化的索引按还原后的向量计算。索引为空时报错
+   */
+  computeCentroid(): Buffer
+  /**
+   * 查看某个节点在 HNSW 图第 level 层的邻居，用于排查搜索结果异常。
+   * usearch 的 Rust 绑定没有暴露图结构，这里是近似实现：用该节点的向量搜索，
+   * 返回除它自己以外最近的 M 个 ID (M = connectivity，第 0 层为 2M，与 usearch 每层的邻居上限一致)。
+   * 实际的图邻居是构建时按启发式裁剪过的，不一定与这里的结果完全相同；ID 不存在时报错
+   */
+  inspectHnswNeighbors(id: number, level: number): Array<number>
+  /**
+   * 排除指定 ID 的搜索：先取 k + excluded_ids.len() 个结果，过滤后不足 k 个时把候选数翻倍重试，
+   * 直到凑够 k 个或候选数达到索引大小。返回至多 k 个结果
+   */
+  searchExcludingTags(query: Buffer, k: number, excludedIds: Array<number>): Array<SearchResult>
+  /**
+   * 多样性搜索 (MMR, Maximal Marginal Relevance)：先取 4k 个候选，
+   * 再逐个挑选使 λ * 与查询的相似度 - (1 - λ) * 与已选结果的最大相似度 最大的候选，λ = diversity_threshold。
+   * λ = 1 等价于普通搜索，越小结果越分散；相似度统一用余弦计算，返回的 score 仍是原始搜索分数
+   */
+  searchDiverse(query: Buffer, k: number, diversityThreshold: number): Array<SearchResult>
+  /**
+   * 多向量 AND 检索：每个查询向量各自取 top-k，只保留在所有结果里都出现的 ID，
+   * 按各查询分数之和重新排序后返回前 k 个。
+   * 交集为空且 fallback_to_union 为 true 时改为返回并集 (同样按分数之和排序)
+   */
+  intersectSearch(queries: Array<Buffer>, k: number, fallbackToUnion?: boolean | undefined | null): Array<SearchResult>
+  /**
+   * 召回率评估：queries 为首尾相接的 f32 向量 (与 add_batch 相同的打包方式)，ground_truth[i] 是第 i 个查询
+   * 由暴力搜索得到的真实近邻 ID (按距离从近到远)。对每个查询做一次 HNSW 搜索，
+   * recall@k = top-k 结果中出现在 ground_truth[i] 前 k 个里的比例，返回所有查询的平均值。
+   * ground_truth 为空的查询不参与平均
+   */
+  recallAtK(groundTruth: Array<Array<number>>, queries: Buffer, k: number): number
+  /**
+   * 在线切换距离度量 (l2sq / cosine / ip)
+   * 度量变化需要重建 HNSW 图：用新度量建一个空索引，逐个导出旧向量重新插入，
+   * 全部成功后才替换 self.index，失败时旧索引保持不变
+   * Warning: O(n) operation, rebuilds the entire graph.
+   */
+  setMetric(metric: string): RebuildStats
   /** 删除 (按 ID) */
   remove(id: number): void
+  /**
+   * 清空全部向量，保留维度、度量、量化方式、容量和已注册的回调，用于从头重新导入。
+   * 存活 ID 集合、i8 缩放系数和过期时间一并清空；不会对被清掉的 ID 触发 on_remove
+   */
+  reset(): void
+  /**
+   * 批量重映射 ID (例如数据库压缩自增主键之后)，只加一次写锁。
+   * 先校验整个映射 (同一个旧 ID 或新 ID 出现多次、新 ID 已被一个不参与迁移的向量占用)，
+   * 有冲突时整体放弃；随后先全部删除再全部添加，因此 5→3、3→1 这样的链式映射是安全的。
+   * 改动索引之前先预留好容量；中途写入失败时撤销已做的改动，恢复到调用前的状态并报错。
+   * 索引中不存在的旧 ID 会被跳过并打印警告。返回实际重映射的数量
+   */
+  reassignIds(mapping: Array<IdMapping>): number
+  /** 统计 [min_id, max_id] 区间内的向量数量 (基于存活 ID 集合，不分配完整列表) */
+  countInRange(minId: number, maxId: number): number
+  /** 列出 [min_id, max_id] 区间内的全部 ID (升序) */
+  idsInRange(minId: number, maxId: number): Array<number>
+  /**
+   * 批量检查 ID 是否在索引中，只加一次读锁。返回值与 ids 等长、顺序一致，
+   * 例如在 add_batch 之前过滤掉已存在的向量
+   */
+  batchContains(ids: Array<number>): Array<boolean>
+  /** 注册 add / add_batch 成功后的回调 (参数为 ID)，传 null 取消 */
+  setOnAdd(callback?: ((id: number) => void) | undefined | null): void
+  /** 注册 remove 成功后的回调 (参数为 ID)，传 null 取消 */
+  setOnRemove(callback?: ((id: number) => void) | undefined | null): void
+  /** 注册 save 成功后的回调 (参数为索引文件路径)，传 null 取消 */
+  setOnSave(callback?: ((indexPath: string) => void) | undefined | null): void
   /** 获取当前索引状态 */
   stats(): VexusStats
-  /** 从 SQLite 数据库恢复索引 (异步版本，不阻塞主线程) */
-  recoverFromSqlite(dbPath: string, tableType: string, filterDiaryName?: string | undefined | null): Promise<unknown>
+  /** 获取索引的完整配置 (度量、量化方式、HNSW 参数) 和当前状态，只读，无副作用 */
+  info(): IndexInfo
+  /**
+   * 从 SQLite 数据库恢复索引 (异步版本，不阻塞主线程)
+   * 数据库被其他连接锁住且超过 options.connection_timeout_ms 时，返回 code 为 DB_BUSY 的错误
+   */
+  recoverFromSqlite(dbPath: string, tableType: string, filterDiaryName?: string | undefined | null, options?: RecoverOptions | undefined | null): Promise<unknown>
+  /**
+   * 可取消的 SQLite 恢复：每处理 1000 行检查一次 cancel_token，
+   * 取消后停止读取并返回已恢复的部分 (已写入索引的向量保留)
+   */
+  recoverFromSqliteAsyncWithCancellation(dbPath: string, tableType: string, filterDiaryName?: string | undefined | null, cancelToken?: CancelToken | undefined | null, options?: RecoverOptions | undefined | null): Promise<RecoverStats>
 }
@@ -310,6 +310,7 @@ if (!nativeBinding) {
   throw new Error(`Failed to load native binding`)
 }
 
-const { VexusIndex } = nativeBinding
+const { CancelToken, VexusIndex } = nativeBinding
 
+module.exports.CancelToken = CancelToken
 module.exports.VexusIndex = VexusIndex
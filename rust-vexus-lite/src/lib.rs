@@ -1,16 +1,35 @@
 #![deny(clippy::all)]
 
 use napi::bindgen_prelude::*;
+use napi::threadsafe_function::{
+    ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode,
+};
+use napi::JsFunction;
 use napi_derive::napi;
-use std::sync::{Arc, RwLock};
-use usearch::Index;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use usearch::{Index, MetricKind, ScalarKind};
 use rusqlite::Connection;
 
+// ID 类型说明 (u32 -> i64 迁移指南)
+// SQLite 的 INTEGER 主键是有符号 64 位整数，大库的自增 ID 可能超过 2^32，
+// 因此所有对外的 ID 参数/返回值统一使用 i64：
+// - napi-rs 只能把 u64 映射成 BigInt，而 i64 映射成普通的 JS number，
+//   TS 声明仍然是 `number`，现有 JS 调用方无需修改
+// - JS number 只能精确表示 2^53 以内的整数，超过这个范围的 ID 需要在 JS 侧自行处理
+// - 内部 usearch 的 key 是 u64，两者之间按位转换 (id as u64 / key as i64)，可以无损往返
+// - 写入接口拒绝负数 ID：它们会变成 2^63 以上的 key，SQLite 的主键也不会是负数，多半是调用方的错误
+
 /// 搜索结果 (返回 ID 而非 Tag 文本)
 /// 上层 JS 会拿着 ID 去 SQLite 里查具体的文本内容
 #[napi(object)]
 pub struct SearchResult {
-    pub id: u32,   // 对应 SQLite 中的 chunks.id 或 tags.id
+    pub id: i64,   // 对应 SQLite 中的 chunks.id 或 tags.id
     pub score: f64,
 }
 
@@ -23,216 +42,1789 @@ pub struct VexusStats {
     pub memory_usage: u32,
 }
 
+/// 索引的运行时配置 (构造参数 + 当前状态)，用于确认加载后的度量、量化方式等是否符合预期
+#[napi(object)]
+pub struct IndexInfo {
+    pub dimensions: u32,
+    pub metric: String,           // "l2sq" / "cosine" / "ip"，无法识别时为 "unknown"
+    pub quantization: String,     // "f32" / "i8"
+    pub connectivity: u32,
+    pub expansion_add: u32,
+    pub expansion_search: u32,
+    pub total_vectors: u32,
+    pub capacity: u32,
+    pub memory_usage: u32,
+    pub hardware_acceleration: String,  // usearch 实际使用的 SIMD 实现，例如 "haswell"、"serial"
+}
+
+/// 索引的构造参数，可以序列化为 JSON 保存到磁盘、写进日志或在进程之间传递。
+/// JSON 字段名与 JS 对象一致 (camelCase)；metric 缺省为 "l2sq"，quantization 缺省为 "f32"
+#[napi(object)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct VexusIndexConfig {
+    pub dimensions: u32,
+    pub capacity: u32,
+    #[serde(default = "default_metric")]
+    pub metric: String,           // "l2sq" / "cosine" / "ip"
+    #[serde(default = "default_quantization")]
+    pub quantization: String,     // "f32" / "i8"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,        // 目前被忽略，见 VexusIndex 构造函数的说明
+}
+
+fn default_metric() -> String {
+    "l2sq".to_string()
+}
+
+fn default_quantization() -> String {
+    "f32".to_string()
+}
+
+/// 重建统计 (切换度量等全量重建操作的返回值)
+#[napi(object)]
+pub struct RebuildStats {
+    pub vector_count: u32,
+    pub elapsed_ms: f64,
+}
+
+/// validate_and_add 的结果：added 为 false 时 reason 说明拒绝原因
+#[napi(object)]
+pub struct ValidationResult {
+    pub added: bool,
+    pub reason: Option<String>,
+}
+
+/// 可取消恢复的结果：cancelled 为 true 时 recovered 只是取消前已写入索引的部分
+#[napi(object)]
+pub struct RecoverStats {
+    pub recovered: u32,
+    pub skipped_dim_mismatch: u32,
+    pub cancelled: bool,
+}
+
+/// SQLite 恢复的连接选项
+#[napi(object)]
+pub struct RecoverOptions {
+    /// 数据库被其他连接锁住时最多等待的毫秒数，超时后以 code 为 DB_BUSY 的错误失败。
+    /// 缺省时使用 rusqlite 的默认值 (5 秒)
+    pub connection_timeout_ms: Option<u32>,
+    /// 数据库已经是 WAL 模式时，查询前执行 PRAGMA wal_checkpoint(PASSIVE)，
+    /// 让其他进程已提交但还在 WAL 文件里的事务可见。不会改变数据库文件的日志模式，其他模式的数据库忽略此选项
+    pub wal: Option<bool>,
+}
+
+/// 取消令牌：传给 recover_from_sqlite_async_with_cancellation，在 JS 侧调用 cancel() 请求中止。
+/// 标志位放在 Arc 里，后台任务持有同一份引用，JS 对象先被回收也不影响任务读取
+#[napi]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+#[napi]
+impl CancelToken {
+    #[napi(constructor)]
+    pub fn new() -> Self {
+        Self { cancelled: Arc::new(AtomicBool::new(false)) }
+    }
+
+    /// 请求取消，后台任务会在下一次检查时停止 (每 1000 行检查一次)
+    #[napi]
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    #[napi(getter)]
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+impl Default for CancelToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// ID 重映射项 (reassign_ids 的参数)
+#[napi(object)]
+pub struct IdMapping {
+    pub old_id: i64,
+    pub new_id: i64,
+}
+
 /// 核心索引结构 (无状态，只存向量)
 #[napi]
 pub struct VexusIndex {
     index: Arc<RwLock<Index>>,
     dimensions: u32,
+    // 当前度量 (usearch 没有提供读取接口)，只在持有 index 写锁时修改
+    metric: RwLock<MetricKind>,
+    // 存活 ID 集合 (usearch 不支持枚举 key)，有序以便做区间查询；save 时一并写入 .vexus 状态文件。
+    // 区间查询最初按 u32 ID 设计，随 ID 整体迁移到 i64 (见文件开头的说明)
+    // 加锁顺序：先 index 后 ids
+    ids: Arc<RwLock<BTreeSet<i64>>>,
+    hooks: Arc<RwLock<EventHooks>>,
+    // i8 量化时每个向量的缩放系数 (原始向量的最大绝对值)，get_vector 用它还原量级
+    quantization: ScalarKind,
+    scales: Arc<RwLock<HashMap<i64, f32>>>,
+    // 构造时传入的 seed，只用于 config() 原样返回
+    seed: Option<i64>,
+    // add_with_expiry 写入的向量的过期时间，prune_expired 按它删除；加锁顺序在 scales 之后
+    expiries: Arc<RwLock<HashMap<i64, Instant>>>,
+    // set_prune_interval 启动的后台清理线程的停止信号，drop 发送端即让线程退出
+    prune_worker: Mutex<Option<mpsc::Sender<()>>>,
+}
+
+/// prune_expired 和后台定时清理共用的状态 (VexusIndex 中对应字段的 Arc 克隆)
+struct ExpiryPruner {
+    index: Arc<RwLock<Index>>,
+    ids: Arc<RwLock<BTreeSet<i64>>>,
+    scales: Arc<RwLock<HashMap<i64, f32>>>,
+    expiries: Arc<RwLock<HashMap<i64, Instant>>>,
+    hooks: Arc<RwLock<EventHooks>>,
+}
+
+impl ExpiryPruner {
+    /// 删除所有已过期的向量，返回实际删除的数量；已经被 remove 删掉的 ID 只清理过期记录。
+    /// 释放索引的锁之后再逐个触发 on_remove
+    fn prune(&self) -> Result<u32> {
+        let removed = {
+            let index = self.index.write().map_err(lock_error)?;
+            let mut ids = self.ids.write().map_err(lock_error)?;
+            let mut scales = self.scales.write().map_err(lock_error)?;
+            let mut expiries = self.expiries.write().map_err(lock_error)?;
+            let now = Instant::now();
+            let expired: Vec<i64> = expiries
+                .iter()
+                .filter(|(_, deadline)| **deadline <= now)
+                .map(|(id, _)| *id)
+                .collect();
+            let mut removed = Vec::new();
+            for id in expired {
+                expiries.remove(&id);
+                if !ids.contains(&id) {
+                    continue;
+                }
+                index.remove(id as u64)
+                    .map_err(|e| Error::from_reason(format!("Remove failed for {}: {:?}", id, e)))?;
+                ids.remove(&id);
+                scales.remove(&id);
+                removed.push(id);
+            }
+            removed
+        };
+        if let Some(hook) = &self.hooks.read().map_err(lock_error)?.on_remove {
+            for id in &removed {
+                hook.call(*id, ThreadsafeFunctionCallMode::NonBlocking);
+            }
+        }
+        Ok(removed.len() as u32)
+    }
+}
+
+/// 索引变更事件回调，通过 set_on_add / set_on_remove / set_on_save 注册。
+/// ThreadsafeFunction 在被替换或随 VexusIndex 一起 drop 时自动 release，不会泄漏
+#[derive(Default)]
+struct EventHooks {
+    on_add: Option<ThreadsafeFunction<i64, ErrorStrategy::Fatal>>,
+    on_remove: Option<ThreadsafeFunction<i64, ErrorStrategy::Fatal>>,
+    on_save: Option<ThreadsafeFunction<String, ErrorStrategy::Fatal>>,
+}
+
+/// 把 JS 回调包装成 ThreadsafeFunction；传 null/undefined 表示取消注册。
+/// unref 之后回调不会阻止 Node 进程退出
+fn make_hook<T: ToNapiValue + 'static>(
+    env: &Env,
+    callback: Option<JsFunction>,
+) -> Result<Option<ThreadsafeFunction<T, ErrorStrategy::Fatal>>> {
+    callback
+        .map(|cb| {
+            let mut tsfn = cb.create_threadsafe_function(0, |ctx: ThreadSafeCallContext<T>| {
+                Ok(vec![ctx.value])
+            })?;
+            tsfn.unref(env)?;
+            Ok(tsfn)
+        })
+        .transpose()
+}
+
+/// 自定义错误码，JS 侧可以通过 err.code 区分具体的失败原因
+#[derive(Clone, Copy, PartialEq)]
+pub enum VexusErrorCode {
+    SearchTimeout,
+    DbBusy,
+    GenericFailure,
+}
+
+impl AsRef<str> for VexusErrorCode {
+    fn as_ref(&self) -> &str {
+        match self {
+            VexusErrorCode::SearchTimeout => "SEARCH_TIMEOUT",
+            VexusErrorCode::DbBusy => "DB_BUSY",
+            VexusErrorCode::GenericFailure => "GenericFailure",
+        }
+    }
+}
+
+/// 索引元数据，与索引二进制文件一起保存为 .meta.json
+#[derive(Serialize, Deserialize)]
+struct IndexMeta {
+    dimensions: u32,
+    vector_count: u64,
+    sha256: String,
+    saved_at: u64,
+}
+
+/// save 写在索引文件旁边的状态文件 ({index_path}.vexus)，保存 usearch 文件格式里没有的信息
+#[derive(Serialize, Deserialize)]
+struct IndexState {
+    version: u32,
+    ids: Vec<i64>,  // 存活 ID 集合，加载时直接使用，不必再用暴力搜索枚举 key
+    quantization: String,  // "f32" 或 "i8"
+    scales: Vec<(i64, f32)>,  // i8 量化时每个向量的缩放系数，f32 时为空
+}
+
+const INDEX_STATE_VERSION: u32 = 2;
+
+fn state_path(index_path: &str) -> String {
+    format!("{}.vexus", index_path)
+}
+
+/// 读取与索引文件配套的状态文件。文件不存在、损坏、版本不符，或者与刚加载的索引对不上
+/// (数量不同或有 ID 不在索引中，例如只替换了索引文件) 时返回 None，调用方退回暴力枚举
+fn load_state(index_path: &str, index: &Index) -> Option<IndexState> {
+    let bytes = std::fs::read(state_path(index_path)).ok()?;
+    decode_state(&bytes, index)
+}
+
+/// 解码状态并检查它与索引是否对应，规则同 load_state
+fn decode_state(bytes: &[u8], index: &Index) -> Option<IndexState> {
+    let state: IndexState = bincode::deserialize(bytes).ok()?;
+    let matches = state.version == INDEX_STATE_VERSION
+        && parse_quantization(&state.quantization).is_ok()
+        && state.ids.len() == index.size()
+        && state.ids.iter().all(|id| index.contains(*id as u64));
+    matches.then_some(state)
+}
+
+/// to_buffer 在索引数据之后追加状态 (bincode) + u64 状态长度 + 这个标记，
+/// 使存活 ID 集合、量化类型和缩放系数随 Buffer 一起还原
+const BUFFER_STATE_MAGIC: &[u8; 8] = b"vexus-st";
+
+/// 把 to_buffer 的输出拆成 usearch 索引数据和状态字节。没有状态 (旧版本的输出或 save 写出的文件) 时状态为 None
+fn split_buffer_state(bytes: &[u8]) -> (&[u8], Option<&[u8]>) {
+    let split = || -> Option<(&[u8], &[u8])> {
+        let rest = bytes.strip_suffix(BUFFER_STATE_MAGIC)?;
+        let (rest, len) = rest.split_at_checked(rest.len().checked_sub(8)?)?;
+        let len = usize::try_from(u64::from_le_bytes(len.try_into().ok()?)).ok()?;
+        rest.split_at_checked(rest.len().checked_sub(len)?)
+    };
+    match split() {
+        Some((index_bytes, state_bytes)) => (index_bytes, Some(state_bytes)),
+        None => (bytes, None),
+    }
+}
+
+/// 计算字节内容的 SHA-256 (十六进制小写)
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// 把 usearch 的匹配结果转换为对外的 SearchResult
+fn to_search_results(matches: &usearch::ffi::Matches) -> Vec<SearchResult> {
+    matches
+        .keys
+        .iter()
+        .zip(matches.distances.iter())
+        .map(|(key, &dist)| SearchResult {
+            id: *key as i64,
+            score: 1.0 - dist as f64, // L2sq 距离转相似度分数 (近似)
+        })
+        .collect()
+}
+
+/// 检查向量能否安全写入 usearch：维度、NaN、Inf、零向量。可以写入时返回 None
+fn validate_vector(vector: &[f32], dim: u32) -> Option<String> {
+    if vector.len() != dim as usize {
+        return Some(format!("dimension mismatch: expected {}, got {}", dim, vector.len()));
+    }
+    if let Some(i) = vector.iter().position(|v| v.is_nan()) {
+        return Some(format!("NaN at position {}", i));
+    }
+    if let Some(i) = vector.iter().position(|v| v.is_infinite()) {
+        return Some(format!("Inf at position {}", i));
+    }
+    if vector.iter().all(|v| *v == 0.0) {
+        return Some("zero-norm vector".to_string());
+    }
+    None
+}
+
+/// validate_and_add 的检查：负数 ID 与向量问题一样作为拒绝原因返回，而不是报错
+fn validate_id_and_vector(id: i64, vector: &[f32], dim: u32) -> Option<String> {
+    if id < 0 {
+        return Some(format!("negative id {}", id));
+    }
+    validate_vector(vector, dim)
+}
+
+/// 余弦相似度 (与索引使用的度量无关，MMR 需要统一的尺度)
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f64 {
+    let (mut dot, mut norm_a, mut norm_b) = (0.0f64, 0.0f64, 0.0f64);
+    for (x, y) in a.iter().zip(b) {
+        dot += (*x as f64) * (*y as f64);
+        norm_a += (*x as f64) * (*x as f64);
+        norm_b += (*y as f64) * (*y as f64);
+    }
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a.sqrt() * norm_b.sqrt())
+    }
+}
+
+fn lock_error<E: std::fmt::Display>(e: E) -> Error {
+    Error::from_reason(format!("Lock failed: {}", e))
+}
+
+/// 解析 JS 传入的量化类型
+fn parse_quantization(quantization: &str) -> Result<ScalarKind> {
+    match quantization.to_lowercase().as_str() {
+        "f32" => Ok(ScalarKind::F32),
+        "i8" => Ok(ScalarKind::I8),
+        other => Err(Error::from_reason(format!(
+            "Unknown quantization: {} (expected f32 or i8)",
+            other
+        ))),
+    }
+}
+
+/// i8 量化的缩放系数：向量的最大绝对值 (全零向量取 1)
+fn vector_scale(vector: &[f32]) -> f32 {
+    let scale = vector.iter().fold(0.0f32, |max, v| max.max(v.abs()));
+    if scale > 0.0 { scale } else { 1.0 }
+}
+
+/// i8 量化：按向量自身的最大绝对值缩放到 [-127, 127] 并四舍五入。
+/// 缩放是逐向量的 (不是全局的)：方向保持不变，每个分量有约 1/254 的相对误差，
+/// 但向量之间的量级差异会丢失，L2sq / IP 下的分数与 f32 索引不再可比。
+/// (usearch 自带的 f32 -> i8 转换按 L2 范数缩放，高维时每个分量只能用到很少几个量化级)
+fn quantize_i8(vector: &[f32]) -> Vec<i8> {
+    let scale = vector_scale(vector);
+    vector
+        .iter()
+        .map(|v| (v / scale * 127.0).round().clamp(-127.0, 127.0) as i8)
+        .collect()
+}
+
+/// 按索引的量化类型写入 f32 向量
+fn add_vector(
+    index: &Index,
+    key: u64,
+    vector: &[f32],
+    quantization: ScalarKind,
+) -> std::result::Result<(), impl std::fmt::Debug> {
+    if quantization == ScalarKind::I8 {
+        index.add(key, &quantize_i8(vector))
+    } else {
+        index.add(key, vector)
+    }
+}
+
+/// 按索引的量化类型搜索，查询向量与写入时做同样的缩放
+fn search_vector(
+    index: &Index,
+    query: &[f32],
+    count: usize,
+    quantization: ScalarKind,
+) -> std::result::Result<usearch::ffi::Matches, impl std::fmt::Debug> {
+    if quantization == ScalarKind::I8 {
+        index.search(&quantize_i8(query), count)
+    } else {
+        index.search(query, count)
+    }
+}
+
+/// 解析 JS 传入的度量名称
+fn parse_metric(metric: &str) -> Result<MetricKind> {
+    match metric.to_lowercase().as_str() {
+        "l2sq" | "l2" => Ok(MetricKind::L2sq),
+        "cos" | "cosine" => Ok(MetricKind::Cos),
+        "ip" | "dot" => Ok(MetricKind::IP),
+        other => Err(Error::from_reason(format!(
+            "Unknown metric: {} (expected l2sq, cosine or ip)",
+            other
+        ))),
+    }
+}
+
+fn metric_name(metric: MetricKind) -> &'static str {
+    match metric {
+        MetricKind::L2sq => "l2sq",
+        MetricKind::Cos => "cosine",
+        MetricKind::IP => "ip",
+        _ => "unknown",
+    }
+}
+
+/// save 输出的开头是向量矩阵 (u32 行数 + u32 每行字节数 + 数据)，索引头紧随其后
+fn index_head_offset(prefix: &[u8]) -> Option<usize> {
+    let rows = u32::from_le_bytes(prefix.get(0..4)?.try_into().ok()?) as usize;
+    let row_bytes = u32::from_le_bytes(prefix.get(4..8)?.try_into().ok()?) as usize;
+    Some(8 + rows * row_bytes)
+}
+
+/// 从索引头读出度量。usearch 加载时会按索引头重设度量，
+/// 因此 load 得到的索引不一定是创建空索引时指定的 L2sq。
+/// 索引头：7 字节 magic "usearch" + 3 个 u16 版本号，随后 1 字节度量代码
+fn head_metric(head: &[u8]) -> MetricKind {
+    if !head.starts_with(b"usearch") {
+        return MetricKind::Unknown;
+    }
+    match head.get(13) {
+        Some(b'e') => MetricKind::L2sq,
+        Some(b'c') => MetricKind::Cos,
+        Some(b'i') => MetricKind::IP,
+        _ => MetricKind::Unknown,
+    }
+}
+
+/// 从索引头读出向量的存储类型：度量代码后 1 字节 scalar_kind_t (i8 为 23)。
+/// usearch 加载时同样按索引头设置存储类型，不是 i8 或读不出时按 f32 处理
+fn head_quantization(head: &[u8]) -> ScalarKind {
+    match head.get(14) {
+        Some(23) if head.starts_with(b"usearch") => ScalarKind::I8,
+        _ => ScalarKind::F32,
+    }
+}
+
+/// 完整的 save 输出中的索引头，定位失败时为空
+fn buffer_head(bytes: &[u8]) -> &[u8] {
+    index_head_offset(bytes)
+        .and_then(|offset| bytes.get(offset..))
+        .unwrap_or_default()
+}
+
+/// 从索引文件中读出索引头的前 15 字节，只读取向量矩阵的尺寸和索引头，读取失败时为空
+fn file_head(index_path: &str) -> Vec<u8> {
+    use std::io::{Read, Seek, SeekFrom};
+    let read_head = || -> std::io::Result<Option<[u8; 15]>> {
+        let mut file = std::fs::File::open(index_path)?;
+        let mut prefix = [0u8; 8];
+        file.read_exact(&mut prefix)?;
+        let Some(offset) = index_head_offset(&prefix) else { return Ok(None) };
+        file.seek(SeekFrom::Start(offset as u64))?;
+        let mut head = [0u8; 15];
+        file.read_exact(&mut head)?;
+        Ok(Some(head))
+    };
+    match read_head() {
+        Ok(Some(head)) => head.to_vec(),
+        _ => Vec::new(),
+    }
+}
+
+/// 按统一的 HNSW 参数创建空索引
+fn create_index(dim: u32, metric: MetricKind, quantization: ScalarKind) -> Result<Index> {
+    Index::new(&usearch::IndexOptions {
+        dimensions: dim as usize,
+        metric,
+        quantization,
+        connectivity: 16,
+        expansion_add: 128,
+        expansion_search: 64,
+        multi: false,
+    })
+    .map_err(|e| Error::from_reason(format!("Failed to create index: {:?}", e)))
+}
+
+/// 枚举索引中所有存活的 ID
+/// usearch 没有提供 key 迭代器，这里借助暴力搜索 (count = size) 拿到全部 key，O(n·dim)。
+/// 加载时只在没有可用的状态文件时才走这里
+fn collect_keys(index: &Index, dim: u32) -> Result<Vec<u64>> {
+    if index.size() == 0 {
+        return Ok(Vec::new());
+    }
+    let probe = vec![1.0f32; dim as usize];
+    let matches = index
+        .exact_search(&probe, index.size())
+        .map_err(|e| Error::from_reason(format!("Failed to enumerate keys: {:?}", e)))?;
+    Ok(matches.keys)
+}
+
+/// 写入接口的 ID 检查，负数 ID 直接报错 (见文件开头的说明)
+fn check_id(id: i64) -> Result<()> {
+    if id < 0 {
+        return Err(Error::from_reason(format!("Invalid id {}: ids must be non-negative", id)));
+    }
+    Ok(())
+}
+
+fn check_ids<'a>(ids: impl IntoIterator<Item = &'a i64>) -> Result<()> {
+    ids.into_iter().try_for_each(|id| check_id(*id))
+}
+
+impl VexusIndex {
+    /// 包装一个从磁盘/内存加载好的索引。state 为配套的状态文件，从中恢复存活 ID 集合、量化类型和缩放系数；
+    /// 没有时用暴力搜索重建存活 ID 集合，量化类型取索引头 head 中的存储类型，i8 向量的缩放系数按 1 处理
+    fn from_loaded(index: Index, dim: u32, head: &[u8], state: Option<IndexState>) -> Result<Self> {
+        let (ids, quantization, scales) = match state {
+            Some(state) => (
+                state.ids.into_iter().collect(),
+                parse_quantization(&state.quantization)?,
+                state.scales.into_iter().collect(),
+            ),
+            None => {
+                let ids = collect_keys(&index, dim)?
+                    .into_iter()
+                    .map(|key| key as i64)
+                    .collect();
+                (ids, head_quantization(head), HashMap::new())
+            }
+        };
+        Ok(Self {
+            index: Arc::new(RwLock::new(index)),
+            dimensions: dim,
+            metric: RwLock::new(head_metric(head)),
+            ids: Arc::new(RwLock::new(ids)),
+            hooks: Arc::new(RwLock::new(EventHooks::default())),
+            quantization,
+            scales: Arc::new(RwLock::new(scales)),
+            seed: None,
+            expiries: Arc::new(RwLock::new(HashMap::new())),
+            prune_worker: Mutex::new(None),
+        })
+    }
+
+    /// 按构造参数创建新的空索引 (构造函数和 from_config_json 共用)
+    pub fn from_config(config: &VexusIndexConfig) -> Result<Self> {
+        let metric = parse_metric(&config.metric)?;
+        let quantization = parse_quantization(&config.quantization)?;
+        if config.seed.is_some() && std::env::var("VEXUS_WARN_NONDETERMINISTIC").is_ok_and(|v| v == "1") {
+            eprintln!("[Vexus-Lite] ⚠️ seed is ignored: usearch does not support seeding HNSW graph construction");
+        }
+        // 余弦相似度通常用 L2sq 或 Cosine (如果是归一化向量，L2sq 等价于 Cosine)
+        let index = create_index(config.dimensions, metric, quantization)?;
+
+        index
+            .reserve(config.capacity as usize)
+            .map_err(|e| Error::from_reason(format!("Failed to reserve capacity: {:?}", e)))?;
+
+        Ok(Self {
+            index: Arc::new(RwLock::new(index)),
+            dimensions: config.dimensions,
+            metric: RwLock::new(metric),
+            ids: Arc::new(RwLock::new(BTreeSet::new())),
+            hooks: Arc::new(RwLock::new(EventHooks::default())),
+            quantization,
+            scales: Arc::new(RwLock::new(HashMap::new())),
+            seed: config.seed,
+            expiries: Arc::new(RwLock::new(HashMap::new())),
+            prune_worker: Mutex::new(None),
+        })
+    }
+
+    /// 序列化 save / to_buffer 写出的状态
+    fn state_bytes(&self) -> Result<Vec<u8>> {
+        let state = IndexState {
+            version: INDEX_STATE_VERSION,
+            ids: self.ids.read().map_err(lock_error)?.iter().copied().collect(),
+            quantization: if self.quantization == ScalarKind::I8 { "i8" } else { "f32" }.to_string(),
+            scales: self.scales.read().map_err(lock_error)?.iter().map(|(id, scale)| (*id, *scale)).collect(),
+        };
+        bincode::serialize(&state)
+            .map_err(|e| Error::from_reason(format!("Failed to serialize index state: {}", e)))
+    }
+
+    /// i8 量化时记录新写入向量的缩放系数
+    fn remember_scales<'a>(&self, added: impl IntoIterator<Item = (i64, &'a [f32])>) -> Result<()> {
+        if self.quantization != ScalarKind::I8 {
+            return Ok(());
+        }
+        let mut scales = self.scales.write().map_err(lock_error)?;
+        for (id, vector) in added {
+            scales.insert(id, vector_scale(vector));
+        }
+        Ok(())
+    }
+
+    /// 读出已存在的 ID 对应的 f32 向量，i8 量化的索引按记录的缩放系数还原
+    fn read_vector(&self, index: &Index, id: i64) -> Result<Vec<f32>> {
+        if self.quantization == ScalarKind::I8 {
+            let mut quantized = vec![0i8; self.dimensions as usize];
+            index.get(id as u64, &mut quantized)
+                .map_err(|e| Error::from_reason(format!("Failed to read vector {}: {:?}", id, e)))?;
+            let scale = self.scales.read().map_err(lock_error)?.get(&id).copied().unwrap_or(1.0);
+            Ok(quantized.iter().map(|q| *q as f32 / 127.0 * scale).collect())
+        } else {
+            let mut vector = vec![0.0f32; self.dimensions as usize];
+            index.get(id as u64, &mut vector)
+                .map_err(|e| Error::from_reason(format!("Failed to read vector {}: {:?}", id, e)))?;
+            Ok(vector)
+        }
+    }
+
+    /// 逐个触发 on_add。调用方须先释放 index / ids 的锁，避免回调排队期间其他读写被锁阻塞
+    fn emit_add(&self, ids: impl IntoIterator<Item = i64>) -> Result<()> {
+        if let Some(hook) = &self.hooks.read().map_err(lock_error)?.on_add {
+            for id in ids {
+                hook.call(id, ThreadsafeFunctionCallMode::NonBlocking);
+            }
+        }
+        Ok(())
+    }
+
+    fn expiry_pruner(&self) -> ExpiryPruner {
+        ExpiryPruner {
+            index: self.index.clone(),
+            ids: self.ids.clone(),
+            scales: self.scales.clone(),
+            expiries: self.expiries.clone(),
+            hooks: self.hooks.clone(),
+        }
+    }
 }
 
 #[napi]
 impl VexusIndex {
     /// 创建新的空索引
+    /// quantization 为 "f32" (默认) 或 "i8"。i8 每个分量只占 1 字节，内存约为 f32 的 1/4，
+    /// 写入时逐向量缩放 (见 quantize_i8)，会损失精度和向量之间的量级差异。
+    /// 量化类型和 i8 的缩放系数随 save 写入状态文件、随 to_buffer 写入 Buffer，
+    /// load / load_and_verify / load_from_buffer 时恢复。
+    /// seed 目前被忽略：usearch 的层级随机数生成器在每个线程上下文中以固定的默认种子创建，没有提供设置种子的接口。
+    /// 因此逐条 add 按相同顺序写入时图结构本来就可复现，parallel_add_batch 中向量落到哪个线程上下文不确定，无法复现。
+    /// 设置环境变量 VEXUS_WARN_NONDETERMINISTIC=1 时，传入 seed 会打印一条警告
     #[napi(constructor)]
-    pub fn new(dim: u32, capacity: u32) -> Result<Self> {
-        let index = Index::new(&usearch::IndexOptions {
-            dimensions: dim as usize,
-            metric: usearch::MetricKind::L2sq, // 余弦相似度通常用 L2sq 或 Cosine (如果是归一化向量，L2sq 等价于 Cosine)
-            quantization: usearch::ScalarKind::F32,
-            connectivity: 16,
-            expansion_add: 128,
-            expansion_search: 64,
-            multi: false,
+    pub fn new(dim: u32, capacity: u32, quantization: Option<String>, seed: Option<i64>) -> Result<Self> {
+        Self::from_config(&VexusIndexConfig {
+            dimensions: dim,
+            capacity,
+            metric: default_metric(),
+            quantization: quantization.unwrap_or_else(default_quantization),
+            seed,
+        })
+    }
+
+    /// 按 JSON 形式的构造参数 (见 VexusIndexConfig) 创建新的空索引，JSON 无效时报错
+    #[napi(factory)]
+    pub fn from_config_json(json: String) -> Result<Self> {
+        let config: VexusIndexConfig = serde_json::from_str(&json)
+            .map_err(|e| Error::from_reason(format!("Invalid index config: {}", e)))?;
+        Self::from_config(&config)
+    }
+
+    /// 当前索引的构造参数。capacity 是当前已预留的容量 (扩容后可能大于创建时的值)，
+    /// load 得到的索引 seed 为空
+    #[napi]
+    pub fn config(&self) -> Result<VexusIndexConfig> {
+        let capacity = self.index.read().map_err(lock_error)?.capacity() as u32;
+        let metric = *self.metric.read().map_err(lock_error)?;
+        Ok(VexusIndexConfig {
+            dimensions: self.dimensions,
+            capacity,
+            metric: metric_name(metric).to_string(),
+            quantization: if self.quantization == ScalarKind::I8 { "i8" } else { "f32" }.to_string(),
+            seed: self.seed,
+        })
+    }
+
+    /// 从磁盘加载索引
+    /// 注意：移除了 map_path，因为映射关系现在由 SQLite 管理
+    /// 存活 ID 集合从 save 写出的 {index_path}.vexus 读取；没有该文件 (旧版本保存的索引) 或与索引对不上时，
+    /// 用一次暴力搜索重建，大索引会明显变慢
+    #[napi(factory)]
+    pub fn load(index_path: String, _unused_map_path: Option<String>, dim: u32, capacity: u32) -> Result<Self> {
+        // 为了保持 JS 调用签名兼容，保留了 map_path 参数但忽略它
+        // 或者你可以修改 JS 里的调用去掉第二个参数
+
+        // 创建空索引配置
+        let index = create_index(dim, MetricKind::L2sq, ScalarKind::F32)?;
+
+        // 加载二进制文件
+        index.load(&index_path)
+            .map_err(|e| Error::from_reason(format!("Failed to load index from disk: {:?}", e)))?;
+
+        // 检查容量并扩容
+        let current_capacity = index.capacity();
+        if capacity as usize > current_capacity {
+            // eprintln!("[Vexus] Expanding capacity on load: {} -> {}", current_capacity, capacity);
+            index
+                .reserve(capacity as usize)
+                .map_err(|e| Error::from_reason(format!("Failed to expand capacity: {:?}", e)))?;
+        }
+
+        let state = load_state(&index_path, &index);
+        Self::from_loaded(index, dim, &file_head(&index_path), state)
+    }
+
+    /// 从内存中的索引数据加载 (例如存放在数据库或对象存储里的 to_buffer / save 输出)，不经过临时文件。
+    /// 数据会被复制进索引，调用返回后 buf 可以释放。to_buffer 的输出带有状态，与 load 一样恢复
+    /// 存活 ID 集合、量化类型和缩放系数；只有 save 写出的索引文件 (或旧版本的 to_buffer 输出) 时，
+    /// 存活 ID 集合用一次暴力搜索重建，量化类型取索引头中的存储类型，i8 向量的缩放系数按 1 处理
+    #[napi(factory)]
+    pub fn load_from_buffer(buf: Buffer, dim: u32) -> Result<Self> {
+        let (index_bytes, state_bytes) = split_buffer_state(&buf);
+        let index = create_index(dim, MetricKind::L2sq, ScalarKind::F32)?;
+        index.load_from_buffer(index_bytes)
+            .map_err(|e| Error::from_reason(format!("Failed to load index from buffer: {:?}", e)))?;
+        if index.dimensions() != dim as usize {
+            return Err(Error::from_reason(format!(
+                "Index dimension mismatch: expected {}, got {}",
+                dim,
+                index.dimensions()
+            )));
+        }
+
+        let state = state_bytes.and_then(|bytes| decode_state(bytes, &index));
+        Self::from_loaded(index, dim, buffer_head(index_bytes), state)
+    }
+
+    /// 只加载白名单中的向量 (例如单个用户的文档)
+    /// 以内存映射方式打开完整索引文件，只读出白名单 ID 对应的向量写入新的空索引，
+    /// 不在索引中的 ID 直接忽略；实际找到的数量即返回索引的 size()。
+    /// 新索引沿用源文件索引头中的度量 (读不出时为 L2sq)，以及状态文件中的量化类型和白名单 ID 的缩放系数，
+    /// 搜索分数与加载完整索引一致
+    #[napi(factory)]
+    pub fn load_partial(index_path: String, id_whitelist: Vec<i64>, dim: u32) -> Result<Self> {
+        let head = file_head(&index_path);
+        let metric = match head_metric(&head) {
+            MetricKind::Unknown => MetricKind::L2sq,
+            metric => metric,
+        };
+        let source = create_index(dim, metric, ScalarKind::F32)?;
+        source.view(&index_path)
+            .map_err(|e| Error::from_reason(format!("Failed to load index from disk: {:?}", e)))?;
+
+        let wanted: BTreeSet<i64> = id_whitelist
+            .into_iter()
+            .filter(|id| source.contains(*id as u64))
+            .collect();
+        let (quantization, scales) = match load_state(&index_path, &source) {
+            Some(state) => (
+                parse_quantization(&state.quantization)?,
+                state.scales.into_iter().filter(|(id, _)| wanted.contains(id)).collect(),
+            ),
+            None => (head_quantization(&head), HashMap::new()),
+        };
+
+        let index = create_index(dim, metric, quantization)?;
+        index
+            .reserve(wanted.len().max(1))
+            .map_err(|e| Error::from_reason(format!("Failed to reserve capacity: {:?}", e)))?;
+
+        // 按存储类型原样复制，i8 向量不经过反量化，配合源索引记录的缩放系数还原
+        let mut vector = vec![0.0f32; dim as usize];
+        let mut quantized = vec![0i8; dim as usize];
+        for id in &wanted {
+            let key = *id as u64;
+            let copied = if quantization == ScalarKind::I8 {
+                source.get(key, &mut quantized).and_then(|_| index.add(key, &quantized))
+            } else {
+                source.get(key, &mut vector).and_then(|_| index.add(key, &vector))
+            };
+            copied.map_err(|e| Error::from_reason(format!("Failed to copy vector {}: {:?}", id, e)))?;
+        }
+
+        Ok(Self {
+            index: Arc::new(RwLock::new(index)),
+            dimensions: dim,
+            metric: RwLock::new(metric),
+            ids: Arc::new(RwLock::new(wanted)),
+            hooks: Arc::new(RwLock::new(EventHooks::default())),
+            quantization,
+            scales: Arc::new(RwLock::new(scales)),
+            seed: None,
+            expiries: Arc::new(RwLock::new(HashMap::new())),
+            prune_worker: Mutex::new(None),
         })
-        .map_err(|e| Error::from_reason(format!("Failed to create index: {:?}", e)))?;
+    }
+
+    /// 从磁盘加载索引并校验完整性 (推荐的生产环境加载方式)
+    /// 读取 meta 中保存的 SHA-256，与索引文件内容重新计算的结果比对，不一致则拒绝加载，
+    /// 用于发现磁盘损坏、写入中断或文件被意外覆盖。校验和加载使用同一份字节，避免读两次文件
+    #[napi(factory)]
+    pub fn load_and_verify(index_path: String, meta_path: String, dim: u32) -> Result<Self> {
+        let meta_json = std::fs::read_to_string(&meta_path)
+            .map_err(|e| Error::from_reason(format!("Failed to read meta file: {}", e)))?;
+        let meta: IndexMeta = serde_json::from_str(&meta_json)
+            .map_err(|e| Error::from_reason(format!("Invalid meta file: {}", e)))?;
+
+        if meta.dimensions != dim {
+            return Err(Error::from_reason(format!(
+                "Dimension mismatch: meta says {}, expected {}",
+                meta.dimensions, dim
+            )));
+        }
+
+        let bytes = std::fs::read(&index_path)
+            .map_err(|e| Error::from_reason(format!("Failed to read index file: {}", e)))?;
+        let actual = sha256_hex(&bytes);
+        if actual != meta.sha256 {
+            return Err(Error::from_reason(format!(
+                "Checksum mismatch for {}: expected {}, got {}",
+                index_path, meta.sha256, actual
+            )));
+        }
+
+        let index = create_index(dim, MetricKind::L2sq, ScalarKind::F32)?;
+        index.load_from_buffer(&bytes)
+            .map_err(|e| Error::from_reason(format!("Failed to load index from disk: {:?}", e)))?;
+
+        let state = load_state(&index_path, &index);
+        Self::from_loaded(index, dim, buffer_head(&bytes), state)
+    }
+
+    /// 保存索引到磁盘
+    /// 同时在旁边写出状态文件 {index_path}.vexus (存活 ID 集合、量化类型和 i8 缩放系数)，
+    /// load 时据此跳过重建并还原 i8 向量的量级。
+    /// 传入 meta_path 时额外写出元数据文件 (包含索引文件的 SHA-256)，供 load_and_verify 校验
+    #[napi]
+    pub fn save(&self, index_path: String, meta_path: Option<String>) -> Result<()> {
+        let index = self.index.read()
+            .map_err(|e| Error::from_reason(format!("Lock failed: {}", e)))?;
+        
+        // 原子写入：先写临时文件，再重命名
+        let temp_path = format!("{}.tmp", index_path);
+
+        index
+            .save(&temp_path)
+            .map_err(|e| Error::from_reason(format!("Failed to save index: {:?}", e)))?;
+
+        let state_bytes = self.state_bytes()?;
+        let temp_state_path = format!("{}.tmp", state_path(&index_path));
+        std::fs::write(&temp_state_path, state_bytes)
+            .map_err(|e| Error::from_reason(format!("Failed to write index state: {}", e)))?;
+
+        // 在重命名前计算校验和，保证 meta 对应的就是这次写出的内容
+        let checksum = match meta_path {
+            Some(_) => {
+                let bytes = std::fs::read(&temp_path)
+                    .map_err(|e| Error::from_reason(format!("Failed to read saved index: {}", e)))?;
+                Some(sha256_hex(&bytes))
+            }
+            None => None,
+        };
+
+        std::fs::rename(&temp_path, &index_path)
+            .map_err(|e| Error::from_reason(format!("Failed to rename index file: {}", e)))?;
+        std::fs::rename(&temp_state_path, state_path(&index_path))
+            .map_err(|e| Error::from_reason(format!("Failed to rename index state file: {}", e)))?;
+
+        if let (Some(meta_path), Some(sha256)) = (meta_path, checksum) {
+            let meta = IndexMeta {
+                dimensions: self.dimensions,
+                vector_count: index.size() as u64,
+                sha256,
+                saved_at: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0),
+            };
+            let json = serde_json::to_string_pretty(&meta)
+                .map_err(|e| Error::from_reason(format!("Failed to serialize meta: {}", e)))?;
+
+            let temp_meta_path = format!("{}.tmp", meta_path);
+            std::fs::write(&temp_meta_path, json)
+                .map_err(|e| Error::from_reason(format!("Failed to write meta file: {}", e)))?;
+            std::fs::rename(&temp_meta_path, &meta_path)
+                .map_err(|e| Error::from_reason(format!("Failed to rename meta file: {}", e)))?;
+        }
+        drop(index);
+
+        if let Some(hook) = &self.hooks.read().map_err(lock_error)?.on_save {
+            hook.call(index_path, ThreadsafeFunctionCallMode::NonBlocking);
+        }
+
+        Ok(())
+    }
+
+    /// 把索引序列化到内存 Buffer，可交给 load_from_buffer 还原。内容为 save 写出的索引文件，
+    /// 后面追加状态文件的内容 (存活 ID 集合、量化类型和 i8 缩放系数)。
+    /// 序列化期间持有读锁，不会读到写了一半的状态；不触发 on_save
+    #[napi]
+    pub fn to_buffer(&self) -> Result<Buffer> {
+        let index = self.index.read()
+            .map_err(|e| Error::from_reason(format!("Lock failed: {}", e)))?;
+
+        let mut buffer = vec![0u8; index.serialized_length()];
+        index
+            .save_to_buffer(&mut buffer)
+            .map_err(|e| Error::from_reason(format!("Failed to serialize index: {:?}", e)))?;
+        let state_bytes = self.state_bytes()?;
+        buffer.extend_from_slice(&state_bytes);
+        buffer.extend_from_slice(&(state_bytes.len() as u64).to_le_bytes());
+        buffer.extend_from_slice(BUFFER_STATE_MAGIC);
+        Ok(buffer.into())
+    }
+
+    /// 单个添加 (JS 循环调用)，ID 为负数时报错
+    #[napi]
+    pub fn add(&self, id: i64, vector: Buffer) -> Result<()> {
+        check_id(id)?;
+        let index = self.index.write()
+            .map_err(|e| Error::from_reason(format!("Lock failed: {}", e)))?;
+
+        let vec_slice: &[f32] = unsafe {
+            std::slice::from_raw_parts(
+                vector.as_ptr() as *const f32,
+                vector.len() / std::mem::size_of::<f32>(),
+            )
+        };
+
+        if vec_slice.len() != self.dimensions as usize {
+            return Err(Error::from_reason(format!(
+                "Dimension mismatch: expected {}, got {}",
+                self.dimensions,
+                vec_slice.len()
+            )));
+        }
+
+        // 自动扩容检查
+        if index.size() + 1 >= index.capacity() {
+             let new_cap = (index.capacity() as f64 * 1.5) as usize;
+             let _ = index.reserve(new_cap);
+        }
+
+        add_vector(&index, id as u64, vec_slice, self.quantization)
+            .map_err(|e| Error::from_reason(format!("Add failed: {:?}", e)))?;
+        self.ids.write().map_err(lock_error)?.insert(id);
+        self.remember_scales([(id, vec_slice)])?;
+        drop(index);
+        self.emit_add([id])?;
+
+        Ok(())
+    }
+
+    /// 添加一个带有效期的向量 (例如只在一次会话内有用的消息向量)，ttl_seconds 秒后过期。
+    /// 过期的向量不会自动消失，由 prune_expired 或 set_prune_interval 启动的后台清理删除；
+    /// 用普通 add 写入或被 remove 删除的 ID 没有过期时间
+    #[napi]
+    pub fn add_with_expiry(&self, id: i64, vector: Buffer, ttl_seconds: u32) -> Result<()> {
+        self.add(id, vector)?;
+        let deadline = Instant::now() + Duration::from_secs(ttl_seconds as u64);
+        self.expiries.write().map_err(lock_error)?.insert(id, deadline);
+        Ok(())
+    }
+
+    /// 删除所有已过期的向量 (会触发 on_remove)，返回删除的数量
+    #[napi]
+    pub fn prune_expired(&self) -> Result<u32> {
+        self.expiry_pruner().prune()
+    }
+
+    /// 每隔 seconds 秒在后台线程中自动执行一次 prune_expired，0 表示停止。
+    /// 重复调用会替换之前的间隔；索引被回收时后台线程随之退出
+    #[napi]
+    pub fn set_prune_interval(&self, seconds: u32) -> Result<()> {
+        let mut worker = self.prune_worker.lock().map_err(lock_error)?;
+        // drop 旧的发送端，旧线程在 recv_timeout 返回 Disconnected 后退出
+        *worker = None;
+        if seconds == 0 {
+            return Ok(());
+        }
+        let (stop_tx, stop_rx) = mpsc::channel::<()>();
+        let pruner = self.expiry_pruner();
+        let interval = Duration::from_secs(seconds as u64);
+        std::thread::spawn(move || {
+            while let Err(mpsc::RecvTimeoutError::Timeout) = stop_rx.recv_timeout(interval) {
+                if let Err(e) = pruner.prune() {
+                    println!("[Vexus-Lite] ⚠️ Background prune failed: {}", e);
+                }
+            }
+        });
+        *worker = Some(stop_tx);
+        Ok(())
+    }
+
+    /// 批量添加 (更高效，建议未来 JS 改用此接口)，有任一 ID 为负数时整批不写入
+    #[napi]
+    pub fn add_batch(&self, ids: Vec<i64>, vectors: Buffer) -> Result<()> {
+        check_ids(&ids)?;
+        let index = self.index.write()
+            .map_err(|e| Error::from_reason(format!("Lock failed: {}", e)))?;
+
+        let count = ids.len();
+        let dim = self.dimensions as usize;
+        
+        let vec_slice: &[f32] = unsafe {
+            std::slice::from_raw_parts(
+                vectors.as_ptr() as *const f32,
+                vectors.len() / std::mem::size_of::<f32>(),
+            )
+        };
+
+        if vec_slice.len() != count * dim {
+             return Err(Error::from_reason("Batch size mismatch".to_string()));
+        }
+
+        // 预扩容
+        if index.size() + count >= index.capacity() {
+            let new_cap = ((index.size() + count) as f64 * 1.5) as usize;
+            let _ = index.reserve(new_cap);
+        }
+
+        let mut live_ids = self.ids.write().map_err(lock_error)?;
+        let mut added = Vec::with_capacity(count);
+        let outcome = (|| -> Result<()> {
+            for (i, id) in ids.iter().enumerate() {
+                let start = i * dim;
+                let v = &vec_slice[start..start+dim];
+                // remove + add = update (usearch 行为)
+                // let _ = index.remove(*id as u64); 
+                add_vector(&index, *id as u64, v, self.quantization)
+                    .map_err(|e| Error::from_reason(format!("Batch add failed idx {}: {:?}", i, e)))?;
+                live_ids.insert(*id);
+                self.remember_scales([(*id, v)])?;
+                added.push(*id);
+            }
+            Ok(())
+        })();
+
+        // 中途失败时已写入的部分保留，同样触发回调
+        drop(live_ids);
+        drop(index);
+        self.emit_add(added)?;
+        outcome
+    }
+
+    /// 校验后添加：NaN、Inf、零向量、维度不符或 ID 为负数时不写入索引，而是在结果中说明原因
+    /// (usearch 对 NaN 输入的行为未定义，可能污染整个图)
+    #[napi]
+    pub fn validate_and_add(&self, id: i64, vector: Buffer) -> Result<ValidationResult> {
+        let vec_slice: &[f32] = unsafe {
+            std::slice::from_raw_parts(
+                vector.as_ptr() as *const f32,
+                vector.len() / std::mem::size_of::<f32>(),
+            )
+        };
+        if let Some(reason) = validate_id_and_vector(id, vec_slice, self.dimensions) {
+            return Ok(ValidationResult { added: false, reason: Some(reason) });
+        }
+
+        self.add(id, vector)?;
+        Ok(ValidationResult { added: true, reason: None })
+    }
+
+    /// validate_and_add 的批量版本：只加一次写锁，逐个校验，返回与 ids 一一对应的结果
+    #[napi]
+    pub fn validate_and_add_batch(&self, ids: Vec<i64>, vectors: Buffer) -> Result<Vec<ValidationResult>> {
+        let index = self.index.write()
+            .map_err(|e| Error::from_reason(format!("Lock failed: {}", e)))?;
+
+        let dim = self.dimensions as usize;
+        let vec_slice: &[f32] = unsafe {
+            std::slice::from_raw_parts(
+                vectors.as_ptr() as *const f32,
+                vectors.len() / std::mem::size_of::<f32>(),
+            )
+        };
+
+        if vec_slice.len() != ids.len() * dim {
+             return Err(Error::from_reason("Batch size mismatch".to_string()));
+        }
+
+        if index.size() + ids.len() >= index.capacity() {
+            let new_cap = ((index.size() + ids.len()) as f64 * 1.5) as usize;
+            let _ = index.reserve(new_cap);
+        }
+
+        let mut live_ids = self.ids.write().map_err(lock_error)?;
+        let mut results = Vec::with_capacity(ids.len());
+        let mut added = Vec::new();
+        for (id, v) in ids.iter().zip(vec_slice.chunks(dim)) {
+            if let Some(reason) = validate_id_and_vector(*id, v, self.dimensions) {
+                results.push(ValidationResult { added: false, reason: Some(reason) });
+                continue;
+            }
+            match add_vector(&index, *id as u64, v, self.quantization) {
+                Ok(_) => {
+                    live_ids.insert(*id);
+                    self.remember_scales([(*id, v)])?;
+                    added.push(*id);
+                    results.push(ValidationResult { added: true, reason: None });
+                }
+                Err(e) => results.push(ValidationResult {
+                    added: false,
+                    reason: Some(format!("add failed: {:?}", e)),
+                }),
+            }
+        }
+
+        drop(live_ids);
+        drop(index);
+        self.emit_add(added)?;
+        Ok(results)
+    }
+
+    /// 多线程批量添加：usearch 的 add 在预留了足够线程槽位后可以并发调用，
+    /// 这里用限定为 threads 个线程的 rayon 线程池并行插入 (threads 为 0 时使用 CPU 核数)。
+    /// 单个向量插入失败只打印警告，返回成功插入的数量
+    #[napi]
+    pub fn parallel_add_batch(&self, ids: Vec<i64>, vectors: Buffer, threads: u32) -> Result<u32> {
+        check_ids(&ids)?;
+        let index = self.index.write()
+            .map_err(|e| Error::from_reason(format!("Lock failed: {}", e)))?;
+
+        let count = ids.len();
+        let dim = self.dimensions as usize;
+
+        let vec_slice: &[f32] = unsafe {
+            std::slice::from_raw_parts(
+                vectors.as_ptr() as *const f32,
+                vectors.len() / std::mem::size_of::<f32>(),
+            )
+        };
+
+        if vec_slice.len() != count * dim {
+             return Err(Error::from_reason("Batch size mismatch".to_string()));
+        }
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads as usize)
+            .build()
+            .map_err(|e| Error::from_reason(format!("Failed to build thread pool: {}", e)))?;
+
+        // 并发 add 需要为每个线程预留槽位，容量一次性扩好，避免插入过程中扩容
+        let capacity = std::cmp::max(index.capacity(), ((index.size() + count) as f64 * 1.5) as usize);
+        index
+            .reserve_capacity_and_threads(capacity, pool.current_num_threads())
+            .map_err(|e| Error::from_reason(format!("Failed to reserve capacity: {:?}", e)))?;
+
+        let shared: &Index = &index;
+        let quantization = self.quantization;
+        let added: Vec<(i64, &[f32])> = pool.install(|| {
+            ids.par_iter()
+                .zip(vec_slice.par_chunks(dim))
+                .filter_map(|(id, v)| match add_vector(shared, *id as u64, v, quantization) {
+                    Ok(_) => Some((*id, v)),
+                    Err(e) => {
+                        println!("[Vexus-Lite] ⚠️ parallel_add_batch: failed to add {}: {:?}", id, e);
+                        None
+                    }
+                })
+                .collect()
+        });
+
+        let mut live_ids = self.ids.write().map_err(lock_error)?;
+        live_ids.extend(added.iter().map(|(id, _)| *id));
+        self.remember_scales(added.iter().copied())?;
+
+        drop(live_ids);
+        drop(index);
+        self.emit_add(added.iter().map(|(id, _)| *id))?;
+        Ok(added.len() as u32)
+    }
+
+    /// 搜索
+    #[napi]
+    pub fn search(&self, query: Buffer, k: u32) -> Result<Vec<SearchResult>> {
+        let index = self.index.read()
+            .map_err(|e| Error::from_reason(format!("Lock failed: {}", e)))?;
+
+        let query_slice: &[f32] = unsafe {
+            std::slice::from_raw_parts(
+                query.as_ptr() as *const f32,
+                query.len() / std::mem::size_of::<f32>(),
+            )
+        };
+
+        // 🔥🔥🔥【新增】维度安全检查 🔥🔥🔥
+        if query_slice.len() != self.dimensions as usize {
+            return Err(Error::from_reason(format!(
+                "Search dimension mismatch: expected {}, got {}. (Check your JS Buffer slicing!)",
+                self.dimensions,
+                query_slice.len()
+            )));
+        }
+
+        // 执行搜索
+        let matches = search_vector(&index, query_slice, k as usize, self.quantization)
+            .map_err(|e| Error::from_reason(format!("Search failed: {:?}", e)))?;
+
+        Ok(to_search_results(&matches))
+    }
+
+    /// 带超时的搜索
+    /// 在独立线程中执行 usearch 搜索并用 recv_timeout 等待，超时返回 code 为 SEARCH_TIMEOUT 的错误。
+    /// 注意：usearch 的搜索线程无法被安全地中止，超时后它仍会在后台跑完 (结果被丢弃)
+    #[napi]
+    pub fn search_with_timeout(
+        &self,
+        query: Buffer,
+        k: u32,
+        timeout_ms: u32,
+    ) -> Result<Vec<SearchResult>, VexusErrorCode> {
+        let query_slice: &[f32] = unsafe {
+            std::slice::from_raw_parts(
+                query.as_ptr() as *const f32,
+                query.len() / std::mem::size_of::<f32>(),
+            )
+        };
+        let query_vec = query_slice.to_vec();
+
+        if query_vec.len() != self.dimensions as usize {
+            return Err(Error::new(
+                VexusErrorCode::GenericFailure,
+                format!(
+                    "Search dimension mismatch: expected {}, got {}. (Check your JS Buffer slicing!)",
+                    self.dimensions,
+                    query_vec.len()
+                ),
+            ));
+        }
+
+        let (tx, rx) = mpsc::channel();
+        let index = self.index.clone();
+        let quantization = self.quantization;
+        std::thread::spawn(move || {
+            let outcome = match index.read() {
+                Ok(index) => search_vector(&index, &query_vec, k as usize, quantization)
+                    .map(|matches| to_search_results(&matches))
+                    .map_err(|e| format!("Search failed: {:?}", e)),
+                Err(e) => Err(format!("Lock failed: {}", e)),
+            };
+            // 超时后接收端已经关闭，发送失败可以忽略
+            let _ = tx.send(outcome);
+        });
+
+        match rx.recv_timeout(Duration::from_millis(timeout_ms as u64)) {
+            Ok(Ok(results)) => Ok(results),
+            Ok(Err(reason)) => Err(Error::new(VexusErrorCode::GenericFailure, reason)),
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                println!("[Vexus-Lite] ⚠️ Search timed out after {}ms, the search thread will keep running in the background until it completes", timeout_ms);
+                Err(Error::new(
+                    VexusErrorCode::SearchTimeout,
+                    format!("Search did not complete within {}ms", timeout_ms),
+                ))
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => Err(Error::new(
+                VexusErrorCode::GenericFailure,
+                "Search thread exited unexpectedly".to_string(),
+            )),
+        }
+    }
+
+    /// 按 ID 取出向量 (f32 小端字节)，ID 不存在时返回 null
+    /// i8 量化的索引按写入时记录的缩放系数还原成 f32，精度损失见 quantize_i8
+    #[napi]
+    pub fn get_vector(&self, id: i64) -> Result<Option<Buffer>> {
+        let index = self.index.read()
+            .map_err(|e| Error::from_reason(format!("Lock failed: {}", e)))?;
+        if !index.contains(id as u64) {
+            return Ok(None);
+        }
+
+        let vector = self.read_vector(&index, id)?;
+        let bytes: Vec<u8> = vector.iter().flat_map(|v| v.to_le_bytes()).collect();
+        Ok(Some(bytes.into()))
+    }
+
+    /// 计算全部向量的均值 (质心，f32 小端字节)，用于判断查询是否落在某个主题簇内或筛掉离题内容。
+    /// 逐个读出存活 ID 的向量并用 f64 累加，O(n) 且要读出每个向量，开销较大，不宜在每次查询时调用；
+    /// i8 量化的索引按还原后的向量计算。索引为空时报错
+    #[napi]
+    pub fn compute_centroid(&self) -> Result<Buffer> {
+        let index = self.index.read().map_err(lock_error)?;
+        let ids = self.ids.read().map_err(lock_error)?;
+        if ids.is_empty() {
+            return Err(Error::from_reason("Cannot compute centroid of an empty index"));
+        }
+
+        let mut sum = vec![0.0f64; self.dimensions as usize];
+        for id in ids.iter() {
+            let vector = self.read_vector(&index, *id)?;
+            for (total, v) in sum.iter_mut().zip(vector) {
+                *total += v as f64;
+            }
+        }
+        let count = ids.len() as f64;
+        let bytes: Vec<u8> = sum.iter().flat_map(|total| ((total / count) as f32).to_le_bytes()).collect();
+        Ok(bytes.into())
+    }
+
+    /// 查看某个节点在 HNSW 图第 level 层的邻居，用于排查搜索结果异常。
+    /// usearch 的 Rust 绑定没有暴露图结构，这里是近似实现：用该节点的向量搜索，
+    /// 返回除它自己以外最近的 M 个 ID (M = connectivity，第 0 层为 2M，与 usearch 每层的邻居上限一致)。
+    /// 实际的图邻居是构建时按启发式裁剪过的，不一定与这里的结果完全相同；ID 不存在时报错
+    #[napi]
+    pub fn inspect_hnsw_neighbors(&self, id: i64, level: u32) -> Result<Vec<i64>> {
+        let index = self.index.read()
+            .map_err(|e| Error::from_reason(format!("Lock failed: {}", e)))?;
+        if !index.contains(id as u64) {
+            return Err(Error::from_reason(format!("ID {} not found", id)));
+        }
+
+        let vector = self.read_vector(&index, id)?;
+        let neighbors = if level == 0 { index.connectivity() * 2 } else { index.connectivity() };
+        let matches = search_vector(&index, &vector, neighbors + 1, self.quantization)
+            .map_err(|e| Error::from_reason(format!("Search failed: {:?}", e)))?;
+
+        Ok(matches
+            .keys
+            .iter()
+            .map(|key| *key as i64)
+            .filter(|key| *key != id)
+            .take(neighbors)
+            .collect())
+    }
+
+    /// 排除指定 ID 的搜索：先取 k + excluded_ids.len() 个结果，过滤后不足 k 个时把候选数翻倍重试，
+    /// 直到凑够 k 个或候选数达到索引大小。返回至多 k 个结果
+    #[napi]
+    pub fn search_excluding_tags(&self, query: Buffer, k: u32, excluded_ids: Vec<i64>) -> Result<Vec<SearchResult>> {
+        let index = self.index.read()
+            .map_err(|e| Error::from_reason(format!("Lock failed: {}", e)))?;
+
+        let query_slice: &[f32] = unsafe {
+            std::slice::from_raw_parts(
+                query.as_ptr() as *const f32,
+                query.len() / std::mem::size_of::<f32>(),
+            )
+        };
+        if query_slice.len() != self.dimensions as usize {
+            return Err(Error::from_reason(format!(
+                "Search dimension mismatch: expected {}, got {}",
+                self.dimensions,
+                query_slice.len()
+            )));
+        }
+
+        let k = k as usize;
+        let excluded: HashSet<i64> = excluded_ids.into_iter().collect();
+        let size = index.size();
+        let mut fetch = (k + excluded.len()).min(size);
+        if k == 0 || fetch == 0 {
+            return Ok(Vec::new());
+        }
+        loop {
+            let matches = search_vector(&index, query_slice, fetch, self.quantization)
+                .map_err(|e| Error::from_reason(format!("Search failed: {:?}", e)))?;
+            let mut results: Vec<SearchResult> = to_search_results(&matches)
+                .into_iter()
+                .filter(|result| !excluded.contains(&result.id))
+                .collect();
+            if results.len() >= k || fetch >= size {
+                results.truncate(k);
+                return Ok(results);
+            }
+            fetch = (fetch * 2).min(size);
+        }
+    }
+
+    /// 多样性搜索 (MMR, Maximal Marginal Relevance)：先取 4k 个候选，
+    /// 再逐个挑选使 λ * 与查询的相似度 - (1 - λ) * 与已选结果的最大相似度 最大的候选，λ = diversity_threshold。
+    /// λ = 1 等价于普通搜索，越小结果越分散；相似度统一用余弦计算，返回的 score 仍是原始搜索分数
+    #[napi]
+    pub fn search_diverse(&self, query: Buffer, k: u32, diversity_threshold: f64) -> Result<Vec<SearchResult>> {
+        let index = self.index.read()
+            .map_err(|e| Error::from_reason(format!("Lock failed: {}", e)))?;
+
+        let query_slice: &[f32] = unsafe {
+            std::slice::from_raw_parts(
+                query.as_ptr() as *const f32,
+                query.len() / std::mem::size_of::<f32>(),
+            )
+        };
+        if query_slice.len() != self.dimensions as usize {
+            return Err(Error::from_reason(format!(
+                "Search dimension mismatch: expected {}, got {}",
+                self.dimensions,
+                query_slice.len()
+            )));
+        }
+
+        let matches = search_vector(&index, query_slice, k as usize * 4, self.quantization)
+            .map_err(|e| Error::from_reason(format!("Search failed: {:?}", e)))?;
 
-        index
-            .reserve(capacity as usize)
-            .map_err(|e| Error::from_reason(format!("Failed to reserve capacity: {:?}", e)))?;
+        // 候选：(搜索结果, 向量, 与查询的相似度)
+        let mut candidates = Vec::with_capacity(matches.keys.len());
+        for result in to_search_results(&matches) {
+            let mut vector = vec![0.0f32; self.dimensions as usize];
+            index.get(result.id as u64, &mut vector)
+                .map_err(|e| Error::from_reason(format!("Failed to read vector {}: {:?}", result.id, e)))?;
+            let relevance = cosine_similarity(query_slice, &vector);
+            candidates.push((result, vector, relevance));
+        }
 
-        Ok(Self {
-            index: Arc::new(RwLock::new(index)),
-            dimensions: dim,
-        })
+        let lambda = diversity_threshold.clamp(0.0, 1.0);
+        let mut selected: Vec<(SearchResult, Vec<f32>, f64)> = Vec::with_capacity(k as usize);
+        while selected.len() < k as usize && !candidates.is_empty() {
+            let mmr = |(_, vector, relevance): &(SearchResult, Vec<f32>, f64)| {
+                let redundancy = selected
+                    .iter()
+                    .map(|(_, chosen, _)| cosine_similarity(vector, chosen))
+                    .fold(f64::NEG_INFINITY, f64::max);
+                let redundancy = if redundancy.is_finite() { redundancy } else { 0.0 };
+                lambda * relevance - (1.0 - lambda) * redundancy
+            };
+            let best = candidates
+                .iter()
+                .enumerate()
+                .max_by(|(_, a), (_, b)| mmr(a).total_cmp(&mmr(b)))
+                .map(|(i, _)| i)
+                .unwrap_or(0);
+            selected.push(candidates.swap_remove(best));
+        }
+
+        Ok(selected.into_iter().map(|(result, _, _)| result).collect())
     }
 
-    /// 从磁盘加载索引
-    /// 注意：移除了 map_path，因为映射关系现在由 SQLite 管理
-    #[napi(factory)]
-    pub fn load(index_path: String, _unused_map_path: Option<String>, dim: u32, capacity: u32) -> Result<Self> {
-        // 为了保持 JS 调用签名兼容，保留了 map_path 参数但忽略它
-        // 或者你可以修改 JS 里的调用去掉第二个参数
+    /// 多向量 AND 检索：每个查询向量各自取 top-k，只保留在所有结果里都出现的 ID，
+    /// 按各查询分数之和重新排序后返回前 k 个。
+    /// 交集为空且 fallback_to_union 为 true 时改为返回并集 (同样按分数之和排序)
+    #[napi]
+    pub fn intersect_search(
+        &self,
+        queries: Vec<Buffer>,
+        k: u32,
+        fallback_to_union: Option<bool>,
+    ) -> Result<Vec<SearchResult>> {
+        if queries.is_empty() {
+            return Err(Error::from_reason("intersect_search requires at least one query".to_string()));
+        }
 
-        // 创建空索引配置
-        let index = Index::new(&usearch::IndexOptions {
-            dimensions: dim as usize,
-            metric: usearch::MetricKind::L2sq,
-            quantization: usearch::ScalarKind::F32,
-            connectivity: 16,
-            expansion_add: 128,
-            expansion_search: 64,
-            multi: false,
-        })
-        .map_err(|e| Error::from_reason(format!("Failed to create index wrapper: {:?}", e)))?;
+        let index = self.index.read()
+            .map_err(|e| Error::from_reason(format!("Lock failed: {}", e)))?;
 
-        // 加载二进制文件
-        index.load(&index_path)
-            .map_err(|e| Error::from_reason(format!("Failed to load index from disk: {:?}", e)))?;
+        // id -> (分数之和, 命中的查询数)
+        let mut scores: HashMap<i64, (f64, usize)> = HashMap::new();
+        for (i, query) in queries.iter().enumerate() {
+            let query_slice: &[f32] = unsafe {
+                std::slice::from_raw_parts(
+                    query.as_ptr() as *const f32,
+                    query.len() / std::mem::size_of::<f32>(),
+                )
+            };
+            if query_slice.len() != self.dimensions as usize {
+                return Err(Error::from_reason(format!(
+                    "Search dimension mismatch for query {}: expected {}, got {}",
+                    i,
+                    self.dimensions,
+                    query_slice.len()
+                )));
+            }
 
-        // 检查容量并扩容
-        let current_capacity = index.capacity();
-        if capacity as usize > current_capacity {
-            // eprintln!("[Vexus] Expanding capacity on load: {} -> {}", current_capacity, capacity);
-            index
-                .reserve(capacity as usize)
-                .map_err(|e| Error::from_reason(format!("Failed to expand capacity: {:?}", e)))?;
+            let matches = search_vector(&index, query_slice, k as usize, self.quantization)
+                .map_err(|e| Error::from_reason(format!("Search failed: {:?}", e)))?;
+            for result in to_search_results(&matches) {
+                let entry = scores.entry(result.id).or_insert((0.0, 0));
+                entry.0 += result.score;
+                entry.1 += 1;
+            }
         }
 
-        Ok(Self {
-            index: Arc::new(RwLock::new(index)),
-            dimensions: dim,
-        })
+        let mut results: Vec<SearchResult> = scores
+            .iter()
+            .filter(|(_, (_, hits))| *hits == queries.len())
+            .map(|(&id, &(score, _))| SearchResult { id, score })
+            .collect();
+        if results.is_empty() && fallback_to_union.unwrap_or(false) {
+            results = scores
+                .into_iter()
+                .map(|(id, (score, _))| SearchResult { id, score })
+                .collect();
+        }
+
+        results.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.id.cmp(&b.id)));
+        results.truncate(k as usize);
+        Ok(results)
     }
 
-    /// 保存索引到磁盘
+    /// 召回率评估：queries 为首尾相接的 f32 向量 (与 add_batch 相同的打包方式)，ground_truth[i] 是第 i 个查询
+    /// 由暴力搜索得到的真实近邻 ID (按距离从近到远)。对每个查询做一次 HNSW 搜索，
+    /// recall@k = top-k 结果中出现在 ground_truth[i] 前 k 个里的比例，返回所有查询的平均值。
+    /// ground_truth 为空的查询不参与平均
     #[napi]
-    pub fn save(&self, index_path: String) -> Result<()> {
+    pub fn recall_at_k(&self, ground_truth: Vec<Vec<i64>>, queries: Buffer, k: u32) -> Result<f64> {
+        if k == 0 {
+            return Err(Error::from_reason("recall_at_k requires k > 0".to_string()));
+        }
+        if ground_truth.is_empty() {
+            return Err(Error::from_reason("recall_at_k requires at least one query".to_string()));
+        }
+        let dim = self.dimensions as usize;
+        // 先核对长度再构造切片，空 Buffer 的指针可能为空
+        if queries.len() != ground_truth.len() * dim * std::mem::size_of::<f32>() {
+            return Err(Error::from_reason(format!(
+                "Batch size mismatch: {} ground truth lists but {} bytes of queries (dimension {})",
+                ground_truth.len(),
+                queries.len(),
+                dim
+            )));
+        }
+        let query_slice: &[f32] = unsafe {
+            std::slice::from_raw_parts(queries.as_ptr() as *const f32, ground_truth.len() * dim)
+        };
+
         let index = self.index.read()
             .map_err(|e| Error::from_reason(format!("Lock failed: {}", e)))?;
-        
-        // 原子写入：先写临时文件，再重命名
-        let temp_path = format!("{}.tmp", index_path);
+        let recalls = ground_truth
+            .par_iter()
+            .zip(query_slice.par_chunks(dim))
+            .filter(|(truth, _)| !truth.is_empty())
+            .map(|(truth, query)| {
+                let truth: HashSet<i64> = truth.iter().take(k as usize).copied().collect();
+                let matches = search_vector(&index, query, k as usize, self.quantization)
+                    .map_err(|e| Error::from_reason(format!("Search failed: {:?}", e)))?;
+                let hits = matches.keys.iter().filter(|&&key| truth.contains(&(key as i64))).count();
+                Ok(hits as f64 / truth.len() as f64)
+            })
+            .collect::<Result<Vec<f64>>>()?;
 
-        index
-            .save(&temp_path)
-            .map_err(|e| Error::from_reason(format!("Failed to save index: {:?}", e)))?;
+        if recalls.is_empty() {
+            return Err(Error::from_reason("recall_at_k requires at least one non-empty ground truth list".to_string()));
+        }
+        Ok(recalls.iter().sum::<f64>() / recalls.len() as f64)
+    }
 
-        std::fs::rename(&temp_path, &index_path)
-            .map_err(|e| Error::from_reason(format!("Failed to rename index file: {}", e)))?;
+    /// 在线切换距离度量 (l2sq / cosine / ip)
+    /// 度量变化需要重建 HNSW 图：用新度量建一个空索引，逐个导出旧向量重新插入，
+    /// 全部成功后才替换 self.index，失败时旧索引保持不变
+    #[doc = "Warning: O(n) operation, rebuilds the entire graph."]
+    #[napi]
+    pub fn set_metric(&self, metric: String) -> Result<RebuildStats> {
+        let new_metric = parse_metric(&metric)?;
+        let started = Instant::now();
 
-        Ok(())
+        // 全程持有写锁，避免重建期间有新的写入丢失
+        let mut index = self.index.write()
+            .map_err(|e| Error::from_reason(format!("Lock failed: {}", e)))?;
+
+        let rebuilt = create_index(self.dimensions, new_metric, self.quantization)?;
+        rebuilt
+            .reserve(index.capacity())
+            .map_err(|e| Error::from_reason(format!("Failed to reserve capacity: {:?}", e)))?;
+
+        // 存活 ID 集合不随度量变化，直接按它逐个导出，不必再用暴力搜索枚举 key。
+        // i8 索引读出的是 q / 127，其中最大分量为 ±1，经 add_vector 重新量化后与原值完全一致
+        let ids = self.ids.read().map_err(lock_error)?;
+        let mut buffer = vec![0.0f32; self.dimensions as usize];
+        for id in ids.iter() {
+            index.get(*id as u64, &mut buffer)
+                .map_err(|e| Error::from_reason(format!("Failed to read vector {}: {:?}", id, e)))?;
+            add_vector(&rebuilt, *id as u64, &buffer, self.quantization)
+                .map_err(|e| Error::from_reason(format!("Rebuild add failed for {}: {:?}", id, e)))?;
+        }
+
+        *index = rebuilt;
+        *self.metric.write().map_err(lock_error)? = new_metric;
+
+        Ok(RebuildStats {
+            vector_count: ids.len() as u32,
+            elapsed_ms: started.elapsed().as_secs_f64() * 1000.0,
+        })
     }
 
-    /// 单个添加 (JS 循环调用)
+    /// 删除 (按 ID)
     #[napi]
-    pub fn add(&self, id: u32, vector: Buffer) -> Result<()> {
+    pub fn remove(&self, id: i64) -> Result<()> {
         let index = self.index.write()
             .map_err(|e| Error::from_reason(format!("Lock failed: {}", e)))?;
+        
+        index.remove(id as u64)
+             .map_err(|e| Error::from_reason(format!("Remove failed: {:?}", e)))?;
+        let removed = self.ids.write().map_err(lock_error)?.remove(&id);
+        self.scales.write().map_err(lock_error)?.remove(&id);
+        self.expiries.write().map_err(lock_error)?.remove(&id);
+        drop(index);
 
-        let vec_slice: &[f32] = unsafe {
-            std::slice::from_raw_parts(
-                vector.as_ptr() as *const f32,
-                vector.len() / std::mem::size_of::<f32>(),
-            )
-        };
-
-        if vec_slice.len() != self.dimensions as usize {
-            return Err(Error::from_reason(format!(
-                "Dimension mismatch: expected {}, got {}",
-                self.dimensions,
-                vec_slice.len()
-            )));
+        // 只对确实存在过的 ID 触发 on_remove，且与 emit_add 一样在释放锁之后
+        if removed {
+            if let Some(hook) = &self.hooks.read().map_err(lock_error)?.on_remove {
+                hook.call(id, ThreadsafeFunctionCallMode::NonBlocking);
+            }
         }
 
-        // 自动扩容检查
-        if index.size() + 1 >= index.capacity() {
-             let new_cap = (index.capacity() as f64 * 1.5) as usize;
-             let _ = index.reserve(new_cap);
-        }
+        Ok(())
+    }
 
+    /// 清空全部向量，保留维度、度量、量化方式、容量和已注册的回调，用于从头重新导入。
+    /// 存活 ID 集合、i8 缩放系数和过期时间一并清空；不会对被清掉的 ID 触发 on_remove
+    #[napi]
+    pub fn reset(&self) -> Result<()> {
+        let index = self.index.write()
+            .map_err(|e| Error::from_reason(format!("Lock failed: {}", e)))?;
+
+        let capacity = index.capacity();
+        index.reset()
+            .map_err(|e| Error::from_reason(format!("Reset failed: {:?}", e)))?;
+        // usearch 的 reset 会把内存还给系统，这里按原容量重新预留
         index
-            .add(id as u64, vec_slice)
-            .map_err(|e| Error::from_reason(format!("Add failed: {:?}", e)))?;
+            .reserve(capacity)
+            .map_err(|e| Error::from_reason(format!("Failed to reserve capacity: {:?}", e)))?;
+        self.ids.write().map_err(lock_error)?.clear();
+        self.scales.write().map_err(lock_error)?.clear();
+        self.expiries.write().map_err(lock_error)?.clear();
 
         Ok(())
     }
 
-    /// 批量添加 (更高效，建议未来 JS 改用此接口)
+    /// 批量重映射 ID (例如数据库压缩自增主键之后)，只加一次写锁。
+    /// 先校验整个映射 (同一个旧 ID 或新 ID 出现多次、新 ID 已被一个不参与迁移的向量占用)，
+    /// 有冲突时整体放弃；随后先全部删除再全部添加，因此 5→3、3→1 这样的链式映射是安全的。
+    /// 改动索引之前先预留好容量；中途写入失败时撤销已做的改动，恢复到调用前的状态并报错。
+    /// 索引中不存在的旧 ID 会被跳过并打印警告。返回实际重映射的数量
     #[napi]
-    pub fn add_batch(&self, ids: Vec<u32>, vectors: Buffer) -> Result<()> {
+    pub fn reassign_ids(&self, mapping: Vec<IdMapping>) -> Result<u32> {
+        let mut sources = HashSet::new();
+        let mut targets = HashSet::new();
+        for m in &mapping {
+            check_ids([&m.old_id, &m.new_id])?;
+            if !sources.insert(m.old_id) {
+                return Err(Error::from_reason(format!(
+                    "Conflicting mapping: old id {} appears more than once",
+                    m.old_id
+                )));
+            }
+            if !targets.insert(m.new_id) {
+                return Err(Error::from_reason(format!(
+                    "Conflicting mapping: new id {} is assigned more than once",
+                    m.new_id
+                )));
+            }
+        }
+
         let index = self.index.write()
             .map_err(|e| Error::from_reason(format!("Lock failed: {}", e)))?;
+        let mut live_ids = self.ids.write().map_err(lock_error)?;
+
+        let (found, missing): (Vec<&IdMapping>, Vec<&IdMapping>) =
+            mapping.iter().partition(|m| live_ids.contains(&m.old_id));
+        for m in &missing {
+            println!("[Vexus-Lite] ⚠️ reassign_ids: old id {} not found in index, skipped", m.old_id);
+        }
+
+        let moving: HashSet<i64> = found.iter().map(|m| m.old_id).collect();
+        if let Some(m) = found.iter().find(|m| live_ids.contains(&m.new_id) && !moving.contains(&m.new_id)) {
+            return Err(Error::from_reason(format!(
+                "Conflicting mapping: new id {} is already in use",
+                m.new_id
+            )));
+        }
 
-        let count = ids.len();
         let dim = self.dimensions as usize;
-        
-        let vec_slice: &[f32] = unsafe {
-            std::slice::from_raw_parts(
-                vectors.as_ptr() as *const f32,
-                vectors.len() / std::mem::size_of::<f32>(),
-            )
-        };
+        let mut vectors = vec![0.0f32; found.len() * dim];
+        for (m, buffer) in found.iter().zip(vectors.chunks_mut(dim)) {
+            index.get(m.old_id as u64, buffer)
+                .map_err(|e| Error::from_reason(format!("Failed to read vector {}: {:?}", m.old_id, e)))?;
+        }
 
-        if vec_slice.len() != count * dim {
-             return Err(Error::from_reason("Batch size mismatch".to_string()));
+        // 删除只做标记，不释放槽位，重新写入需要 found.len() 个新槽位
+        let required = index.size() + found.len() + 1;
+        if required > index.capacity() {
+            index
+                .reserve(required)
+                .map_err(|e| Error::from_reason(format!("Failed to reserve capacity: {:?}", e)))?;
         }
 
-        // 预扩容
-        if index.size() + count >= index.capacity() {
-            let new_cap = ((index.size() + count) as f64 * 1.5) as usize;
-            let _ = index.reserve(new_cap);
+        // 读出的是存储空间里的向量 (i8 时为 q / 127)，重新写入不会改变量化结果；缩放系数和过期时间跟着 ID 一起迁移
+        let mut scales = self.scales.write().map_err(lock_error)?;
+
+        let mut expiries = self.expiries.write().map_err(lock_error)?;
+
+        let mut removed = 0;
+        let mut added = 0;
+        let outcome = (|| -> Result<()> {
+            for m in &found {
+                index.remove(m.old_id as u64)
+                    .map_err(|e| Error::from_reason(format!("Remove failed for {}: {:?}", m.old_id, e)))?;
+                removed += 1;
+            }
+            for (m, vector) in found.iter().zip(vectors.chunks(dim)) {
+                add_vector(&index, m.new_id as u64, vector, self.quantization)
+                    .map_err(|e| Error::from_reason(format!("Add failed for {} (was {}): {:?}", m.new_id, m.old_id, e)))?;
+                added += 1;
+            }
+            Ok(())
+        })();
+
+        if let Err(e) = outcome {
+            // 撤销：删掉已写入的新 ID，再把已删除的旧 ID 写回去。集合和缩放系数此时还没有改动
+            for m in &found[..added] {
+                let _ = index.remove(m.new_id as u64);
+            }
+            for (m, vector) in found[..removed].iter().zip(vectors.chunks(dim)) {
+                if let Err(restore) = add_vector(&index, m.old_id as u64, vector, self.quantization) {
+                    println!(
+                        "[Vexus-Lite] ⚠️ reassign_ids: failed to restore {} after error: {:?}",
+                        m.old_id, restore
+                    );
+                }
+            }
+            return Err(e);
         }
 
-        for (i, id) in ids.iter().enumerate() {
-            let start = i * dim;
-            let v = &vec_slice[start..start+dim];
-            // remove + add = update (usearch 行为)
-            // let _ = index.remove(*id as u64); 
-            index.add(*id as u64, v)
-                .map_err(|e| Error::from_reason(format!("Batch add failed idx {}: {:?}", i, e)))?;
+        let moved: Vec<(Option<f32>, Option<Instant>)> = found
+            .iter()
+            .map(|m| {
+                live_ids.remove(&m.old_id);
+                (scales.remove(&m.old_id), expiries.remove(&m.old_id))
+            })
+            .collect();
+        for (m, (scale, deadline)) in found.iter().zip(moved) {
+            live_ids.insert(m.new_id);
+            if let Some(scale) = scale {
+                scales.insert(m.new_id, scale);
+            }
+            if let Some(deadline) = deadline {
+                expiries.insert(m.new_id, deadline);
+            }
         }
 
-        Ok(())
+        Ok(found.len() as u32)
     }
 
-    /// 搜索
+    /// 统计 [min_id, max_id] 区间内的向量数量 (基于存活 ID 集合，不分配完整列表)
     #[napi]
-    pub fn search(&self, query: Buffer, k: u32) -> Result<Vec<SearchResult>> {
-        let index = self.index.read()
-            .map_err(|e| Error::from_reason(format!("Lock failed: {}", e)))?;
-
-        let query_slice: &[f32] = unsafe {
-            std::slice::from_raw_parts(
-                query.as_ptr() as *const f32,
-                query.len() / std::mem::size_of::<f32>(),
-            )
-        };
+    pub fn count_in_range(&self, min_id: i64, max_id: i64) -> Result<u32> {
+        if min_id > max_id {
+            return Ok(0);
+        }
+        let ids = self.ids.read().map_err(lock_error)?;
+        Ok(ids.range(min_id..=max_id).count() as u32)
+    }
 
-        // 🔥🔥🔥【新增】维度安全检查 🔥🔥🔥
-        if query_slice.len() != self.dimensions as usize {
-            return Err(Error::from_reason(format!(
-                "Search dimension mismatch: expected {}, got {}. (Check your JS Buffer slicing!)",
-                self.dimensions,
-                query_slice.len()
-            )));
+    /// 列出 [min_id, max_id] 区间内的全部 ID (升序)
+    #[napi]
+    pub fn ids_in_range(&self, min_id: i64, max_id: i64) -> Result<Vec<i64>> {
+        if min_id > max_id {
+            return Ok(Vec::new());
         }
+        let ids = self.ids.read().map_err(lock_error)?;
+        Ok(ids.range(min_id..=max_id).copied().collect())
+    }
 
-        // 执行搜索
-        let matches = index
-            .search(query_slice, k as usize)
-            .map_err(|e| Error::from_reason(format!("Search failed: {:?}", e)))?;
+    /// 批量检查 ID 是否在索引中，只加一次读锁。返回值与 ids 等长、顺序一致，
+    /// 例如在 add_batch 之前过滤掉已存在的向量
+    #[napi]
+    pub fn batch_contains(&self, ids: Vec<i64>) -> Result<Vec<bool>> {
+        let live_ids = self.ids.read().map_err(lock_error)?;
+        Ok(ids.iter().map(|id| live_ids.contains(id)).collect())
+    }
 
-        let mut results = Vec::with_capacity(matches.keys.len());
-        
-        for (key, &dist) in matches.keys.iter().zip(matches.distances.iter()) {
-            results.push(SearchResult {
-                id: *key as u32,
-                score: 1.0 - dist as f64, // L2sq 距离转相似度分数 (近似)
-            });
-        }
+    /// 注册 add / add_batch 成功后的回调 (参数为 ID)，传 null 取消
+    #[napi]
+    pub fn set_on_add(&self, env: Env, callback: Option<JsFunction>) -> Result<()> {
+        self.hooks.write().map_err(lock_error)?.on_add = make_hook(&env, callback)?;
+        Ok(())
+    }
 
-        Ok(results)
+    /// 注册 remove 成功后的回调 (参数为 ID)，传 null 取消
+    #[napi]
+    pub fn set_on_remove(&self, env: Env, callback: Option<JsFunction>) -> Result<()> {
+        self.hooks.write().map_err(lock_error)?.on_remove = make_hook(&env, callback)?;
+        Ok(())
     }
 
-    /// 删除 (按 ID)
+    /// 注册 save 成功后的回调 (参数为索引文件路径)，传 null 取消
     #[napi]
-    pub fn remove(&self, id: u32) -> Result<()> {
-        let index = self.index.write()
-            .map_err(|e| Error::from_reason(format!("Lock failed: {}", e)))?;
-        
-        index.remove(id as u64)
-             .map_err(|e| Error::from_reason(format!("Remove failed: {:?}", e)))?;
-             
+    pub fn set_on_save(&self, env: Env, callback: Option<JsFunction>) -> Result<()> {
+        self.hooks.write().map_err(lock_error)?.on_save = make_hook(&env, callback)?;
         Ok(())
     }
 
@@ -250,39 +1842,147 @@ impl VexusIndex {
         })
     }
 
+    /// 获取索引的完整配置 (度量、量化方式、HNSW 参数) 和当前状态，只读，无副作用
+    #[napi]
+    pub fn info(&self) -> Result<IndexInfo> {
+        let index = self.index.read()
+            .map_err(|e| Error::from_reason(format!("Lock failed: {}", e)))?;
+        let metric = *self.metric.read().map_err(lock_error)?;
+
+        Ok(IndexInfo {
+            dimensions: self.dimensions,
+            metric: metric_name(metric).to_string(),
+            quantization: if self.quantization == ScalarKind::I8 { "i8" } else { "f32" }.to_string(),
+            connectivity: index.connectivity() as u32,
+            expansion_add: index.expansion_add() as u32,
+            expansion_search: index.expansion_search() as u32,
+            total_vectors: index.size() as u32,
+            capacity: index.capacity() as u32,
+            memory_usage: index.memory_usage() as u32,
+            hardware_acceleration: index.hardware_acceleration(),
+        })
+    }
+
     /// 从 SQLite 数据库恢复索引 (异步版本，不阻塞主线程)
+    /// 数据库被其他连接锁住且超过 options.connection_timeout_ms 时，返回 code 为 DB_BUSY 的错误
     #[napi]
     pub fn recover_from_sqlite(
         &self,
         db_path: String,
         table_type: String,
         filter_diary_name: Option<String>,
+        options: Option<RecoverOptions>,
     ) -> AsyncTask<RecoverTask> {
-        AsyncTask::new(RecoverTask {
+        AsyncTask::new(self.recover_task(db_path, table_type, filter_diary_name, None, options))
+    }
+
+    /// 可取消的 SQLite 恢复：每处理 1000 行检查一次 cancel_token，
+    /// 取消后停止读取并返回已恢复的部分 (已写入索引的向量保留)
+    #[napi]
+    pub fn recover_from_sqlite_async_with_cancellation(
+        &self,
+        db_path: String,
+        table_type: String,
+        filter_diary_name: Option<String>,
+        cancel_token: Option<&CancelToken>,
+        options: Option<RecoverOptions>,
+    ) -> AsyncTask<CancellableRecoverTask> {
+        let cancel = cancel_token.map(|token| token.cancelled.clone());
+        AsyncTask::new(CancellableRecoverTask(
+            self.recover_task(db_path, table_type, filter_diary_name, cancel, options),
+        ))
+    }
+}
+
+impl VexusIndex {
+    fn recover_task(
+        &self,
+        db_path: String,
+        table_type: String,
+        filter_diary_name: Option<String>,
+        cancel: Option<Arc<AtomicBool>>,
+        options: Option<RecoverOptions>,
+    ) -> RecoverTask {
+        let options = options.unwrap_or(RecoverOptions { connection_timeout_ms: None, wal: None });
+        RecoverTask {
             index: self.index.clone(),
+            ids: self.ids.clone(),
+            quantization: self.quantization,
+            scales: self.scales.clone(),
             db_path,
             table_type,
             filter_diary_name,
             dimensions: self.dimensions,
-        })
+            cancel,
+            connection_timeout_ms: options.connection_timeout_ms.map(u64::from),
+            wal: options.wal.unwrap_or(false),
+            error_code: None,
+        }
     }
 }
 
 pub struct RecoverTask {
     index: Arc<RwLock<Index>>,
+    ids: Arc<RwLock<BTreeSet<i64>>>,
+    quantization: ScalarKind,
+    scales: Arc<RwLock<HashMap<i64, f32>>>,
     db_path: String,
     table_type: String,
     filter_diary_name: Option<String>,
     dimensions: u32,
+    cancel: Option<Arc<AtomicBool>>,
+    connection_timeout_ms: Option<u64>,
+    wal: bool,
+    error_code: Option<VexusErrorCode>,  // compute 失败时记录，reject 中据此设置 JS 错误的 code
 }
 
-impl Task for RecoverTask {
-    type Output = u32;
-    type JsValue = u32;
+/// 数据库被其他连接锁住 (SQLITE_BUSY / SQLITE_LOCKED) 时返回 DB_BUSY，其余错误为 GenericFailure
+fn sqlite_error(context: &str, e: rusqlite::Error) -> Error<VexusErrorCode> {
+    let busy = matches!(
+        e.sqlite_error_code(),
+        Some(rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked)
+    );
+    let code = if busy { VexusErrorCode::DbBusy } else { VexusErrorCode::GenericFailure };
+    Error::new(code, format!("{}: {}", context, e))
+}
 
-    fn compute(&mut self) -> Result<Self::Output> {
-        let conn = Connection::open(&self.db_path)
-            .map_err(|e| Error::from_reason(format!("Failed to open DB: {}", e)))?;
+impl RecoverTask {
+    fn cancelled(&self) -> bool {
+        self.cancel.as_ref().is_some_and(|flag| flag.load(Ordering::Relaxed))
+    }
+
+    /// 执行恢复，失败时记录错误码供 reject 使用 (Task::compute 只能返回 Status 错误)
+    fn run(&mut self) -> Result<RecoverStats> {
+        self.recover().map_err(|e| {
+            self.error_code = Some(e.status);
+            Error::new(Status::GenericFailure, e.reason.clone())
+        })
+    }
+
+    /// 把 compute 的错误重新构造成带自定义 code 的 JS 错误 (如 DB_BUSY)
+    fn reject_with_code(&self, env: Env, err: Error) -> Error {
+        match self.error_code {
+            Some(code) if code != VexusErrorCode::GenericFailure => {
+                Error::from(JsError::from(Error::new(code, err.reason)).into_unknown(env))
+            }
+            _ => err,
+        }
+    }
+
+    fn recover(&self) -> std::result::Result<RecoverStats, Error<VexusErrorCode>> {
+        let conn = Connection::open(&self.db_path).map_err(|e| sqlite_error("Failed to open DB", e))?;
+        if let Some(ms) = self.connection_timeout_ms {
+            conn.busy_timeout(Duration::from_millis(ms))
+                .map_err(|e| sqlite_error("Failed to set busy timeout", e))?;
+        }
+        if self.wal {
+            let journal_mode: String = conn.query_row("PRAGMA journal_mode", [], |row| row.get(0))
+                .map_err(|e| sqlite_error("Failed to read journal mode", e))?;
+            if journal_mode.eq_ignore_ascii_case("wal") {
+                conn.query_row("PRAGMA wal_checkpoint(PASSIVE)", [], |_| Ok(()))
+                    .map_err(|e| sqlite_error("WAL checkpoint failed", e))?;
+            }
+        }
 
         let sql: String;
         
@@ -291,27 +1991,32 @@ impl Task for RecoverTask {
         } else if self.table_type == "chunks" && self.filter_diary_name.is_some() {
             sql = "SELECT c.id, c.vector FROM chunks c JOIN files f ON c.file_id = f.id WHERE f.diary_name = ?1 AND c.vector IS NOT NULL".to_string();
         } else {
-            return Ok(0);
+            return Ok(RecoverStats { recovered: 0, skipped_dim_mismatch: 0, cancelled: false });
         }
 
-        let mut stmt = conn
-            .prepare(&sql)
-            .map_err(|e| Error::from_reason(format!("Failed to prepare statement: {}", e)))?;
+        let mut stmt = conn.prepare(&sql).map_err(|e| sqlite_error("Failed to prepare statement", e))?;
 
         // 参数在下面的 query_map 调用中直接处理，这里不再需要准备 params 变量
         
         // 为了避免复杂的生命周期问题，我们简单地分别处理
         let mut count = 0;
         let mut skipped_dim_mismatch = 0;
+        let mut cancelled = false;
         let expected_byte_len = self.dimensions as usize * std::mem::size_of::<f32>();
         
         // 获取写锁
-        let index = self.index.write()
-            .map_err(|e| Error::from_reason(format!("Lock failed: {}", e)))?;
+        let lock_failed = |reason: String| Error::new(VexusErrorCode::GenericFailure, format!("Lock failed: {}", reason));
+        let index = self.index.write().map_err(|e| lock_failed(e.to_string()))?;
+        let mut live_ids = self.ids.write().map_err(|e| lock_failed(e.to_string()))?;
+        let mut scales = self.scales.write().map_err(|e| lock_failed(e.to_string()))?;
+        let quantization = self.quantization;
 
         // 定义处理单行的闭包
+        let mut skipped_negative_id = 0;
         let mut process_row = |id: i64, vector_bytes: Vec<u8>| {
-             if vector_bytes.len() == expected_byte_len {
+             if id < 0 {
+                skipped_negative_id += 1;
+             } else if vector_bytes.len() == expected_byte_len {
                 let vec_slice: &[f32] = unsafe {
                     std::slice::from_raw_parts(
                         vector_bytes.as_ptr() as *const f32,
@@ -324,7 +2029,11 @@ impl Task for RecoverTask {
                     let _ = index.reserve(new_cap);
                 }
 
-                if index.add(id as u64, vec_slice).is_ok() {
+                if add_vector(&index, id as u64, vec_slice, quantization).is_ok() {
+                    live_ids.insert(id);
+                    if quantization == ScalarKind::I8 {
+                        scales.insert(id, vector_scale(vec_slice));
+                    }
                     count += 1;
                 }
             } else {
@@ -334,20 +2043,42 @@ impl Task for RecoverTask {
 
         if let Some(name) = &self.filter_diary_name {
             let rows = stmt.query_map([name], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, Vec<u8>>(1)?)))
-                .map_err(|e| Error::from_reason(format!("Query failed: {}", e)))?;
+                .map_err(|e| sqlite_error("Query failed", e))?;
             
-            for row_result in rows {
-                if let Ok((id, vector_bytes)) = row_result {
-                    process_row(id, vector_bytes);
+            for (i, row) in rows.enumerate() {
+                if i % 1000 == 0 && self.cancelled() {
+                    cancelled = true;
+                    break;
+                }
+                // 读取单行失败 (类型不符等) 时跳过该行，数据库被锁住时整体失败
+                match row {
+                    Ok((id, vector_bytes)) => process_row(id, vector_bytes),
+                    Err(e) => {
+                        let e = sqlite_error("Query failed", e);
+                        if e.status == VexusErrorCode::DbBusy {
+                            return Err(e);
+                        }
+                    }
                 }
             }
         } else {
             let rows = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, Vec<u8>>(1)?)))
-                .map_err(|e| Error::from_reason(format!("Query failed: {}", e)))?;
+                .map_err(|e| sqlite_error("Query failed", e))?;
             
-            for row_result in rows {
-                if let Ok((id, vector_bytes)) = row_result {
-                    process_row(id, vector_bytes);
+            for (i, row) in rows.enumerate() {
+                if i % 1000 == 0 && self.cancelled() {
+                    cancelled = true;
+                    break;
+                }
+                // 读取单行失败 (类型不符等) 时跳过该行，数据库被锁住时整体失败
+                match row {
+                    Ok((id, vector_bytes)) => process_row(id, vector_bytes),
+                    Err(e) => {
+                        let e = sqlite_error("Query failed", e);
+                        if e.status == VexusErrorCode::DbBusy {
+                            return Err(e);
+                        }
+                    }
                 }
             }
         }
@@ -356,11 +2087,53 @@ impl Task for RecoverTask {
             // 这里使用 println!，它会输出到 Node.js 的 stdout
             println!("[Vexus-Lite] ⚠️ Skipped {} vectors due to dimension mismatch (Expected {} bytes, got various)", skipped_dim_mismatch, expected_byte_len);
         }
+        if skipped_negative_id > 0 {
+            println!("[Vexus-Lite] ⚠️ Skipped {} rows with negative ids", skipped_negative_id);
+        }
+        if cancelled {
+            println!("[Vexus-Lite] ⚠️ Recovery cancelled after {} vectors", count);
+        }
+
+        Ok(RecoverStats { recovered: count, skipped_dim_mismatch, cancelled })
+    }
+}
+
+impl Task for RecoverTask {
+    type Output = u32;
+    type JsValue = u32;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        self.run().map(|stats| stats.recovered)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output)
+    }
+
+    fn reject(&mut self, env: Env, err: Error) -> Result<Self::JsValue> {
+        Err(self.reject_with_code(env, err))
+    }
+}
+
+/// recover_from_sqlite_async_with_cancellation 的任务，结果带 cancelled 标志
+pub struct CancellableRecoverTask(RecoverTask);
 
-        Ok(count)
+impl Task for CancellableRecoverTask {
+    type Output = RecoverStats;
+    type JsValue = RecoverStats;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        self.0.run()
     }
 
     fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
         Ok(output)
     }
-}
\ No newline at end of file
+
+    fn reject(&mut self, env: Env, err: Error) -> Result<Self::JsValue> {
+        Err(self.0.reject_with_code(env, err))
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,995 @@
+use super::*;
+use napi::{sys, NapiValue};
+use std::sync::Mutex;
+
+// 单元测试不经过 Node，这里补上被 napi 引用到的 N-API 符号让测试程序能够链接和加载。
+// 测试只用由 Vec 构造的 Buffer，不会真正调用它们
+macro_rules! napi_stubs {
+    ($($name:ident),* $(,)?) => {
+        $(
+            #[no_mangle]
+            extern "C" fn $name() -> i32 {
+                eprintln!(concat!(stringify!($name), " called outside Node"));
+                std::process::abort()
+            }
+        )*
+    };
+}
+
+napi_stubs!(
+    napi_call_function,
+    napi_coerce_to_string,
+    napi_create_error,
+    napi_create_int64,
+    napi_create_reference,
+    napi_delete_reference,
+    napi_fatal_error,
+    napi_fatal_exception,
+    napi_get_and_clear_last_exception,
+    napi_get_null,
+    napi_get_reference_value,
+    napi_get_undefined,
+    napi_get_value_string_utf8,
+    napi_is_error,
+    napi_is_exception_pending,
+    napi_reference_unref,
+    napi_throw,
+);
+
+// 事件回调测试用的 ThreadsafeFunction 假实现：每次创建分配一个新句柄，
+// 记录每个句柄被 call 的次数以及是否已经 release
+static TSFN_CALLS: Mutex<Vec<(usize, bool)>> = Mutex::new(Vec::new());
+
+#[no_mangle]
+extern "C" fn napi_create_string_utf8(
+    _env: sys::napi_env,
+    _str: *const std::os::raw::c_char,
+    _length: usize,
+    result: *mut sys::napi_value,
+) -> sys::napi_status {
+    unsafe { *result = std::ptr::null_mut() };
+    sys::Status::napi_ok
+}
+
+#[no_mangle]
+extern "C" fn napi_create_threadsafe_function(
+    _env: sys::napi_env,
+    _func: sys::napi_value,
+    _async_resource: sys::napi_value,
+    _async_resource_name: sys::napi_value,
+    _max_queue_size: usize,
+    _initial_thread_count: usize,
+    _thread_finalize_data: *mut std::ffi::c_void,
+    _thread_finalize_cb: sys::napi_finalize,
+    _context: *mut std::ffi::c_void,
+    _call_js_cb: sys::napi_threadsafe_function_call_js,
+    result: *mut sys::napi_threadsafe_function,
+) -> sys::napi_status {
+    let mut calls = TSFN_CALLS.lock().unwrap();
+    calls.push((0, false));
+    // 句柄即 TSFN_CALLS 中的下标加一，保证非空
+    unsafe { *result = calls.len() as sys::napi_threadsafe_function };
+    sys::Status::napi_ok
+}
+
+#[no_mangle]
+extern "C" fn napi_unref_threadsafe_function(
+    _env: sys::napi_env,
+    _func: sys::napi_threadsafe_function,
+) -> sys::napi_status {
+    sys::Status::napi_ok
+}
+
+#[no_mangle]
+extern "C" fn napi_call_threadsafe_function(
+    func: sys::napi_threadsafe_function,
+    _data: *mut std::ffi::c_void,
+    _is_blocking: sys::napi_threadsafe_function_call_mode,
+) -> sys::napi_status {
+    TSFN_CALLS.lock().unwrap()[func as usize - 1].0 += 1;
+    sys::Status::napi_ok
+}
+
+#[no_mangle]
+extern "C" fn napi_release_threadsafe_function(
+    func: sys::napi_threadsafe_function,
+    _mode: sys::napi_threadsafe_function_release_mode,
+) -> sys::napi_status {
+    TSFN_CALLS.lock().unwrap()[func as usize - 1].1 = true;
+    sys::Status::napi_ok
+}
+
+/// 经 make_hook 创建一个假的回调，返回回调与其在 TSFN_CALLS 中的下标
+fn fake_hook<T: ToNapiValue + 'static>() -> (ThreadsafeFunction<T, ErrorStrategy::Fatal>, usize) {
+    let raw_env = std::ptr::NonNull::<sys::napi_env__>::dangling().as_ptr();
+    let env = unsafe { Env::from_raw(raw_env) };
+    let callback = unsafe { JsFunction::from_raw_unchecked(raw_env, std::ptr::null_mut()) };
+    let hook = make_hook(&env, Some(callback)).unwrap().unwrap();
+    let slot = hook.raw() as usize - 1;
+    (hook, slot)
+}
+
+fn tsfn_state(slot: usize) -> (usize, bool) {
+    TSFN_CALLS.lock().unwrap()[slot]
+}
+
+fn to_buffer(vector: &[f32]) -> Buffer {
+    vector.iter().flat_map(|v| v.to_le_bytes()).collect::<Vec<u8>>().into()
+}
+
+fn from_buffer(buffer: &Buffer) -> Vec<f32> {
+    buffer.chunks(4).map(|b| f32::from_le_bytes(b.try_into().unwrap())).collect()
+}
+
+/// 每个 ID 对应一个可辨认的向量：第一个分量为 ID 本身
+fn id_vector(id: i64, dim: usize) -> Vec<f32> {
+    let mut vector = vec![1.0f32; dim];
+    vector[0] = id as f32;
+    vector
+}
+
+fn index_with_ids(dim: u32, ids: &[i64]) -> VexusIndex {
+    let index = VexusIndex::new(dim, 16, None, None).unwrap();
+    for &id in ids {
+        index.add(id, to_buffer(&id_vector(id, dim as usize))).unwrap();
+    }
+    index
+}
+
+fn live_ids(index: &VexusIndex) -> Vec<i64> {
+    index.ids_in_range(i64::MIN, i64::MAX).unwrap()
+}
+
+/// 测试用的临时文件路径，Drop 时删除
+struct TempPath(std::path::PathBuf);
+
+impl TempPath {
+    fn new(name: &str) -> Self {
+        static NEXT: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+        let n = NEXT.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        TempPath(std::env::temp_dir().join(format!("vexus-test-{}-{}-{}", std::process::id(), n, name)))
+    }
+
+    fn as_string(&self) -> String {
+        self.0.to_string_lossy().into_owned()
+    }
+}
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+        let _ = std::fs::remove_file(state_path(&self.as_string()));
+    }
+}
+
+#[test]
+fn set_metric_rebuilds_with_the_new_metric() {
+    let index = VexusIndex::new(3, 16, None, None).unwrap();
+    let vectors = [(1, [1.0f32, 0.0, 0.0]), (2, [0.0, 3.0, 0.0]), (3, [0.0, 0.0, 5.0]), (4, [2.0, 2.0, 0.0])];
+    for (id, vector) in vectors {
+        index.add(id, to_buffer(&vector)).unwrap();
+    }
+    let top1 = |index: &VexusIndex, query: &[f32]| index.search(to_buffer(query), 1).unwrap()[0].id;
+
+    // 缩放过的查询在 L2 下离原向量很远，在余弦下与原向量重合
+    assert_eq!(top1(&index, &[0.0, 0.0, 0.5]), 1);
+    let stats = index.set_metric("cos".to_string()).unwrap();
+    assert_eq!(stats.vector_count, 4);
+    assert_eq!(index.stats().unwrap().total_vectors, 4);
+    for (id, vector) in vectors {
+        let results = index.search(to_buffer(&vector), 1).unwrap();
+        assert_eq!(results[0].id, id);
+        assert!((results[0].score - 1.0).abs() < 1e-5, "{}: {}", id, results[0].score);
+    }
+    assert_eq!(top1(&index, &[0.0, 0.0, 0.5]), 3);
+    assert_eq!(live_ids(&index), vec![1, 2, 3, 4]);
+
+    assert!(index.set_metric("hamming".to_string()).is_err());
+    assert_eq!(index.stats().unwrap().total_vectors, 4);
+}
+
+#[test]
+fn ids_above_u32_round_trip() {
+    let index = VexusIndex::new(3, 16, None, None).unwrap();
+    let big = (1i64 << 40) + 7;
+    index.add(big, to_buffer(&[1.0, 0.0, 0.0])).unwrap();
+    index.add_batch(vec![1, u32::MAX as i64 + 1], to_buffer(&[0.0, 1.0, 0.0, 0.0, 0.0, 1.0])).unwrap();
+
+    assert_eq!(index.search(to_buffer(&[1.0, 0.0, 0.0]), 1).unwrap()[0].id, big);
+    assert_eq!(index.search(to_buffer(&[0.0, 0.0, 1.0]), 1).unwrap()[0].id, u32::MAX as i64 + 1);
+    index.remove(big).unwrap();
+    assert_eq!(index.stats().unwrap().total_vectors, 2);
+}
+
+#[test]
+fn negative_ids_are_rejected_on_write() {
+    let index = VexusIndex::new(3, 16, None, None).unwrap();
+    let vector = [1.0f32, 0.0, 0.0];
+
+    let err = index.add(-1, to_buffer(&vector)).unwrap_err();
+    assert!(err.reason.contains("Invalid id -1"), "{}", err.reason);
+    let batch: Vec<f32> = [vector, vector].concat();
+    assert!(index.add_batch(vec![1, -2], to_buffer(&batch)).is_err());
+    assert!(index.parallel_add_batch(vec![1, -2], to_buffer(&batch), 2).is_err());
+    assert_eq!(index.stats().unwrap().total_vectors, 0);
+
+    // validate_and_add 系列不报错，而是把负数 ID 作为拒绝原因返回
+    let result = index.validate_and_add(-3, to_buffer(&vector)).unwrap();
+    assert!(!result.added);
+    assert_eq!(result.reason.as_deref(), Some("negative id -3"));
+    let results = index.validate_and_add_batch(vec![-4, 6], to_buffer(&batch)).unwrap();
+    assert_eq!(results.iter().map(|r| r.added).collect::<Vec<_>>(), vec![false, true]);
+    assert_eq!(live_ids(&index), vec![6]);
+    index.remove(6).unwrap();
+
+    index.add(5, to_buffer(&vector)).unwrap();
+    assert!(index.reassign_ids(mapping(&[(5, -5)])).is_err());
+    assert_eq!(live_ids(&index), vec![5]);
+}
+
+#[test]
+fn load_and_verify_rejects_a_corrupted_index() {
+    let index = VexusIndex::new(3, 16, None, None).unwrap();
+    index.add_batch(vec![1, 2], to_buffer(&[1.0, 0.0, 0.0, 0.0, 1.0, 0.0])).unwrap();
+    let path = TempPath::new("verify.usearch");
+    let meta = TempPath::new("verify.meta.json");
+    index.save(path.as_string(), Some(meta.as_string())).unwrap();
+
+    let loaded = VexusIndex::load_and_verify(path.as_string(), meta.as_string(), 3).unwrap();
+    assert_eq!(loaded.stats().unwrap().total_vectors, 2);
+    assert_eq!(loaded.search(to_buffer(&[0.0, 1.0, 0.0]), 1).unwrap()[0].id, 2);
+
+    let err = VexusIndex::load_and_verify(path.as_string(), meta.as_string(), 4).err().unwrap();
+    assert!(err.reason.contains("Dimension mismatch"), "{}", err.reason);
+
+    let mut bytes = std::fs::read(&path.0).unwrap();
+    let last = bytes.len() - 1;
+    bytes[last] ^= 0xff;
+    std::fs::write(&path.0, bytes).unwrap();
+    let err = VexusIndex::load_and_verify(path.as_string(), meta.as_string(), 3).err().unwrap();
+    assert!(err.reason.starts_with("Checksum mismatch"), "{}", err.reason);
+}
+
+#[test]
+fn search_with_timeout_reports_search_timeout() {
+    let index = VexusIndex::new(3, 16, None, None).unwrap();
+    index.add_batch(vec![1, 2], to_buffer(&[1.0, 0.0, 0.0, 0.0, 1.0, 0.0])).unwrap();
+    let results = index.search_with_timeout(to_buffer(&[0.0, 1.0, 0.0]), 1, 5_000).ok().unwrap();
+    assert_eq!(results[0].id, 2);
+
+    // 持有写锁让搜索线程一直等待，超时后再放开
+    let guard = index.index.write().unwrap();
+    let err = index.search_with_timeout(to_buffer(&[0.0, 1.0, 0.0]), 1, 20).err().unwrap();
+    drop(guard);
+    assert_eq!(err.status.as_ref(), "SEARCH_TIMEOUT");
+    assert!(err.reason.contains("within 20ms"), "{}", err.reason);
+
+    let err = index.search_with_timeout(to_buffer(&[0.0, 1.0]), 1, 5_000).err().unwrap();
+    assert_eq!(err.status.as_ref(), "GenericFailure");
+}
+
+#[test]
+fn save_writes_id_state_used_by_load() {
+    let ids = [0, 3, 7, 1 << 40];
+    let index = index_with_ids(4, &ids);
+    index.remove(3).unwrap();
+    let path = TempPath::new("state.usearch");
+    index.save(path.as_string(), None).unwrap();
+
+    let state: IndexState = bincode::deserialize(&std::fs::read(state_path(&path.as_string())).unwrap()).unwrap();
+    assert_eq!(state.ids, vec![0, 7, 1 << 40]);
+
+    let loaded = VexusIndex::load(path.as_string(), None, 4, 16).unwrap();
+    assert_eq!(live_ids(&loaded), vec![0, 7, 1 << 40]);
+    assert_eq!(loaded.count_in_range(0, 10).unwrap(), 2);
+}
+
+#[test]
+fn load_falls_back_when_state_is_missing_or_stale() {
+    let index = index_with_ids(4, &[1, 2, 3]);
+    let path = TempPath::new("stale.usearch");
+    index.save(path.as_string(), None).unwrap();
+
+    let stale = IndexState {
+        version: INDEX_STATE_VERSION,
+        ids: vec![1, 2, 99],
+        quantization: "f32".to_string(),
+        scales: Vec::new(),
+    };
+    std::fs::write(state_path(&path.as_string()), bincode::serialize(&stale).unwrap()).unwrap();
+    let loaded = VexusIndex::load(path.as_string(), None, 4, 16).unwrap();
+    assert_eq!(live_ids(&loaded), vec![1, 2, 3]);
+
+    std::fs::write(state_path(&path.as_string()), b"not bincode").unwrap();
+    let loaded = VexusIndex::load(path.as_string(), None, 4, 16).unwrap();
+    assert_eq!(live_ids(&loaded), vec![1, 2, 3]);
+
+    std::fs::remove_file(state_path(&path.as_string())).unwrap();
+    let loaded = VexusIndex::load(path.as_string(), None, 4, 16).unwrap();
+    assert_eq!(live_ids(&loaded), vec![1, 2, 3]);
+}
+
+#[test]
+fn range_queries_follow_adds_and_removes() {
+    let index = index_with_ids(4, &[1, 5, 9, 1 << 40]);
+    index.remove(5).unwrap();
+    assert_eq!(index.count_in_range(0, 10).unwrap(), 2);
+    assert_eq!(index.ids_in_range(2, 1 << 41).unwrap(), vec![9, 1 << 40]);
+    assert_eq!(index.count_in_range(10, 0).unwrap(), 0);
+    assert!(index.ids_in_range(10, 0).unwrap().is_empty());
+}
+
+fn mapping(pairs: &[(i64, i64)]) -> Vec<IdMapping> {
+    pairs.iter().map(|&(old_id, new_id)| IdMapping { old_id, new_id }).collect()
+}
+
+#[test]
+fn reassign_ids_handles_chains() {
+    let index = index_with_ids(4, &[1, 3, 5]);
+    // 3→1 依赖 1→0 先腾出 1，5→3 依赖 3→1 先腾出 3
+    assert_eq!(index.reassign_ids(mapping(&[(5, 3), (3, 1), (1, 0), (9, 10)])).unwrap(), 3);
+    assert_eq!(live_ids(&index), vec![0, 1, 3]);
+    for (new_id, old_id) in [(0, 1), (1, 3), (3, 5)] {
+        let results = index.search(to_buffer(&id_vector(old_id, 4)), 1).unwrap();
+        assert_eq!(results[0].id, new_id);
+        assert!((results[0].score - 1.0).abs() < 1e-5, "{}: {}", new_id, results[0].score);
+    }
+    assert_eq!(index.stats().unwrap().total_vectors, 3);
+}
+
+#[test]
+fn reassign_ids_rejects_conflicts_without_changes() {
+    let index = index_with_ids(4, &[1, 2, 3]);
+    let rejected = [
+        (vec![(1, 10), (1, 11)], "old id 1 appears more than once"),
+        (vec![(1, 10), (2, 10)], "new id 10 is assigned more than once"),
+        // 未找到的旧 ID 也参与重复检查
+        (vec![(1, 10), (9, 10)], "new id 10 is assigned more than once"),
+        (vec![(1, 3)], "new id 3 is already in use"),
+    ];
+    for (pairs, message) in rejected {
+        let err = index.reassign_ids(mapping(&pairs)).unwrap_err();
+        assert!(err.reason.contains(message), "{:?}: {}", pairs, err.reason);
+        assert_eq!(live_ids(&index), vec![1, 2, 3]);
+        assert_eq!(index.stats().unwrap().total_vectors, 3);
+    }
+}
+
+#[test]
+fn reassign_ids_grows_a_full_index() {
+    let index = VexusIndex::new(4, 1, None, None).unwrap();
+    for id in 0..8 {
+        index.add(id, to_buffer(&id_vector(id, 4))).unwrap();
+    }
+    let pairs: Vec<(i64, i64)> = (0..8).map(|id| (id, id + 100)).collect();
+    assert_eq!(index.reassign_ids(mapping(&pairs)).unwrap(), 8);
+    assert_eq!(live_ids(&index), (100..108).collect::<Vec<_>>());
+    let results = index.search(to_buffer(&id_vector(7, 4)), 1).unwrap();
+    assert_eq!(results[0].id, 107);
+}
+
+#[test]
+fn event_hooks_fire_after_writes_and_release_on_drop() {
+    let dim = 4;
+    let index = index_with_ids(dim, &[]);
+    let (on_add, add_slot) = fake_hook();
+    let (on_remove, remove_slot) = fake_hook();
+    let (on_save, save_slot) = fake_hook();
+    {
+        let mut hooks = index.hooks.write().unwrap();
+        hooks.on_add = Some(on_add);
+        hooks.on_remove = Some(on_remove);
+        hooks.on_save = Some(on_save);
+    }
+
+    index.add(1, to_buffer(&id_vector(1, dim as usize))).unwrap();
+    let batch: Vec<f32> = [2, 3].iter().flat_map(|&id| id_vector(id, dim as usize)).collect();
+    index.add_batch(vec![2, 3], to_buffer(&batch)).unwrap();
+    assert_eq!(tsfn_state(add_slot), (3, false));
+
+    index.remove(2).unwrap();
+    // 不存在的 ID 不触发 on_remove
+    index.remove(99).unwrap();
+    assert_eq!(tsfn_state(remove_slot), (1, false));
+
+    let path = TempPath::new("hooks.usearch");
+    index.save(path.as_string(), None).unwrap();
+    assert_eq!(tsfn_state(save_slot), (1, false));
+
+    // 替换回调时旧的 ThreadsafeFunction 被释放，索引销毁时其余回调也被释放
+    index.hooks.write().unwrap().on_add = None;
+    assert_eq!(tsfn_state(add_slot), (3, true));
+    drop(index);
+    assert!(tsfn_state(remove_slot).1);
+    assert!(tsfn_state(save_slot).1);
+}
+
+#[test]
+fn intersect_search_keeps_ids_found_by_every_query() {
+    let index = VexusIndex::new(3, 16, None, None).unwrap();
+    let vectors = [(1, [1.0f32, 0.0, 0.0]), (2, [0.0, 1.0, 0.0]), (3, [0.7, 0.7, 0.0]), (4, [0.0, 0.0, 1.0]), (5, [0.0, 0.0, 0.9])];
+    for (id, vector) in vectors {
+        index.add(id, to_buffer(&vector)).unwrap();
+    }
+    let ids = |results: Vec<SearchResult>| {
+        let mut ids: Vec<i64> = results.into_iter().map(|r| r.id).collect();
+        ids.sort();
+        ids
+    };
+
+    let x = to_buffer(&[1.0, 0.0, 0.0]);
+    let y = to_buffer(&[0.0, 1.0, 0.0]);
+    let z = to_buffer(&[0.0, 0.0, 1.0]);
+    assert_eq!(ids(index.intersect_search(vec![x.clone(), y], 2, None).unwrap()), vec![3]);
+
+    // x 的 top-2 为 {1, 3}，z 的为 {4, 5}。交集为空时默认返回空结果；
+    // fallback_to_union 时返回并集中分数最高的 k 个
+    assert!(index.intersect_search(vec![x.clone(), z.clone()], 2, None).unwrap().is_empty());
+    assert_eq!(ids(index.intersect_search(vec![x.clone(), z], 2, Some(true)).unwrap()), vec![1, 4]);
+
+    assert!(index.intersect_search(vec![], 2, None).is_err());
+    assert!(index.intersect_search(vec![x, to_buffer(&[1.0, 0.0])], 2, None).is_err());
+}
+
+#[test]
+fn parallel_add_batch_adds_every_id_and_counts_failures() {
+    let dim = 8;
+    let index = VexusIndex::new(dim, 16, None, None).unwrap();
+    let ids: Vec<i64> = (1..=500).collect();
+    let vectors: Vec<f32> = ids.iter().flat_map(|&id| id_vector(id, dim as usize)).collect();
+    assert_eq!(index.parallel_add_batch(ids.clone(), to_buffer(&vectors), 4).unwrap(), 500);
+    assert_eq!(live_ids(&index), ids);
+    assert_eq!(index.stats().unwrap().total_vectors, 500);
+    // HNSW 是近似搜索，多线程插入的图结构不固定，只要求在前几个结果中找到自己
+    for id in [1, 250, 500] {
+        let found = index.search(to_buffer(&id_vector(id, dim as usize)), 5).unwrap();
+        assert!(found.iter().any(|r| r.id == id), "{} not found in {:?}", id, found.iter().map(|r| r.id).collect::<Vec<_>>());
+    }
+
+    // 已存在的 ID 插入失败，只计入成功的数量
+    let again: Vec<i64> = vec![10, 501, 20];
+    let vectors: Vec<f32> = again.iter().flat_map(|&id| id_vector(id, dim as usize)).collect();
+    assert_eq!(index.parallel_add_batch(again, to_buffer(&vectors), 2).unwrap(), 1);
+    assert_eq!(index.stats().unwrap().total_vectors, 501);
+}
+
+/// 吞吐量随线程数的变化，数据量较大，默认不运行：
+/// cargo test --release parallel_add_batch_throughput -- --ignored --nocapture
+#[test]
+#[ignore]
+fn parallel_add_batch_throughput() {
+    let dim = 128;
+    let count = 100_000;
+    let ids: Vec<i64> = (0..count).collect();
+    // 简单的线性同余序列生成可复现的伪随机向量
+    let mut state = 12345u64;
+    let vectors: Vec<f32> = (0..count as usize * dim)
+        .map(|_| {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (state >> 40) as f32 / (1u64 << 24) as f32
+        })
+        .collect();
+
+    for threads in [1, 2, 4, 8] {
+        let index = VexusIndex::new(dim as u32, count as u32, None, None).unwrap();
+        let start = Instant::now();
+        let added = index.parallel_add_batch(ids.clone(), to_buffer(&vectors), threads).unwrap();
+        let elapsed = start.elapsed();
+        assert_eq!(added as i64, count);
+        println!(
+            "threads={} elapsed={:.2}s throughput={:.0} vectors/s",
+            threads,
+            elapsed.as_secs_f64(),
+            count as f64 / elapsed.as_secs_f64()
+        );
+    }
+}
+
+#[test]
+fn search_diverse_skips_near_duplicates() {
+    let index = VexusIndex::new(3, 16, None, None).unwrap();
+    // 1..=3 几乎重合且离查询最近，4 稍远但方向不同
+    let vectors = [
+        (1, [1.0f32, 0.0, 0.0]),
+        (2, [1.0, 0.001, 0.0]),
+        (3, [1.0, 0.0, 0.001]),
+        (4, [0.6, 0.8, 0.0]),
+    ];
+    for (id, vector) in vectors {
+        index.add(id, to_buffer(&vector)).unwrap();
+    }
+    let query = to_buffer(&[1.0, 0.1, 0.0]);
+
+    let plain: Vec<i64> = index.search_diverse(query.clone(), 2, 1.0).unwrap().iter().map(|r| r.id).collect();
+    assert!(!plain.contains(&4), "{:?}", plain);
+
+    let diverse: Vec<i64> = index.search_diverse(query.clone(), 2, 0.5).unwrap().iter().map(|r| r.id).collect();
+    assert_eq!(diverse.len(), 2);
+    assert!(diverse.contains(&4), "{:?}", diverse);
+    assert_eq!(diverse.iter().filter(|id| **id <= 3).count(), 1, "{:?}", diverse);
+
+    // 分数仍是原始搜索分数，与 search 一致
+    let scores: HashMap<i64, f64> = index.search(query.clone(), 4).unwrap().into_iter().map(|r| (r.id, r.score)).collect();
+    for result in index.search_diverse(query, 2, 0.5).unwrap() {
+        assert_eq!(result.score, scores[&result.id]);
+    }
+}
+
+#[test]
+fn validate_and_add_rejects_unsafe_vectors() {
+    let index = VexusIndex::new(3, 16, None, None).unwrap();
+    let cases: [(&[f32], &str); 4] = [
+        (&[1.0, f32::NAN, 0.0], "NaN at position 1"),
+        (&[f32::INFINITY, 0.0, 0.0], "Inf at position 0"),
+        (&[0.0, 0.0, 0.0], "zero-norm vector"),
+        (&[1.0, 0.0], "dimension mismatch: expected 3, got 2"),
+    ];
+    for (vector, reason) in cases {
+        let result = index.validate_and_add(1, to_buffer(vector)).unwrap();
+        assert!(!result.added, "{}", reason);
+        assert_eq!(result.reason.as_deref(), Some(reason));
+    }
+    assert_eq!(index.stats().unwrap().total_vectors, 0);
+
+    assert!(index.validate_and_add(1, to_buffer(&[1.0, 0.0, 0.0])).unwrap().added);
+
+    // 批量版本逐个给出结果，只写入合格的向量
+    let batch: Vec<f32> = [[0.0, 1.0, 0.0], [f32::NAN, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0, 1.0]].concat();
+    let results = index.validate_and_add_batch(vec![2, 3, 4, 5], to_buffer(&batch)).unwrap();
+    let reasons: Vec<Option<&str>> = results.iter().map(|r| r.reason.as_deref()).collect();
+    assert_eq!(reasons, vec![None, Some("NaN at position 0"), Some("zero-norm vector"), None]);
+    assert_eq!(live_ids(&index), vec![1, 2, 5]);
+    assert!(index.validate_and_add_batch(vec![6], to_buffer(&[1.0, 0.0])).is_err());
+}
+
+fn index_with_metric(dim: u32, metric: &str) -> VexusIndex {
+    let index = VexusIndex::new(dim, 16, None, None).unwrap();
+    index.set_metric(metric.to_string()).unwrap();
+    index
+}
+
+#[test]
+fn load_partial_keeps_source_metric() {
+    let vectors = [(1, [1.0f32, 0.0, 0.0]), (2, [0.0, 2.0, 0.0]), (3, [3.0, 3.0, 0.0])];
+    for metric in ["cosine", "ip", "l2sq"] {
+        let index = index_with_metric(3, metric);
+        for (id, vector) in vectors {
+            index.add(id, to_buffer(&vector)).unwrap();
+        }
+        let path = TempPath::new("partial.usearch");
+        index.save(path.as_string(), None).unwrap();
+
+        let partial = VexusIndex::load_partial(path.as_string(), vec![2, 3, 42], 3).unwrap();
+        assert_eq!(live_ids(&partial), vec![2, 3]);
+
+        let query = [1.0f32, 1.0, 0.0];
+        let full: Vec<(i64, f64)> = index
+            .search(to_buffer(&query), 3)
+            .unwrap()
+            .into_iter()
+            .filter(|r| r.id != 1)
+            .map(|r| (r.id, r.score))
+            .collect();
+        let loaded: Vec<(i64, f64)> =
+            partial.search(to_buffer(&query), 2).unwrap().into_iter().map(|r| (r.id, r.score)).collect();
+        assert_eq!(full.len(), loaded.len());
+        for ((full_id, full_score), (id, score)) in full.iter().zip(&loaded) {
+            assert_eq!(full_id, id, "{}", metric);
+            assert!((full_score - score).abs() < 1e-5, "{}: {} vs {}", metric, full_score, score);
+        }
+    }
+}
+
+#[test]
+fn i8_scales_survive_save_and_load() {
+    let index = VexusIndex::new(4, 16, Some("i8".to_string()), None).unwrap();
+    let vectors = [(1, [100.0f32, -50.0, 25.0, 0.0]), (2, [0.5, 0.25, -0.125, 0.0])];
+    for (id, vector) in vectors {
+        index.add(id, to_buffer(&vector)).unwrap();
+    }
+    let path = TempPath::new("i8.usearch");
+    index.save(path.as_string(), None).unwrap();
+
+    let loaded = VexusIndex::load(path.as_string(), None, 4, 16).unwrap();
+    assert!(loaded.quantization == ScalarKind::I8);
+    for (id, vector) in vectors {
+        let restored = from_buffer(&loaded.get_vector(id).unwrap().unwrap());
+        for (r, v) in restored.iter().zip(vector) {
+            assert!((r - v).abs() <= v.abs().max(vector[0].abs()) / 127.0, "{}: {:?}", id, restored);
+        }
+    }
+    // 写入新向量时按 i8 量化，与原索引的搜索结果一致
+    loaded.add(3, to_buffer(&[-1.0, 2.0, 0.0, 0.0])).unwrap();
+    assert_eq!(loaded.search(to_buffer(&[-1.0, 2.0, 0.0, 0.0]), 1).unwrap()[0].id, 3);
+}
+
+#[test]
+fn i8_index_stores_about_a_quarter_of_the_bytes() {
+    let dim = 1024;
+    let count = 400;
+    // memory_usage 包含按线程预分配的固定开销，且按块分配，少量向量时看不出差别；
+    // 这里用序列化大小 (即向量和图实际占用的字节数) 比较
+    let stored_bytes = |quantization: &str| {
+        let index = VexusIndex::new(dim, count, Some(quantization.to_string()), None).unwrap();
+        let vectors: Vec<f32> = (0..count * dim).map(|i| ((i * 7919) % 1000) as f32 / 1000.0 - 0.5).collect();
+        index.add_batch((0..count as i64).collect(), to_buffer(&vectors)).unwrap();
+        index.to_buffer().unwrap().len() as f64
+    };
+    let (f32_bytes, i8_bytes) = (stored_bytes("f32"), stored_bytes("i8"));
+    // 图结构两者相同，向量部分 i8 为 1/4；维度越高越接近 1/4
+    let ratio = i8_bytes / f32_bytes;
+    assert!(ratio < 0.3, "i8 {} vs f32 {} bytes (ratio {:.2})", i8_bytes, f32_bytes, ratio);
+    assert!(f32_bytes >= (count * dim * 4) as f64);
+}
+
+#[test]
+fn reassign_ids_moves_i8_scales() {
+    let index = VexusIndex::new(4, 16, Some("i8".to_string()), None).unwrap();
+    index.add(1, to_buffer(&[50.0, -25.0, 10.0, 5.0])).unwrap();
+    index.reassign_ids(mapping(&[(1, 2)])).unwrap();
+    let restored = from_buffer(&index.get_vector(2).unwrap().unwrap());
+    assert!((restored[0] - 50.0).abs() < 0.5, "{:?}", restored);
+}
+
+#[test]
+fn load_partial_keeps_i8_scales() {
+    let index = VexusIndex::new(4, 16, Some("i8".to_string()), None).unwrap();
+    let vectors = [(1, [100.0f32, -50.0, 25.0, 0.0]), (2, [0.5, 0.25, -0.125, 0.0]), (3, [0.0, 0.0, 7.0, 1.0])];
+    for (id, vector) in vectors {
+        index.add(id, to_buffer(&vector)).unwrap();
+    }
+    let path = TempPath::new("i8_partial.usearch");
+    index.save(path.as_string(), None).unwrap();
+
+    let partial = VexusIndex::load_partial(path.as_string(), vec![1, 2], 4).unwrap();
+    assert!(partial.quantization == ScalarKind::I8);
+    assert_eq!(live_ids(&partial), vec![1, 2]);
+    for (id, vector) in &vectors[..2] {
+        let restored = from_buffer(&partial.get_vector(*id).unwrap().unwrap());
+        let original = from_buffer(&index.get_vector(*id).unwrap().unwrap());
+        assert_eq!(restored, original, "{}", id);
+        for (r, v) in restored.iter().zip(vector) {
+            assert!((r - v).abs() <= v.abs().max(vector[0].abs()) / 127.0, "{}: {:?}", id, restored);
+        }
+    }
+    let query = to_buffer(&[1.0, 0.5, 0.0, 0.0]);
+    let full: Vec<(i64, f64)> = index
+        .search(query.clone(), 3)
+        .unwrap()
+        .into_iter()
+        .filter(|r| r.id != 3)
+        .map(|r| (r.id, r.score))
+        .collect();
+    let loaded: Vec<(i64, f64)> = partial.search(query, 2).unwrap().into_iter().map(|r| (r.id, r.score)).collect();
+    assert_eq!(full, loaded);
+}
+
+/// 确定性的伪随机向量 (线性同余)，分量在 [-1, 1) 内
+fn pseudo_random_vectors(count: usize, dim: usize, seed: u64) -> Vec<Vec<f32>> {
+    let mut state = seed;
+    (0..count)
+        .map(|_| {
+            (0..dim)
+                .map(|_| {
+                    state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                    ((state >> 40) as f32 / (1u64 << 24) as f32) * 2.0 - 1.0
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn inspect_hnsw_neighbors_returns_nearest_other_nodes() {
+    let dim = 6;
+    let vectors = pseudo_random_vectors(60, dim, 3);
+    let index = VexusIndex::new(dim as u32, 60, None, None).unwrap();
+    index.add_batch((0..60).collect(), to_buffer(&vectors.concat())).unwrap();
+    let distance = |a: &[f32], b: &[f32]| -> f32 { a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum() };
+
+    for (level, expected) in [(0, 32), (1, 16)] {
+        let neighbors = index.inspect_hnsw_neighbors(7, level).unwrap();
+        assert_eq!(neighbors.len(), expected);
+        assert!(!neighbors.contains(&7));
+        // 返回的邻居都不比任何未返回的节点远
+        let farthest = neighbors.iter().map(|id| distance(&vectors[7], &vectors[*id as usize])).fold(0.0, f32::max);
+        for id in (0..60).filter(|id| *id != 7 && !neighbors.contains(id)) {
+            assert!(distance(&vectors[7], &vectors[id as usize]) >= farthest - 1e-5, "node {} is closer", id);
+        }
+    }
+
+    let small = index_with_ids(4, &[1, 2, 3]);
+    assert_eq!(small.inspect_hnsw_neighbors(1, 0).unwrap().len(), 2);
+    assert!(small.inspect_hnsw_neighbors(9, 0).is_err());
+}
+
+#[test]
+fn load_from_buffer_reads_i8_storage_without_state() {
+    let index = VexusIndex::new(4, 16, Some("i8".to_string()), None).unwrap();
+    index.add(1, to_buffer(&[1.0, -1.0, 0.0, 0.0])).unwrap();
+    let path = TempPath::new("i8_buffer.usearch");
+    index.save(path.as_string(), None).unwrap();
+
+    // save 写出的索引文件不带状态，量化类型从索引头读出
+    let bytes = std::fs::read(&path.0).unwrap();
+    let loaded = VexusIndex::load_from_buffer(bytes.into(), 4).unwrap();
+    assert!(loaded.quantization == ScalarKind::I8);
+    assert_eq!(from_buffer(&loaded.get_vector(1).unwrap().unwrap()), vec![1.0, -1.0, 0.0, 0.0]);
+
+    let f32_index = index_with_ids(4, &[1, 2]);
+    let f32_path = TempPath::new("f32_buffer.usearch");
+    f32_index.save(f32_path.as_string(), None).unwrap();
+    let loaded = VexusIndex::load_from_buffer(std::fs::read(&f32_path.0).unwrap().into(), 4).unwrap();
+    assert!(loaded.quantization == ScalarKind::F32);
+    assert_eq!(live_ids(&loaded), vec![1, 2]);
+}
+
+#[test]
+fn to_buffer_round_trips_i8_scales() {
+    let index = VexusIndex::new(4, 16, Some("i8".to_string()), None).unwrap();
+    let vectors = [(1, [100.0f32, -50.0, 25.0, 0.0]), (2, [0.5, 0.25, -0.125, 0.0])];
+    for (id, vector) in vectors {
+        index.add(id, to_buffer(&vector)).unwrap();
+    }
+
+    let loaded = VexusIndex::load_from_buffer(index.to_buffer().unwrap(), 4).unwrap();
+    assert!(loaded.quantization == ScalarKind::I8);
+    assert_eq!(live_ids(&loaded), vec![1, 2]);
+    for (id, _) in vectors {
+        let restored = from_buffer(&loaded.get_vector(id).unwrap().unwrap());
+        assert_eq!(restored, from_buffer(&index.get_vector(id).unwrap().unwrap()), "{}", id);
+    }
+    let query = to_buffer(&[1.0, 0.5, 0.0, 0.0]);
+    let scores = |index: &VexusIndex| -> Vec<(i64, f64)> {
+        index.search(query.clone(), 2).unwrap().into_iter().map(|r| (r.id, r.score)).collect()
+    };
+    assert_eq!(scores(&loaded), scores(&index));
+}
+
+#[test]
+fn recall_at_k_against_brute_force() {
+    let dim = 8;
+    let k = 5;
+    let vectors = pseudo_random_vectors(300, dim, 1);
+    let index = VexusIndex::new(dim as u32, 300, None, None).unwrap();
+    index.add_batch((0..300).collect(), to_buffer(&vectors.concat())).unwrap();
+
+    let queries = pseudo_random_vectors(20, dim, 2);
+    let ground_truth: Vec<Vec<i64>> = queries
+        .iter()
+        .map(|query| {
+            let mut by_distance: Vec<(f32, i64)> = vectors
+                .iter()
+                .enumerate()
+                .map(|(id, v)| (v.iter().zip(query).map(|(a, b)| (a - b) * (a - b)).sum(), id as i64))
+                .collect();
+            by_distance.sort_by(|a, b| a.0.total_cmp(&b.0));
+            by_distance.iter().take(k).map(|(_, id)| *id).collect()
+        })
+        .collect();
+
+    let recall = index.recall_at_k(ground_truth.clone(), to_buffer(&queries.concat()), k as u32).unwrap();
+    assert!(recall >= 0.9, "recall {}", recall);
+
+    // 与 ground truth 完全无关的 ID 召回率为 0；空列表的查询不参与平均
+    let unrelated: Vec<Vec<i64>> = queries.iter().map(|_| vec![1000, 1001, 1002, 1003, 1004]).collect();
+    assert_eq!(index.recall_at_k(unrelated, to_buffer(&queries.concat()), k as u32).unwrap(), 0.0);
+    let mut partial = ground_truth;
+    for truth in partial.iter_mut().skip(1) {
+        truth.clear();
+    }
+    let single = index.recall_at_k(partial, to_buffer(&queries.concat()), k as u32).unwrap();
+    assert!(single >= 0.8, "recall {}", single);
+}
+
+#[test]
+fn recall_at_k_rejects_bad_arguments() {
+    let index = index_with_ids(4, &[1, 2]);
+    let query = to_buffer(&id_vector(1, 4));
+    assert!(index.recall_at_k(vec![vec![1]], query.clone(), 0).is_err());
+    assert!(index.recall_at_k(Vec::new(), to_buffer(&[]), 1).is_err());
+    assert!(index.recall_at_k(vec![vec![1], vec![2]], query.clone(), 1).is_err());
+    assert!(index.recall_at_k(vec![vec![]], query, 1).is_err());
+}
+
+#[test]
+fn reset_clears_vectors_and_keeps_configuration() {
+    let index = index_with_metric(4, "cosine");
+    index.add_batch(vec![1, 2, 3], to_buffer(&[id_vector(1, 4), id_vector(2, 4), id_vector(3, 4)].concat())).unwrap();
+
+    index.reset().unwrap();
+    assert_eq!(index.stats().unwrap().total_vectors, 0);
+    assert_eq!(index.batch_contains(vec![1, 2, 3]).unwrap(), vec![false, false, false]);
+    assert!(live_ids(&index).is_empty());
+    assert_eq!(index.info().unwrap().metric, "cosine");
+
+    index.add(2, to_buffer(&id_vector(2, 4))).unwrap();
+    assert_eq!(live_ids(&index), vec![2]);
+    let results = index.search(to_buffer(&id_vector(2, 4)), 1).unwrap();
+    assert_eq!(results[0].id, 2);
+    assert!((results[0].score - 1.0).abs() < 1e-5, "{}", results[0].score);
+}
+
+#[test]
+fn search_excluding_tags_skips_excluded_top_results() {
+    let index = index_with_ids(4, &(1..=20).collect::<Vec<_>>());
+    let query = to_buffer(&id_vector(1, 4));
+    // 排除的正好是最近的 5 个，结果仍应凑满 k 个
+    let results = index.search_excluding_tags(query.clone(), 5, vec![1, 2, 3, 4, 5]).unwrap();
+    let ids: Vec<i64> = results.iter().map(|r| r.id).collect();
+    assert_eq!(ids, vec![6, 7, 8, 9, 10]);
+
+    let results = index.search_excluding_tags(query, 18, (1..=5).collect()).unwrap();
+    assert_eq!(results.len(), 15);
+    assert!(results.iter().all(|r| r.id > 5), "{:?}", results.iter().map(|r| r.id).collect::<Vec<_>>());
+}
+
+#[test]
+fn prune_expired_removes_only_expired_entries() {
+    let index = index_with_ids(4, &[1]);
+    index.add_with_expiry(2, to_buffer(&id_vector(2, 4)), 0).unwrap();
+    index.add_with_expiry(3, to_buffer(&id_vector(3, 4)), 3600).unwrap();
+
+    assert_eq!(index.prune_expired().unwrap(), 1);
+    assert_eq!(live_ids(&index), vec![1, 3]);
+    assert_eq!(index.prune_expired().unwrap(), 0);
+}
+
+/// 等待后台清理线程退出：线程持有一份 index 的 Arc，退出后引用计数回到 expected
+fn wait_for_strong_count(arc: &Arc<RwLock<Index>>, expected: usize) -> bool {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while Arc::strong_count(arc) != expected {
+        if Instant::now() > deadline {
+            return false;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    true
+}
+
+#[test]
+fn prune_interval_zero_stops_the_worker_and_drop_does_not_block() {
+    let index = index_with_ids(4, &[1]);
+    let shared = index.index.clone();
+    index.set_prune_interval(3600).unwrap();
+    assert_eq!(Arc::strong_count(&shared), 3);
+    index.set_prune_interval(0).unwrap();
+    assert!(wait_for_strong_count(&shared, 2), "worker still running");
+
+    // 间隔很长的后台线程也应在索引被回收时立即退出，drop 不等待下一次清理
+    index.set_prune_interval(3600).unwrap();
+    let started = Instant::now();
+    drop(index);
+    assert!(started.elapsed() < Duration::from_secs(1));
+    assert!(wait_for_strong_count(&shared, 1), "worker outlived the index");
+}
+
+/// 建一个只有 tags 表的 SQLite 数据库，vector 列为 f32 小端字节
+fn tags_db(path: &TempPath, rows: &[(i64, Vec<f32>)]) -> Connection {
+    let conn = Connection::open(&path.0).unwrap();
+    conn.execute("CREATE TABLE tags (id INTEGER PRIMARY KEY, vector BLOB)", []).unwrap();
+    for (id, vector) in rows {
+        let bytes: Vec<u8> = vector.iter().flat_map(|v| v.to_le_bytes()).collect();
+        conn.execute("INSERT INTO tags (id, vector) VALUES (?1, ?2)", rusqlite::params![id, bytes]).unwrap();
+    }
+    conn
+}
+
+#[test]
+fn pre_cancelled_recovery_leaves_the_index_unchanged() {
+    let path = TempPath::new("cancel.db");
+    drop(tags_db(&path, &[(10, id_vector(10, 4)), (11, id_vector(11, 4))]));
+    let index = index_with_ids(4, &[1, 2]);
+
+    let token = CancelToken::new();
+    token.cancel();
+    let stats = index
+        .recover_task(path.as_string(), "tags".to_string(), None, Some(token.cancelled.clone()), None)
+        .run()
+        .unwrap();
+    assert!(stats.cancelled);
+    assert_eq!(stats.recovered, 0);
+    assert_eq!(live_ids(&index), vec![1, 2]);
+    assert_eq!(index.stats().unwrap().total_vectors, 2);
+
+    let stats = index.recover_task(path.as_string(), "tags".to_string(), None, None, None).run().unwrap();
+    assert!(!stats.cancelled);
+    assert_eq!(live_ids(&index), vec![1, 2, 10, 11]);
+}
+
+fn journal_mode(conn: &Connection) -> String {
+    conn.query_row("PRAGMA journal_mode", [], |row| row.get(0)).unwrap()
+}
+
+#[test]
+fn recovery_reports_db_busy_while_another_connection_writes() {
+    let path = TempPath::new("busy.db");
+    let writer = tags_db(&path, &[(10, id_vector(10, 4))]);
+    writer.execute_batch("BEGIN EXCLUSIVE").unwrap();
+
+    let index = index_with_ids(4, &[1]);
+    let options = RecoverOptions { connection_timeout_ms: Some(50), wal: None };
+    let err = index
+        .recover_task(path.as_string(), "tags".to_string(), None, None, Some(options))
+        .recover()
+        .err()
+        .expect("recovery should fail while the database is locked");
+    assert!(err.status == VexusErrorCode::DbBusy, "{}", err.reason);
+    assert_eq!(live_ids(&index), vec![1]);
+
+    writer.execute_batch("COMMIT").unwrap();
+    let stats = index.recover_task(path.as_string(), "tags".to_string(), None, None, None).run().unwrap();
+    assert_eq!(stats.recovered, 1);
+}
+
+#[test]
+fn wal_option_keeps_the_journal_mode() {
+    let wal_options = || Some(RecoverOptions { connection_timeout_ms: None, wal: Some(true) });
+
+    let path = TempPath::new("rollback.db");
+    let conn = tags_db(&path, &[(10, id_vector(10, 4))]);
+    let index = index_with_ids(4, &[1]);
+    index.recover_task(path.as_string(), "tags".to_string(), None, None, wal_options()).run().unwrap();
+    assert_eq!(journal_mode(&conn), "delete");
+    assert_eq!(live_ids(&index), vec![1, 10]);
+
+    // 已经是 WAL 模式的数据库：其他连接提交的行在恢复时可见
+    let path = TempPath::new("wal.db");
+    let conn = tags_db(&path, &[]);
+    conn.pragma_update(None, "journal_mode", "WAL").unwrap();
+    conn.execute("INSERT INTO tags (id, vector) VALUES (11, ?1)", [to_buffer(&id_vector(11, 4)).to_vec()]).unwrap();
+    index.recover_task(path.as_string(), "tags".to_string(), None, None, wal_options()).run().unwrap();
+    assert_eq!(journal_mode(&conn), "wal");
+    assert_eq!(live_ids(&index), vec![1, 10, 11]);
+}
+
+#[test]
+fn compute_centroid_is_the_mean_vector() {
+    let index = VexusIndex::new(3, 16, None, None).unwrap();
+    assert!(index.compute_centroid().is_err());
+
+    let vectors = [[1.0f32, 0.0, 2.0], [3.0, 4.0, 0.0], [2.0, -1.0, 1.0]];
+    index.add_batch(vec![1, 2, 3], to_buffer(&vectors.concat())).unwrap();
+    assert_eq!(from_buffer(&index.compute_centroid().unwrap()), vec![2.0, 1.0, 1.0]);
+}
+
+#[test]
+fn batch_contains_keeps_input_order_and_length() {
+    let index = index_with_ids(4, &[1, 3, 5]);
+    index.remove(3).unwrap();
+    assert_eq!(
+        index.batch_contains(vec![5, 2, 1, 5, 3, -1, 1]).unwrap(),
+        vec![true, false, true, true, false, false, true]
+    );
+    assert!(index.batch_contains(Vec::new()).unwrap().is_empty());
+}
+
+#[test]
+fn info_reports_configuration_and_follows_set_metric() {
+    let index = VexusIndex::new(4, 16, Some("i8".to_string()), None).unwrap();
+    let info = index.info().unwrap();
+    assert_eq!((info.dimensions, info.metric.as_str(), info.quantization.as_str()), (4, "l2sq", "i8"));
+    assert_eq!(info.total_vectors, 0);
+    assert!(info.capacity >= 16);
+
+    index.add_batch(vec![1, 2], to_buffer(&[id_vector(1, 4), id_vector(2, 4)].concat())).unwrap();
+    index.set_metric("ip".to_string()).unwrap();
+    let info = index.info().unwrap();
+    assert_eq!((info.dimensions, info.metric.as_str(), info.quantization.as_str()), (4, "ip", "i8"));
+    assert_eq!(info.total_vectors, 2);
+}
+
+#[test]
+fn seed_is_accepted_and_round_trips_through_config() {
+    let index = VexusIndex::new(4, 16, None, Some(42)).unwrap();
+    let config = index.config().unwrap();
+    assert_eq!(config.seed, Some(42));
+    assert_eq!((config.dimensions, config.metric.as_str(), config.quantization.as_str()), (4, "l2sq", "f32"));
+
+    let json = serde_json::to_string(&config).unwrap();
+    let rebuilt = VexusIndex::from_config_json(json).unwrap().config().unwrap();
+    // capacity 是实际预留的容量，按 usearch 的分配粒度向上取整，不参与比较
+    assert_eq!(VexusIndexConfig { capacity: config.capacity, ..rebuilt }, config);
+
+    assert_eq!(VexusIndex::new(4, 16, None, None).unwrap().config().unwrap().seed, None);
+}